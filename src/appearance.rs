@@ -0,0 +1,171 @@
+// appearance.rs — 系统浅色 / 深色模式模块
+// 读取系统配色方案（手动设置 > freedesktop portal > gsettings > macOS / Windows 系统设置），
+// 按配色方案选择 theme_light / theme_dark 和 query_light / query_dark
+
+use crate::config::{AppConfig, StringOrVec};
+use crate::rotation;
+use crate::screen::command_stdout;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 系统配色方案
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "light" => Some(ColorScheme::Light),
+            "dark" => Some(ColorScheme::Dark),
+            _ => None,
+        }
+    }
+}
+
+/// `wallow mode` 的参数：固定为浅色 / 深色，或恢复跟随系统
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SchemeChoice {
+    Light,
+    Dark,
+    Auto,
+}
+
+/// 手动设置的配色方案保存在 state_dir/color-scheme
+fn override_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("color-scheme")
+}
+
+/// 手动固定配色方案，None 表示恢复跟随系统
+pub fn set_override(state_dir: &Path, scheme: Option<ColorScheme>) -> std::io::Result<()> {
+    match scheme {
+        Some(scheme) => fs::write(override_path(state_dir), scheme.as_str()),
+        None => match fs::remove_file(override_path(state_dir)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// 当前配色方案及其来源（manual / portal / gsettings / macos / windows），都无法获取时返回 None
+pub fn detect(state_dir: &Path) -> Option<(ColorScheme, &'static str)> {
+    if let Some(scheme) = fs::read_to_string(override_path(state_dir)).ok().and_then(|s| ColorScheme::parse(&s)) {
+        return Some((scheme, "manual"));
+    }
+    detect_system()
+}
+
+fn detect_system() -> Option<(ColorScheme, &'static str)> {
+    if cfg!(target_os = "macos") {
+        // 浅色模式下 AppleInterfaceStyle 不存在，命令失败
+        let dark = command_stdout("defaults", &["read", "-g", "AppleInterfaceStyle"]).is_some_and(|s| s.contains("Dark"));
+        return Some((if dark { ColorScheme::Dark } else { ColorScheme::Light }, "macos"));
+    }
+    if cfg!(target_os = "windows") {
+        let out = command_stdout(
+            "reg",
+            &[
+                "query",
+                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+                "/v",
+                "AppsUseLightTheme",
+            ],
+        )?;
+        let dark = out.contains("0x0");
+        return Some((if dark { ColorScheme::Dark } else { ColorScheme::Light }, "windows"));
+    }
+    if let Some(scheme) = read_portal() {
+        return Some((scheme, "portal"));
+    }
+    // GNOME 的 color-scheme 为 'default' / 'prefer-dark' / 'prefer-light'，'default' 即浅色
+    let out = command_stdout("gsettings", &["get", "org.gnome.desktop.interface", "color-scheme"])?;
+    let scheme = if out.contains("dark") { ColorScheme::Dark } else { ColorScheme::Light };
+    Some((scheme, "gsettings"))
+}
+
+/// 通过 xdg-desktop-portal 读取 org.freedesktop.appearance color-scheme
+///
+/// 返回值 1 为深色、2 为浅色、0 为无偏好（视为未知）；
+/// 旧版 portal 没有 ReadOne，回复形如 "(<<uint32 1>>,)"，新版为 "(<uint32 1>,)"
+fn read_portal() -> Option<ColorScheme> {
+    let call = |method: &str| {
+        command_stdout(
+            "gdbus",
+            &[
+                "call",
+                "--session",
+                "--dest",
+                "org.freedesktop.portal.Desktop",
+                "--object-path",
+                "/org/freedesktop/portal/desktop",
+                "--method",
+                method,
+                "org.freedesktop.appearance",
+                "color-scheme",
+            ],
+        )
+    };
+    let out = call("org.freedesktop.portal.Settings.ReadOne").or_else(|| call("org.freedesktop.portal.Settings.Read"))?;
+    let value = out.split("uint32").nth(1)?.trim_start().chars().next()?;
+    match value {
+        '1' => Some(ColorScheme::Dark),
+        '2' => Some(ColorScheme::Light),
+        _ => None,
+    }
+}
+
+/// 是否配置了任何区分浅色 / 深色的选项，没有时无需检测配色方案
+pub fn is_configured(config: &AppConfig) -> bool {
+    config.theme_light.is_some()
+        || config.theme_dark.is_some()
+        || config.search_defaults.query_light.is_some()
+        || config.search_defaults.query_dark.is_some()
+}
+
+/// 当前配色方案，未配置浅色 / 深色选项时不检测
+pub fn current(config: &AppConfig) -> Option<ColorScheme> {
+    if !is_configured(config) {
+        return None;
+    }
+    detect(&config.state_dir).map(|(scheme, _)| scheme)
+}
+
+/// 按配色方案选择主题：配置了对应的 theme_light / theme_dark 时从中选择，否则从 theme 中选择
+pub fn pick_theme(config: &AppConfig, scheme: Option<ColorScheme>) -> Option<&str> {
+    pick(config, "theme", scheme, &config.theme_light, &config.theme_dark, &config.default_theme)
+}
+
+/// 按配色方案选择搜索关键词，规则与 pick_theme 相同
+pub fn pick_query(config: &AppConfig, scheme: Option<ColorScheme>) -> Option<&str> {
+    let search = &config.search_defaults;
+    pick(config, "query", scheme, &search.query_light, &search.query_dark, &search.query)
+}
+
+fn pick<'a>(
+    config: &AppConfig,
+    key: &str,
+    scheme: Option<ColorScheme>,
+    light: &'a Option<StringOrVec>,
+    dark: &'a Option<StringOrVec>,
+    fallback: &'a Option<StringOrVec>,
+) -> Option<&'a str> {
+    // 轮询游标按配色方案分开，浅色和深色各自轮换
+    let specific = match scheme {
+        Some(ColorScheme::Light) => light.as_ref().map(|list| (format!("{}_light", key), list)),
+        Some(ColorScheme::Dark) => dark.as_ref().map(|list| (format!("{}_dark", key), list)),
+        None => None,
+    };
+    match specific {
+        Some((key, list)) => rotation::pick(config, &key, list),
+        None => fallback.as_ref().and_then(|list| rotation::pick(config, key, list)),
+    }
+}
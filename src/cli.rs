@@ -4,6 +4,12 @@
 use clap::{Parser, Subcommand}; // Parser: 解析命令行参数的 trait; Subcommand: 定义子命令的 trait
 use clap_complete::Shell; // Shell 枚举：Bash, Zsh, Fish, Elvish, PowerShell
 
+use crate::appearance::SchemeChoice;
+use crate::config::{MonitorLayout, ScheduleBackend, WallpaperMode};
+use crate::gowall::Operation;
+use crate::palette::PaletteFormat;
+use crate::select::SelectionStrategy;
+
 /// 壁纸下载与主题转换工具
 ///
 /// 从 Wallhaven 或 Unsplash 下载壁纸，使用 gowall 应用配色主题，
@@ -31,7 +37,7 @@ pub enum Commands {
         #[arg(short, long)]
         query: Option<String>,
 
-        /// 壁纸分辨率
+        /// 壁纸分辨率，如 1920x1080；auto 表示不低于屏幕分辨率
         #[arg(short, long)]
         resolution: Option<String>,
 
@@ -54,9 +60,13 @@ pub enum Commands {
         /// 壁纸来源 (wallhaven / unsplash)，不指定则使用配置文件中的默认来源
         #[arg(long)]
         source: Option<String>,
+
+        /// 搜索结果选择策略 (first/random/highest_resolution/closest_aspect/most_favorited/unseen)，不指定则使用配置文件中的 selection
+        #[arg(long, value_enum)]
+        select: Option<SelectionStrategy>,
     },
 
-    /// 使用 gowall 对壁纸应用配色主题
+    /// 对壁纸应用配色主题（gowall 或内置引擎，由 [convert] backend 决定）
     ///
     /// 用法示例:
     ///   wallow convert image.jpg --theme catppuccin
@@ -74,12 +84,79 @@ pub enum Commands {
         output: Option<String>,
     },
 
-    /// 列出所有可用的 gowall 主题
+    /// 使用 gowall 编辑图片：反色、像素化、放大、移除背景、灰度、亮度等
+    ///
+    /// 多个 --op 按顺序串联执行。
+    ///
+    /// 用法示例:
+    ///   wallow edit image.jpg --op invert
+    ///   wallow edit image.jpg --op pixelate:15 --op brightness:0.8 -o out.png
+    ///   wallow edit image.jpg --extract 6
+    Edit {
+        /// 要编辑的图片路径
+        image: String,
+
+        /// 编辑操作 (invert / pixelate[:N] / upscale[:N] / remove_bg / grayscale / brightness:F / flip / mirror)
+        #[arg(long = "op", value_name = "OP")]
+        ops: Vec<Operation>,
+
+        /// 提取图片主色并打印，参数为颜色数量
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "6")]
+        extract: Option<u32>,
+
+        /// 输出路径（不指定则保存到第一个 converted_dirs，文件名加 wallow-edited- 前缀）
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// 从图片提取终端配色（16 色 + 前景 / 背景色）
+    ///
+    /// 用法示例:
+    ///   wallow palette image.jpg --format kitty
+    ///   wallow palette image.jpg -f pywal -o ~/.cache/wal/colors.json
+    Palette {
+        /// 图片路径
+        image: String,
+
+        /// 导出格式 (pywal/alacritty/kitty/wezterm/foot/ghostty)
+        #[arg(short, long, value_enum, default_value = "pywal")]
+        format: PaletteFormat,
+
+        /// 输出文件路径（不指定则打印到标准输出）
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// 列出当前转换引擎可用的所有主题
     ///
     /// 用法示例:
     ///   wallow themes
     Themes,
 
+    /// 查看或设置浅色 / 深色模式，run / set 按它选择 theme_light / theme_dark
+    ///
+    /// 默认跟随系统（xdg-desktop-portal / gsettings / macOS / Windows）；
+    /// 手动设置或 --watch 检测到切换时，用对应主题重新转换当前壁纸
+    ///
+    /// 用法示例:
+    ///   wallow mode
+    ///   wallow mode dark
+    ///   wallow mode auto
+    ///   wallow mode --watch
+    Mode {
+        /// light / dark 固定配色方案，auto 恢复跟随系统
+        #[arg(value_enum)]
+        scheme: Option<SchemeChoice>,
+
+        /// 持续监视配色方案，切换时更新当前壁纸
+        #[arg(short, long)]
+        watch: bool,
+
+        /// 监视时的检测间隔（秒）
+        #[arg(long, default_value_t = 10, requires = "watch")]
+        interval: u64,
+    },
+
     /// 生成 shell 补全脚本（支持 bash, zsh, fish, elvish, powershell）
     ///
     /// 用法示例：
@@ -90,20 +167,63 @@ pub enum Commands {
         shell: Shell,
     },
 
-    /// 定时任务：注册或更新 crontab，自动每天下载一张随机壁纸
+    /// 定时任务：注册或更新 crontab / systemd 定时器 / launchd agent，自动每天下载一张随机壁纸
     ///
     /// 用法示例:
     ///   wallow schedule              # 使用 config.toml 里的 cron 表达式重新注册
     ///   wallow schedule "0 8 * * *"  # 写入配置并注册定时任务
+    ///   wallow schedule --slots      # 查看今天各时段的开始时间
+    ///   wallow schedule --backend systemd  # 改用 systemd 用户定时器
+    ///   wallow schedule --backend launchd --print  # 打印 macOS launchd plist
+    ///   wallow schedule status       # 查看定时任务状态和接下来的触发时间
+    ///   wallow schedule remove       # 移除定时任务
     Schedule {
+        #[command(subcommand)]
+        action: Option<ScheduleAction>,
+
         /// Cron 表达式（不传则使用 config.toml 里的已保存值）
         cron: Option<String>,
+
+        /// 执行一次定时任务（由 crontab 调用）：按当前时段更换壁纸
+        #[arg(long, conflicts_with_all = ["cron", "slots"])]
+        run: bool,
+
+        /// 列出 [schedule.slots] 中各时段今天的开始时间和当前所在时段
+        #[arg(long, conflicts_with = "cron")]
+        slots: bool,
+
+        /// 定时任务后端 (cron / systemd / launchd)，不指定则使用配置文件中的 backend
+        #[arg(long, value_enum, conflicts_with_all = ["run", "slots"])]
+        backend: Option<ScheduleBackend>,
+
+        /// 只打印 crontab 条目、systemd 单元文件或 launchd plist，不写入
+        #[arg(long, conflicts_with_all = ["run", "slots", "remove"])]
+        print: bool,
+
+        /// 移除已注册的定时任务（crontab 条目、systemd 定时器和 launchd agent）
+        #[arg(long, conflicts_with_all = ["cron", "run", "slots"])]
+        remove: bool,
+    },
+
+    /// 动态壁纸：按一天中的时间切换的一组图片
+    ///
+    /// 用法示例:
+    ///   wallow dynamic import ~/Downloads/Mojave.heic
+    ///   wallow dynamic import /usr/share/backgrounds/gnome/adwaita-timed.xml --name adwaita
+    ///   wallow dynamic apply mojave
+    ///   wallow dynamic export ~/Pictures/day -o day.xml
+    Dynamic {
+        #[command(subcommand)]
+        action: DynamicAction,
     },
 
     /// 一键更换：下载、转换并设置为系统壁纸
     ///
     /// 用法示例:
     ///   wallow set --query nature --theme catppuccin
+    ///   wallow set --monitor DP-1
+    ///   wallow set --layout per_monitor
+    ///   wallow set --mode center
     Set {
         /// 搜索关键词
         #[arg(short, long)]
@@ -116,6 +236,21 @@ pub enum Commands {
         /// 壁纸来源 (wallhaven / unsplash)，不指定则使用配置文件中的默认来源
         #[arg(long)]
         source: Option<String>,
+
+        /// 搜索结果选择策略 (first/random/highest_resolution/closest_aspect/most_favorited/unseen)，不指定则使用配置文件中的 selection
+        #[arg(long, value_enum)]
+        select: Option<SelectionStrategy>,
+
+        /// 只设置指定的显示器，按该显示器的分辨率搜索
+        #[arg(long, conflicts_with = "layout")]
+        monitor: Option<String>,
+
+        /// 多显示器布局 (same/per_monitor/span)，不指定则使用配置文件中的 [setter] layout
+        #[arg(long, value_enum)]
+        layout: Option<MonitorLayout>,
+        /// 缩放模式 (crop/fit/stretch/center/tile/span)，不指定则使用配置文件中的 [setter] mode
+        #[arg(long, value_enum)]
+        mode: Option<WallpaperMode>,
     },
 
     /// 一键完成：下载壁纸 + 应用主题
@@ -132,7 +267,7 @@ pub enum Commands {
         #[arg(short, long)]
         theme: Option<String>,
 
-        /// 壁纸分辨率
+        /// 壁纸分辨率，如 1920x1080；auto 表示不低于屏幕分辨率
         #[arg(short, long)]
         resolution: Option<String>,
 
@@ -151,6 +286,10 @@ pub enum Commands {
         /// 壁纸来源 (wallhaven / unsplash)，不指定则使用配置文件中的默认来源
         #[arg(long)]
         source: Option<String>,
+
+        /// 搜索结果选择策略 (first/random/highest_resolution/closest_aspect/most_favorited/unseen)，不指定则使用配置文件中的 selection
+        #[arg(long, value_enum)]
+        select: Option<SelectionStrategy>,
     },
 
     /// 配置管理操作
@@ -169,22 +308,70 @@ pub enum Commands {
     /// 用法示例:
     ///   wallow list
     ///   wallow list --fzf
+    ///   wallow list --fzf --mode fit
     List {
         /// 使用 fzf 进行交互式选择与预览
         #[arg(short = 'F', long)]
         fzf: bool,
+
+        /// 设置选中壁纸时的缩放模式 (crop/fit/stretch/center/tile/span)
+        #[arg(long, value_enum, requires = "fzf")]
+        mode: Option<WallpaperMode>,
     },
 
     /// 将本地指定的图片设置为系统壁纸
     ///
     /// 用法示例:
     ///   wallow apply image.jpg
+    ///   wallow apply image.jpg --monitor HDMI-A-1
+    ///   wallow apply panorama.jpg --layout span
+    ///   wallow apply image.jpg --mode fit
     Apply {
         /// 图片的本地路径
         image: String,
+
+        /// 只设置指定的显示器
+        #[arg(long, conflicts_with = "layout")]
+        monitor: Option<String>,
+
+        /// 多显示器布局：same 所有显示器相同，span 拼接铺满所有显示器
+        #[arg(long, value_enum)]
+        layout: Option<MonitorLayout>,
+        /// 缩放模式 (crop/fit/stretch/center/tile/span)，不指定则使用配置文件中的 [setter] mode
+        #[arg(long, value_enum)]
+        mode: Option<WallpaperMode>,
+    },
+
+    /// 显示当前壁纸（set / apply 设置的最后一张）
+    ///
+    /// 固定路径 ~/.local/state/wallow/current 始终链接到当前壁纸，
+    /// 详细信息保存在同目录的 current.json
+    ///
+    /// 用法示例:
+    ///   wallow current
+    ///   wallow current --path
+    ///   wallow current --json
+    Current {
+        /// 以 JSON 格式输出
+        #[arg(long, conflicts_with = "path")]
+        json: bool,
+        /// 只输出图片路径
+        #[arg(long)]
+        path: bool,
     },
 
-    /// 清理所有带有 wallow- 前缀的下载文件
+    /// 切换到历史中的上一张壁纸
+    ///
+    /// set / apply 设置过的壁纸按顺序记录在 ~/.local/state/wallow/history.json
+    Prev,
+
+    /// 切换到历史中的下一张壁纸（prev 之后使用）
+    Next,
+
+    /// 撤销当前壁纸：从历史中移除并恢复上一张
+    Undo,
+
+    /// 清理所有由 wallow 管理的文件（带 wallow- 前缀或记录在壁纸库索引中）
     ///
     /// 用法示例:
     ///   wallow clean
@@ -198,7 +385,7 @@ pub enum Commands {
     ///   wallow upgrade
     Upgrade,
 
-    /// 卸载 wallow（删除二进制文件、配置目录及壁纸缓存）
+    /// 卸载 wallow（移除定时任务，删除二进制文件、配置目录、状态目录及壁纸缓存）
     ///
     /// 用法示例:
     ///   wallow uninstall
@@ -210,6 +397,55 @@ pub enum Commands {
     },
 }
 
+/// 定时任务管理操作
+#[derive(Subcommand)]
+pub enum ScheduleAction {
+    /// 查看后端、cron 表达式、已注册的任务、接下来 5 次触发时间、上一次执行结果和最近的日志
+    #[command(alias = "list")]
+    Status,
+    /// 移除已注册的定时任务（同 --remove）
+    Remove,
+}
+
+/// 动态壁纸操作
+#[derive(Subcommand)]
+pub enum DynamicAction {
+    /// 导入 GNOME 幻灯片 XML 或 macOS 动态 HEIC
+    Import {
+        /// .xml 或 .heic 文件
+        file: String,
+        /// 保存的名称，默认取文件名
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+    /// 列出已导入的动态壁纸和各帧的开始时间
+    List,
+    /// 启用动态壁纸并立即设置当前时间对应的图片，之后由 schedule --run 切换
+    Apply {
+        /// 动态壁纸名称
+        name: String,
+        /// 保持运行，到点自动切换图片
+        #[arg(short, long)]
+        watch: bool,
+    },
+    /// 停用动态壁纸
+    Off,
+    /// 把文件夹中的图片导出为 GNOME 幻灯片 XML，按文件名顺序平均分配到一天中
+    Export {
+        /// 图片文件夹
+        folder: String,
+        /// 输出文件，默认为 <文件夹>/<文件夹名>.xml
+        #[arg(short, long)]
+        output: Option<String>,
+        /// 第一张图片的开始时间 (HH:MM)
+        #[arg(long, default_value = "00:00")]
+        start: String,
+        /// 相邻图片之间的过渡时长（秒）
+        #[arg(long, default_value_t = 5.0)]
+        transition: f64,
+    },
+}
+
 /// 配置管理操作
 #[derive(Subcommand)]
 pub enum ConfigAction {
@@ -219,9 +455,9 @@ pub enum ConfigAction {
     Schema,
     /// 以 TOML 格式打印当前完整配置内容
     Dump,
-    /// 设置配置项的值项 (支持: query, resolution, sorting)
+    /// 设置配置项的值项 (支持: query, resolution, sorting, selection, setter)
     Set {
-        /// 要设置的键 (query, res, sorting)
+        /// 要设置的键 (query, res, sorting, selection, setter, mode)
         key: String,
         /// 要设置的值
         value: String,
@@ -3,42 +3,130 @@
 
 use schemars::JsonSchema; // 引入用于生成 JSON Schema 的 trait
 use serde::{Deserialize, Serialize}; // 引入序列化与反序列化 trait
+use std::collections::BTreeMap; // 有序映射，保证序列化顺序稳定
 use std::env; // 环境变量模块
 use std::fs; // 文件系统模块
 use std::path::{Path, PathBuf}; // 路径处理类型
 use shellexpand::tilde; // 用于展开 ~ 和环境变量
 use rand::seq::SliceRandom; // 用于随机选择
+use crate::select::SelectionStrategy; // 搜索结果选择策略
+use crate::palette::PaletteFormat; // 终端配色导出格式
+use crate::pipeline::StepConfig; // 后处理流水线步骤
+use crate::screen::Monitor; // 显示器信息
 
 /// 支持字符串或字符串数组的类型
 /// TOML 中可配置为 query = "nature" 或 query = ["nature", "anime", "landscape"]
+/// 数组元素也可以带权重: query = [{ value = "nature", weight = 3 }, "city"]
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum StringOrVec {
     Single(String),
-    Multiple(Vec<String>),
+    Multiple(Vec<WeightedString>),
+}
+
+/// 数组中的单个候选值，可以是普通字符串或带权重的表
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum WeightedString {
+    Plain(String),
+    Weighted {
+        value: String,
+        /// 权重，默认 1；为 0 时永远不会被选中
+        #[serde(default = "default_weight")]
+        weight: u32,
+    },
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+impl WeightedString {
+    pub fn value(&self) -> &str {
+        match self {
+            WeightedString::Plain(s) => s.as_str(),
+            WeightedString::Weighted { value, .. } => value.as_str(),
+        }
+    }
+
+    pub fn weight(&self) -> u32 {
+        match self {
+            WeightedString::Plain(_) => 1,
+            WeightedString::Weighted { weight, .. } => *weight,
+        }
+    }
 }
 
 impl StringOrVec {
-    /// 随机选择一个字符串
+    /// 按权重随机选择一个字符串
     /// - Single: 直接返回该字符串
-    /// - Multiple: 随机选择其中一个
-    pub fn pick_random(&self) -> &str {
-        match self {
+    /// - Multiple: 按 weight 加权随机选择其中一个
+    ///
+    /// 列表为空、全部权重为 0 或选中的值为空字符串时返回 None
+    pub fn pick_random(&self) -> Option<&str> {
+        let picked = match self {
+            StringOrVec::Single(s) => s.as_str(),
+            StringOrVec::Multiple(vec) => vec
+                .choose_weighted(&mut rand::thread_rng(), |item| item.weight())
+                .map(|item| item.value())
+                .ok()?,
+        };
+        Some(picked).filter(|s| !s.is_empty())
+    }
+
+    /// 按轮询游标选择一个字符串
+    ///
+    /// 使用平滑加权轮询（smooth weighted round-robin）展开一个周期：
+    /// 周期长度等于权重之和，每个值在周期内出现 weight 次且尽量均匀分散，
+    /// 例如 nature(3) + city(1) 的周期为 nature, nature, city, nature。
+    /// 与 pick_random 相同，没有可选的值时返回 None
+    pub fn pick_round_robin(&self, cursor: usize) -> Option<&str> {
+        let picked = match self {
             StringOrVec::Single(s) => s.as_str(),
             StringOrVec::Multiple(vec) => {
-                // 使用 thread_rng 进行随机选择
-                vec.choose(&mut rand::thread_rng())
-                    .map(|s| s.as_str())
-                    .unwrap_or("")
+                let total: i64 = vec.iter().map(|item| item.weight() as i64).sum();
+                if total == 0 {
+                    return None;
+                }
+                // 只需模拟到周期内的第 cursor % total 步
+                let steps = cursor % total as usize;
+                let mut current = vec![0i64; vec.len()];
+                let mut chosen = 0;
+                for _ in 0..=steps {
+                    for (i, item) in vec.iter().enumerate() {
+                        current[i] += item.weight() as i64;
+                    }
+                    chosen = (0..vec.len())
+                        .max_by(|a, b| current[*a].cmp(&current[*b]).then(b.cmp(a)))
+                        .unwrap_or(0);
+                    current[chosen] -= total;
+                }
+                vec[chosen].value()
             }
+        };
+        Some(picked).filter(|s| !s.is_empty())
+    }
+
+    /// 是否没有任何候选值
+    pub fn is_empty(&self) -> bool {
+        match self {
+            StringOrVec::Single(s) => s.is_empty(),
+            StringOrVec::Multiple(vec) => vec.is_empty(),
         }
     }
 
-    /// 转换为单个字符串（用于显示）
+    /// 转换为单个字符串（用于显示），带权重的值显示为 value(weight)
     pub fn to_display_string(&self) -> String {
         match self {
             StringOrVec::Single(s) => s.clone(),
-            StringOrVec::Multiple(vec) => vec.join(", "),
+            StringOrVec::Multiple(vec) => vec
+                .iter()
+                .map(|item| match item.weight() {
+                    1 => item.value().to_string(),
+                    w => format!("{}({})", item.value(), w),
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
         }
     }
 }
@@ -49,6 +137,17 @@ impl Default for StringOrVec {
     }
 }
 
+/// 多值配置项（query / source / theme）的选择策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationMode {
+    /// 每次按权重随机选择
+    #[default]
+    Random,
+    /// 按顺序轮询，游标保存在状态目录中，跨运行保持
+    RoundRobin,
+}
+
 /// 展开路径中的 ~ 和环境变量 ($HOME, $XDG_CONFIG_HOME 等)
 /// 支持格式: ~/path, $HOME/path, ${HOME}/path
 fn expand_path(path_str: &str) -> PathBuf {
@@ -57,6 +156,16 @@ fn expand_path(path_str: &str) -> PathBuf {
     PathBuf::from(expanded)
 }
 
+/// 展开路径，相对路径则相对于 $HOME
+pub fn resolve_home_path(path_str: &str) -> PathBuf {
+    let p = expand_path(path_str);
+    if p.is_absolute() {
+        p
+    } else {
+        PathBuf::from(env::var("HOME").unwrap_or_default()).join(p)
+    }
+}
+
 /// 映射 config.toml 文件内容的嵌套结构体
 #[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
 struct ConfigFile {
@@ -66,6 +175,19 @@ struct ConfigFile {
     source: SourceConfigs,
     #[serde(default)]
     schedule: ScheduleConfig,
+    #[serde(default)]
+    convert: ConvertConfig,
+    /// 壁纸设置后端
+    #[serde(default)]
+    setter: SetterConfig,
+    #[serde(default)]
+    palette: PaletteConfig,
+    /// 用户自定义主题，[themes.<name>] colors = ["#1e1e2e", ...]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    themes: BTreeMap<String, ThemeDef>,
+    /// run / set 下载后按顺序执行的后处理步骤，[[pipeline]] step = "..."
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pipeline: Vec<StepConfig>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
@@ -75,14 +197,34 @@ struct CommonConfig {
     /// 转换后壁纸的输出目录列表，支持多个目录
     /// 支持 ~、$HOME 等环境变量，相对路径则相对于 $HOME
     /// 不配置则默认为 wallpaper_dir/converted
+    /// 每项可以是路径字符串，也可以是带处理选项的表：
+    /// { path = "~/.config/wezterm/backgrounds", brightness = 0.6, blur = 4.0 }
     #[serde(default)]
-    converted_dirs: Vec<String>,
+    converted_dirs: Vec<ConvertedDirEntry>,
     /// 默认壁纸来源 (wallhaven / unsplash)，默认 wallhaven
+    /// 支持数组和权重，如 source = [{ value = "wallhaven", weight = 3 }, "unsplash"]
     #[serde(default = "default_source")]
-    source: String,
-    /// 默认主题，不配置则不自动转换
+    source: StringOrVec,
+    /// 默认主题，不配置则不自动转换；支持数组和权重
     #[serde(default)]
-    theme: Option<String>,
+    theme: Option<StringOrVec>,
+    /// 系统为浅色模式时使用的主题，优先于 theme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    theme_light: Option<StringOrVec>,
+    /// 系统为深色模式时使用的主题，优先于 theme
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    theme_dark: Option<StringOrVec>,
+    /// query / source / theme 为数组时的选择策略 (random / round_robin)，默认 random
+    #[serde(default)]
+    rotation: RotationMode,
+    /// 下载壁纸的文件名模板（不含扩展名），默认 "wallow-{source}-{id}"
+    /// 占位符: {source} {id} {theme} {resolution} {date} {query} {hash}
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filename_template: Option<String>,
+    /// 转换后壁纸的文件名模板，默认 "wallow-{theme}-{name}"，{name} 为原文件名去掉 wallow- 前缀
+    /// converted_dirs 中单独设置的 filename 优先
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    converted_template: Option<String>,
     /// 默认搜索参数
     #[serde(default)]
     search: SearchDefaults,
@@ -92,33 +234,51 @@ struct CommonConfig {
 pub struct SearchDefaults {
     /// 默认搜索关键词，支持字符串或数组
     /// 示例: query = "nature" 或 query = ["nature", "anime", "landscape"]
-    /// 如果是数组，每次搜索时会随机选择一个
+    /// 如果是数组，每次搜索时按 common.rotation 策略选择一个
     #[serde(default)]
     pub query: Option<StringOrVec>,
+    /// 浅色模式下的搜索关键词，优先于 query
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_light: Option<StringOrVec>,
+    /// 深色模式下的搜索关键词，优先于 query
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query_dark: Option<StringOrVec>,
+    /// 搜索分辨率，如 "1920x1080"；默认 "auto"，即使用检测到的屏幕分辨率作为最低分辨率
     #[serde(default = "default_resolution")]
     pub resolution: String,
+    /// 屏幕分辨率，如 "2560x1600"，配置后不再自动检测（用于 resolution = "auto" 和 closest_aspect）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen: Option<String>,
     #[serde(default = "default_categories")]
     pub categories: String,
     #[serde(default = "default_purity")]
     pub purity: String,
     #[serde(default = "default_sorting")]
     pub sorting: String,
+    /// 搜索结果选择策略 (first / random / highest_resolution / closest_aspect / most_favorited / unseen)
+    /// 默认 first，即直接取壁纸源返回的第一条
+    #[serde(default)]
+    pub selection: SelectionStrategy,
 }
 
 impl Default for SearchDefaults {
     fn default() -> Self {
         Self {
             query: None,
+            query_light: None,
+            query_dark: None,
             resolution: default_resolution(),
+            screen: None,
             categories: default_categories(),
             purity: default_purity(),
             sorting: default_sorting(),
+            selection: SelectionStrategy::default(),
         }
     }
 }
 
 fn default_resolution() -> String {
-    "3840x2160".to_string()
+    "auto".to_string()
 }
 fn default_categories() -> String {
     "111".to_string()
@@ -129,8 +289,8 @@ fn default_purity() -> String {
 fn default_sorting() -> String {
     "random".to_string()
 }
-fn default_source() -> String {
-    "wallhaven".to_string()
+fn default_source() -> StringOrVec {
+    StringOrVec::Single("wallhaven".to_string())
 }
 
 #[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
@@ -152,11 +312,378 @@ struct UnsplashConfig {
 }
 
 /// 定时任务配置
-#[derive(Debug, Deserialize, Serialize, Default, JsonSchema)]
+#[derive(Debug, Clone, Deserialize, Serialize, Default, JsonSchema)]
 pub struct ScheduleConfig {
     /// Cron 表达式，定义定时执行频率 (例: "0 8 * * *" 表示每天 8:00)
     #[serde(default)]
     pub cron: Option<String>,
+    /// 定时任务后端 (cron / systemd / launchd)，默认 cron
+    #[serde(default)]
+    pub backend: ScheduleBackend,
+    /// 所在地纬度（北纬为正），与 longitude 一起配置后按日出日落计算时段边界
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latitude: Option<f64>,
+    /// 所在地经度（东经为正）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub longitude: Option<f64>,
+    /// 为 true 时只在时段切换后的第一次触发时更换壁纸，适合配合频繁触发的 cron
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub on_slot_change: bool,
+    /// 为 true 时定时任务执行失败后发送桌面通知（notify-send / osascript）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub notify_on_failure: bool,
+    /// 按时间段使用不同的搜索关键词、主题或本地目录，[schedule.slots.<名称>]
+    /// 名称为 morning / day / evening / night 时可省略 start
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub slots: BTreeMap<String, TimeSlot>,
+}
+
+/// 定时任务后端
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleBackend {
+    /// 写入 crontab（默认）
+    #[default]
+    Cron,
+    /// 写入 ~/.config/systemd/user 下的 wallow.service 和 wallow.timer
+    Systemd,
+    /// 写入 ~/Library/LaunchAgents/com.wallow.schedule.plist (macOS)
+    Launchd,
+}
+
+impl ScheduleBackend {
+    /// 所有后端，切换后端时依次清理其他后端的旧任务
+    pub const ALL: [ScheduleBackend; 3] = [ScheduleBackend::Cron, ScheduleBackend::Systemd, ScheduleBackend::Launchd];
+
+    /// 配置文件和提示信息中使用的名称
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScheduleBackend::Cron => "cron",
+            ScheduleBackend::Systemd => "systemd",
+            ScheduleBackend::Launchd => "launchd",
+        }
+    }
+}
+
+/// 一个时间段，从 start 开始到下一个时段的 start 结束
+#[derive(Debug, Clone, Deserialize, Serialize, Default, JsonSchema)]
+pub struct TimeSlot {
+    /// 开始时间："07:30"、"sunrise"、"sunset"，可带偏移如 "sunrise+30m"、"sunset-1h"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    /// 该时段的搜索关键词，支持数组和权重
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<StringOrVec>,
+    /// 该时段的主题，支持数组和权重
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<StringOrVec>,
+    /// 从本地目录中随机选择图片，不再下载
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+}
+
+/// 写入某个输出目录时对图片做的处理，适合把终端背景压暗、模糊
+/// 所有选项都不设置时直接复制文件
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct OutputOptions {
+    /// 亮度系数 0–1，0.6 表示亮度降到 60%（也可写作 opacity）
+    #[serde(default, alias = "opacity", skip_serializing_if = "Option::is_none")]
+    pub brightness: Option<f64>,
+    /// 高斯模糊半径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blur: Option<f64>,
+    /// 目标最大亮度 0–1：图片高光部分（95 分位）亮于该值时整体压暗到该值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_luminance: Option<f64>,
+}
+
+impl OutputOptions {
+    /// 是否没有任何处理选项
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// converted_dirs 中的一项：路径字符串，或带独立主题、尺寸、格式和处理选项的表
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+enum ConvertedDirEntry {
+    Path(String),
+    Table {
+        path: String,
+        #[serde(flatten)]
+        variant: DirVariant,
+        #[serde(flatten)]
+        options: OutputOptions,
+    },
+}
+
+/// 某个输出目录独立的转换设置，未设置的项沿用命令行 / 默认值
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct DirVariant {
+    /// 该目录使用的主题，缺省时使用本次转换的主题
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// 输出尺寸："auto"（当前屏幕）或 "1920x1080"，缩放并居中裁剪填满；缺省时保持原尺寸
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+    /// 输出格式 png / jpg / webp，缺省时与原图相同
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// 文件名模板（不含扩展名），占位符同 converted_template，缺省时使用 converted_template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    /// 只保留最新的 N 个 wallow 文件，写入后删除更旧的；刚写入的文件总会保留，0 等同于 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep: Option<usize>,
+}
+
+/// 解析后的转换输出目录
+#[derive(Debug, Clone)]
+pub struct ConvertedDir {
+    /// 目录路径（已展开）
+    pub path: PathBuf,
+    /// 该目录独立的主题、尺寸、格式等设置
+    pub variant: DirVariant,
+    /// 写入该目录时的处理选项
+    pub options: OutputOptions,
+}
+
+impl ConvertedDir {
+    /// 是否有自己的主题、尺寸、格式、文件名、keep 或处理选项
+    pub fn has_settings(&self) -> bool {
+        !self.options.is_empty() || self.variant != DirVariant::default()
+    }
+
+    fn to_entry(&self) -> ConvertedDirEntry {
+        let path = self.path.to_string_lossy().to_string();
+        if !self.has_settings() {
+            ConvertedDirEntry::Path(path)
+        } else {
+            ConvertedDirEntry::Table {
+                path,
+                variant: self.variant.clone(),
+                options: self.options.clone(),
+            }
+        }
+    }
+}
+
+/// 主题转换引擎
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConvertBackend {
+    /// 调用外部 gowall 命令（默认）
+    #[default]
+    Gowall,
+    /// 使用内置的 Rust 转换引擎，无需安装 gowall
+    Native,
+}
+
+/// 壁纸设置后端
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum SetterBackend {
+    /// 根据 XDG_CURRENT_DESKTOP / WAYLAND_DISPLAY 和已安装的工具自动选择（默认）
+    #[default]
+    Auto,
+    /// 使用 wallpaper 库（macOS、Windows、GNOME、KDE 等桌面环境）
+    System,
+    /// swww img
+    Swww,
+    /// hyprctl hyprpaper
+    Hyprpaper,
+    /// swaymsg output * bg，不在 Sway 中时启动 swaybg
+    Swaybg,
+    /// feh --bg-fill
+    Feh,
+    /// xwallpaper --zoom
+    Xwallpaper,
+    /// nitrogen --set-zoom-fill
+    Nitrogen,
+    /// 执行 [setter] command 中的自定义命令
+    Command,
+}
+
+/// 壁纸设置配置
+#[derive(Debug, Clone, Deserialize, Serialize, Default, JsonSchema)]
+pub struct SetterConfig {
+    /// 设置后端 (auto / system / swww / hyprpaper / swaybg / feh / xwallpaper / nitrogen / command)，默认 auto
+    /// 配置了 command 且 backend 为 auto 时使用自定义命令
+    #[serde(default)]
+    pub backend: SetterBackend,
+    /// 自定义设置命令，可用占位符 {path} {monitor} {theme} {mode}
+    /// 单条命令: command = ["swww", "img", "{path}"]
+    /// 多条命令按顺序执行: command = [["swww", "img", "{path}"], ["pkill", "-SIGUSR2", "waybar"]]
+    /// 只有第一条为每个显示器和每个过渡帧执行，其余命令每次换壁纸只执行一次
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<SetterCommand>,
+    /// 自定义命令自带过渡动画（如 swww img --transition-type grow）时设为 true，不再生成交叉淡入淡出，默认 false
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub command_animates: bool,
+    /// 多显示器布局 (same / per_monitor / span)，默认 same
+    #[serde(default)]
+    pub layout: MonitorLayout,
+    /// 缩放模式 (crop / fit / stretch / center / tile / span)，默认 crop
+    /// 后端不支持的模式会先按屏幕尺寸渲染图片再设置
+    #[serde(default)]
+    pub mode: WallpaperMode,
+    /// 手动列出的显示器，配置后不再自动检测
+    /// monitors = [{ name = "DP-1", width = 2560, height = 1440, x = 0, y = 0 }]
+    /// 开启缩放时 x / y 为逻辑坐标，再加上 scale = 2
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub monitors: Vec<Monitor>,
+    /// 换壁纸时的交叉淡入淡出，只用于自身没有过渡动画的后端
+    #[serde(default)]
+    pub transition: TransitionConfig,
+}
+
+/// 交叉淡入淡出：在旧图和新图之间生成几张混合帧，依次快速设置
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct TransitionConfig {
+    /// 过渡总时长（秒），0 表示关闭，默认 0
+    #[serde(default)]
+    pub duration: f64,
+    /// 中间帧数量，默认 8
+    #[serde(default = "default_transition_steps")]
+    pub steps: u32,
+}
+
+impl Default for TransitionConfig {
+    fn default() -> Self {
+        Self {
+            duration: 0.0,
+            steps: default_transition_steps(),
+        }
+    }
+}
+
+fn default_transition_steps() -> u32 {
+    8
+}
+
+/// 多显示器布局
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum MonitorLayout {
+    /// 所有显示器使用同一张图（默认）
+    #[default]
+    Same,
+    /// 每个显示器按各自的分辨率下载一张不同的图
+    PerMonitor,
+    /// 下载一张覆盖整个桌面的超宽图，按显示器位置裁剪成多块
+    Span,
+}
+
+/// 壁纸缩放模式，与 wallpaper 库的 Mode 对应
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum WallpaperMode {
+    /// 等比缩放铺满屏幕，超出部分裁掉（默认）
+    #[default]
+    #[serde(alias = "fill")]
+    #[value(alias = "fill")]
+    Crop,
+    /// 等比缩放完整显示，空白处填充黑色
+    Fit,
+    /// 拉伸到屏幕尺寸，不保持比例
+    Stretch,
+    /// 原尺寸居中
+    Center,
+    /// 原尺寸平铺
+    Tile,
+    /// 一张图横跨所有显示器
+    Span,
+}
+
+impl WallpaperMode {
+    /// 配置文件和 {mode} 占位符中使用的名称
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WallpaperMode::Crop => "crop",
+            WallpaperMode::Fit => "fit",
+            WallpaperMode::Stretch => "stretch",
+            WallpaperMode::Center => "center",
+            WallpaperMode::Tile => "tile",
+            WallpaperMode::Span => "span",
+        }
+    }
+}
+
+/// 自定义设置命令：一条命令（参数数组）或多条命令
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(untagged)]
+pub enum SetterCommand {
+    Single(Vec<String>),
+    Multiple(Vec<Vec<String>>),
+}
+
+impl SetterCommand {
+    /// 按执行顺序返回所有非空命令
+    pub fn commands(&self) -> Vec<&[String]> {
+        match self {
+            SetterCommand::Single(cmd) => vec![cmd.as_slice()],
+            SetterCommand::Multiple(cmds) => cmds.iter().map(Vec::as_slice).collect(),
+        }
+        .into_iter()
+        .filter(|cmd| !cmd.is_empty())
+        .collect()
+    }
+}
+
+/// 主题转换配置
+#[derive(Debug, Clone, Deserialize, Serialize, Default, JsonSchema)]
+pub struct ConvertConfig {
+    /// 转换引擎 (gowall / native)，默认 gowall
+    #[serde(default)]
+    pub backend: ConvertBackend,
+    /// 内置引擎：是否使用 Floyd–Steinberg 抖动
+    #[serde(default)]
+    pub dither: bool,
+    /// 内置引擎：是否保留原图亮度
+    #[serde(default)]
+    pub preserve_luminance: bool,
+}
+
+/// 终端配色导出配置
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct PaletteConfig {
+    /// run / set 完成后是否自动从最终壁纸提取配色并导出
+    #[serde(default)]
+    pub auto: bool,
+    /// 自动导出的格式列表 (pywal / alacritty / kitty / wezterm / foot / ghostty)
+    #[serde(default = "default_palette_formats")]
+    pub formats: Vec<PaletteFormat>,
+    /// 导出目录，默认 ~/.local/state/wallow/palette
+    /// 支持 ~、$HOME 等环境变量，相对路径则相对于 $HOME
+    #[serde(default)]
+    pub output_dir: Option<String>,
+}
+
+impl Default for PaletteConfig {
+    fn default() -> Self {
+        Self {
+            auto: false,
+            formats: default_palette_formats(),
+            output_dir: None,
+        }
+    }
+}
+
+fn default_palette_formats() -> Vec<PaletteFormat> {
+    vec![PaletteFormat::Pywal]
+}
+
+/// 用户自定义主题定义
+/// 既用于 config.toml 中的 [themes.<name>]，也用于 ~/.config/wallow/themes/ 下的独立调色板文件
+#[derive(Debug, Clone, Deserialize, Serialize, JsonSchema)]
+pub struct ThemeDef {
+    /// 主题名称，独立文件中可省略（默认使用文件名）；config.toml 中以表名为准
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// 十六进制颜色列表，如 ["#1e1e2e", "#cdd6f4"]
+    pub colors: Vec<String>,
 }
 
 /// 应用全局配置项
@@ -165,20 +692,41 @@ pub struct AppConfig {
     pub api_key: Option<String>,
     /// Unsplash Access Key (优先级：ENV > TOML)
     pub unsplash_access_key: Option<String>,
-    /// 默认壁纸来源 (wallhaven / unsplash)
-    pub default_source: String,
-    /// 默认主题（不配置则不自动转换）
-    pub default_theme: Option<String>,
+    /// 默认壁纸来源 (wallhaven / unsplash)，可为多个候选
+    pub default_source: StringOrVec,
+    /// 默认主题（不配置则不自动转换），可为多个候选
+    pub default_theme: Option<StringOrVec>,
+    /// 浅色 / 深色模式下的主题
+    pub theme_light: Option<StringOrVec>,
+    pub theme_dark: Option<StringOrVec>,
+    /// 多值配置项的选择策略
+    pub rotation: RotationMode,
+    /// 下载壁纸的文件名模板，None 时使用 library::DEFAULT_FILENAME_TEMPLATE
+    pub filename_template: Option<String>,
+    /// 转换后壁纸的文件名模板，None 时使用 library::DEFAULT_CONVERTED_TEMPLATE
+    pub converted_template: Option<String>,
     /// 壁纸保存根目录
     pub wallpaper_dir: PathBuf,
-    /// 转换后壁纸的输出目录列表（至少一个）
-    pub converted_dirs: Vec<PathBuf>,
+    /// 转换后壁纸的输出目录列表（至少一个），第一个为主目录
+    pub converted_dirs: Vec<ConvertedDir>,
     /// 配置文件所在路径
     pub config_path: PathBuf,
+    /// 运行状态目录（轮询游标等），遵循 $XDG_STATE_HOME，默认 ~/.local/state/wallow
+    pub state_dir: PathBuf,
     /// 默认搜索参数
     pub search_defaults: SearchDefaults,
     /// 定时任务配置 (cron 表达式)
     pub schedule: ScheduleConfig,
+    /// 主题转换配置
+    pub convert: ConvertConfig,
+    /// 壁纸设置配置
+    pub setter: SetterConfig,
+    /// 终端配色导出配置
+    pub palette: PaletteConfig,
+    /// config.toml 中的自定义主题
+    pub themes: BTreeMap<String, ThemeDef>,
+    /// 后处理流水线
+    pub pipeline: Vec<StepConfig>,
 }
 
 impl AppConfig {
@@ -189,6 +737,14 @@ impl AppConfig {
         let config_dir = home_path.join(".config").join("wallow");
         let config_path = config_dir.join("config.toml");
 
+        // 状态目录：优先 $XDG_STATE_HOME/wallow，否则 ~/.local/state/wallow
+        let state_dir = env::var("XDG_STATE_HOME")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home_path.join(".local").join("state"))
+            .join("wallow");
+
         let config_file = Self::load_config_from_file(&config_path).unwrap_or_default();
 
         // 优先级：环境变量 > 配置文件内容
@@ -217,12 +773,21 @@ impl AppConfig {
         };
 
         // converted_dirs: 配置了则解析每个路径（展开变量），未配置则默认为 wallpaper_dir/converted
-        let converted_dirs: Vec<PathBuf> = if config_file.common.converted_dirs.is_empty() {
-            vec![wallpaper_dir.join("converted")]
+        let converted_dirs: Vec<ConvertedDir> = if config_file.common.converted_dirs.is_empty() {
+            vec![ConvertedDir {
+                path: wallpaper_dir.join("converted"),
+                variant: DirVariant::default(),
+                options: OutputOptions::default(),
+            }]
         } else {
-            config_file.common.converted_dirs.iter().map(|s| {
+            config_file.common.converted_dirs.iter().map(|entry| {
+                let (s, variant, options) = match entry {
+                    ConvertedDirEntry::Path(s) => (s, DirVariant::default(), OutputOptions::default()),
+                    ConvertedDirEntry::Table { path, variant, options } => (path, variant.clone(), options.clone()),
+                };
                 let p = expand_path(s); // 展开 ~ 和 $HOME 等变量
-                if p.is_absolute() { p } else { home_path.join(p) }
+                let path = if p.is_absolute() { p } else { home_path.join(p) };
+                ConvertedDir { path, variant, options }
             }).collect()
         };
         Self {
@@ -230,11 +795,22 @@ impl AppConfig {
             unsplash_access_key,
             default_source: if config_file.common.source.is_empty() { default_source() } else { config_file.common.source },
             default_theme: config_file.common.theme,
+            theme_light: config_file.common.theme_light,
+            theme_dark: config_file.common.theme_dark,
+            rotation: config_file.common.rotation,
+            filename_template: config_file.common.filename_template,
+            converted_template: config_file.common.converted_template,
             wallpaper_dir,
             converted_dirs,
             config_path,
+            state_dir,
             search_defaults: config_file.common.search,
             schedule: config_file.schedule,
+            convert: config_file.convert,
+            setter: config_file.setter,
+            palette: config_file.palette,
+            themes: config_file.themes,
+            pipeline: config_file.pipeline,
         }
     }
 
@@ -251,9 +827,10 @@ impl AppConfig {
             fs::create_dir_all(parent)?;
         }
 
+        fs::create_dir_all(&self.state_dir)?;
         fs::create_dir_all(&self.wallpaper_dir)?;
         for dir in &self.converted_dirs {
-            fs::create_dir_all(dir)?;
+            fs::create_dir_all(&dir.path)?;
         }
 
         Ok(())
@@ -264,15 +841,24 @@ impl AppConfig {
         let config_file = ConfigFile {
             common: CommonConfig {
                 wallpaper_dir: Some(self.wallpaper_dir.to_string_lossy().to_string()),
-                converted_dirs: self.converted_dirs.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+                converted_dirs: self.converted_dirs.iter().map(ConvertedDir::to_entry).collect(),
                 source: self.default_source.clone(),
                 theme: self.default_theme.clone(),
+                theme_light: self.theme_light.clone(),
+                theme_dark: self.theme_dark.clone(),
+                rotation: self.rotation,
+                filename_template: self.filename_template.clone(),
+                converted_template: self.converted_template.clone(),
                 search: SearchDefaults {
                     query: self.search_defaults.query.clone(),
+                    query_light: self.search_defaults.query_light.clone(),
+                    query_dark: self.search_defaults.query_dark.clone(),
                     resolution: self.search_defaults.resolution.clone(),
+                    screen: self.search_defaults.screen.clone(),
                     categories: self.search_defaults.categories.clone(),
                     purity: self.search_defaults.purity.clone(),
                     sorting: self.search_defaults.sorting.clone(),
+                    selection: self.search_defaults.selection,
                 },
             },
             source: SourceConfigs {
@@ -283,13 +869,16 @@ impl AppConfig {
                     access_key: self.unsplash_access_key.clone(),
                 },
             },
-            schedule: ScheduleConfig {
-                cron: self.schedule.cron.clone(),
-            },
+            schedule: self.schedule.clone(),
+            convert: self.convert.clone(),
+            setter: self.setter.clone(),
+            palette: self.palette.clone(),
+            themes: self.themes.clone(),
+            pipeline: self.pipeline.clone(),
         };
 
         let toml_str = toml::to_string_pretty(&config_file)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+            .map_err(std::io::Error::other)?;
         fs::write(&self.config_path, toml_str)
     }
     /// 更新 schedule.cron 并保存到配置文件
@@ -298,6 +887,25 @@ impl AppConfig {
         self.save()
     }
 
+    /// 更新 schedule.backend 并保存到配置文件
+    pub fn set_schedule_backend(&mut self, backend: ScheduleBackend) -> std::io::Result<()> {
+        self.schedule.backend = backend;
+        self.save()
+    }
+
+    /// 配置目录 ~/.config/wallow
+    pub fn config_dir(&self) -> &Path {
+        self.config_path.parent().unwrap_or(Path::new("."))
+    }
+
+    /// 终端配色导出目录：配置了则展开路径，未配置则为 state_dir/palette
+    pub fn palette_dir(&self) -> PathBuf {
+        match &self.palette.output_dir {
+            Some(dir) => resolve_home_path(dir),
+            None => self.state_dir.join("palette"),
+        }
+    }
+
     /// 获取配置文件的 JSON Schema
     pub fn get_schema() -> String {
         let schema = schemars::schema_for!(ConfigFile);
@@ -309,15 +917,24 @@ impl AppConfig {
         let config_file = ConfigFile {
             common: CommonConfig {
                 wallpaper_dir: Some(self.wallpaper_dir.to_string_lossy().to_string()),
-                converted_dirs: self.converted_dirs.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+                converted_dirs: self.converted_dirs.iter().map(ConvertedDir::to_entry).collect(),
                 source: self.default_source.clone(),
                 theme: self.default_theme.clone(),
+                theme_light: self.theme_light.clone(),
+                theme_dark: self.theme_dark.clone(),
+                rotation: self.rotation,
+                filename_template: self.filename_template.clone(),
+                converted_template: self.converted_template.clone(),
                 search: SearchDefaults {
                     query: self.search_defaults.query.clone(),
+                    query_light: self.search_defaults.query_light.clone(),
+                    query_dark: self.search_defaults.query_dark.clone(),
                     resolution: self.search_defaults.resolution.clone(),
+                    screen: self.search_defaults.screen.clone(),
                     categories: self.search_defaults.categories.clone(),
                     purity: self.search_defaults.purity.clone(),
                     sorting: self.search_defaults.sorting.clone(),
+                    selection: self.search_defaults.selection,
                 },
             },
             source: SourceConfigs {
@@ -328,9 +945,12 @@ impl AppConfig {
                     access_key: self.unsplash_access_key.clone(),
                 },
             },
-            schedule: ScheduleConfig {
-                cron: self.schedule.cron.clone(),
-            },
+            schedule: self.schedule.clone(),
+            convert: self.convert.clone(),
+            setter: self.setter.clone(),
+            palette: self.palette.clone(),
+            themes: self.themes.clone(),
+            pipeline: self.pipeline.clone(),
         };
 
         let toml_str = toml::to_string_pretty(&config_file)
@@ -338,11 +958,55 @@ impl AppConfig {
 
         // 在 [source.unsplash] 节后追加注释说明
         // toml 库不支持带注释序列化，所以手动插入
-        let toml_str = toml_str.replace(
+        toml_str.replace(
             "[source.unsplash]",
             "# 壁纸来源配置\n# 可选来源: wallhaven (default), unsplash\n# 在 [common] 中设置 source = \"unsplash\" 可将 Unsplash 设为默认来源\n[source.unsplash]\n# access_key = \"your_unsplash_access_key_here\""
-        );
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(toml_value: &str) -> StringOrVec {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            value: StringOrVec,
+        }
+        toml::from_str::<Wrapper>(&format!("value = {}", toml_value)).unwrap().value
+    }
 
-        toml_str
+    fn cycle(list: &StringOrVec, len: usize) -> Vec<Option<&str>> {
+        (0..len).map(|cursor| list.pick_round_robin(cursor)).collect()
+    }
+
+    #[test]
+    fn round_robin_follows_weights() {
+        let two_to_one = list(r#"[{ value = "a", weight = 2 }, "b"]"#);
+        assert_eq!(cycle(&two_to_one, 3), [Some("a"), Some("b"), Some("a")]);
+        let three_to_one = list(r#"[{ value = "nature", weight = 3 }, "city"]"#);
+        assert_eq!(cycle(&three_to_one, 4), [Some("nature"), Some("nature"), Some("city"), Some("nature")]);
+        // 权重为 0 的值永远不会被选中
+        let with_zero = list(r#"["a", { value = "never", weight = 0 }, "b"]"#);
+        assert_eq!(cycle(&with_zero, 4), [Some("a"), Some("b"), Some("a"), Some("b")]);
+    }
+
+    #[test]
+    fn round_robin_cursor_wraps() {
+        let two_to_one = list(r#"[{ value = "a", weight = 2 }, "b"]"#);
+        assert_eq!(two_to_one.pick_round_robin(3), Some("a"));
+        assert_eq!(two_to_one.pick_round_robin(4), Some("b"));
+        assert_eq!(two_to_one.pick_round_robin(usize::MAX), two_to_one.pick_round_robin(usize::MAX % 3));
+    }
+
+    #[test]
+    fn nothing_to_pick() {
+        for empty in [list("[]"), list(r#"[{ value = "a", weight = 0 }]"#), list(r#""""#), list(r#"["", ""]"#)] {
+            assert_eq!(empty.pick_round_robin(0), None);
+            assert_eq!(empty.pick_random(), None);
+        }
+        assert_eq!(list(r#""nature""#).pick_random(), Some("nature"));
+        assert_eq!(list(r#"[{ value = "a", weight = 0 }, "b"]"#).pick_random(), Some("b"));
     }
 }
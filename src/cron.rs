@@ -0,0 +1,309 @@
+// cron.rs — cron 表达式解析
+// 把五段式 cron 表达式（分 时 日 月 周）展开为各字段的取值集合，
+// 供 systemd 定时器等不直接支持 cron 语法的定时任务后端换算
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone};
+use rust_i18n::t;
+
+/// 展开后的 cron 表达式
+#[derive(Debug, Clone)]
+pub struct CronExpr {
+    pub minutes: Vec<u32>,
+    pub hours: Vec<u32>,
+    /// 每月的第几天 1-31
+    pub days: Vec<u32>,
+    /// 月份 1-12
+    pub months: Vec<u32>,
+    /// 星期 0-6，0 为周日
+    pub weekdays: Vec<u32>,
+    /// 日和周字段是否以 * 开头；两者都有限制时按 cron 的约定取并集（任一满足即触发）
+    pub day_any: bool,
+    pub weekday_any: bool,
+}
+
+const MONTH_NAMES: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// @daily 等简写对应的五段式表达式
+fn expand_macro(expr: &str) -> Option<&'static str> {
+    Some(match expr {
+        "@yearly" | "@annually" => "0 0 1 1 *",
+        "@monthly" => "0 0 1 * *",
+        "@weekly" => "0 0 * * 0",
+        "@daily" | "@midnight" => "0 0 * * *",
+        "@hourly" => "0 * * * *",
+        _ => return None,
+    })
+}
+
+/// 解析字段中的单个值，支持月份和星期的英文缩写
+fn value(s: &str, names: &[&str], offset: u32) -> Option<u32> {
+    // 只接受纯数字，u32::from_str 会接受 "+5" 这样的写法
+    if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+        return s.parse().ok();
+    }
+    let lower = s.to_lowercase();
+    names.iter().position(|n| *n == lower).map(|i| i as u32 + offset)
+}
+
+/// 展开一个字段：* */n a a-b a-b/n a/n 以及逗号分隔的组合
+fn field(s: &str, name: &str, min: u32, max: u32, names: &[&str]) -> Result<Vec<u32>, Box<dyn std::error::Error>> {
+    let error = || t!("error_cron_value", field => name, value => s);
+    let mut values = Vec::new();
+    for item in s.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|n| *n > 0).ok_or_else(error)?),
+            None => (item, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (value(a, names, min).ok_or_else(error)?, value(b, names, min).ok_or_else(error)?),
+                // a/n 表示从 a 开始到最大值
+                None => {
+                    let a = value(range, names, min).ok_or_else(error)?;
+                    (a, if item.contains('/') { max } else { a })
+                }
+            },
+        };
+        // 星期字段允许 7 表示周日
+        let limit = if name == "weekday" { 7 } else { max };
+        if start < min || end > limit || start > end {
+            return Err(error().into());
+        }
+        values.extend((start..=end).step_by(step as usize).map(|v| if name == "weekday" { v % 7 } else { v }));
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(values)
+}
+
+impl CronExpr {
+    /// 解析 cron 表达式，支持 @hourly / @daily / @weekly / @monthly / @yearly 简写
+    pub fn parse(expr: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let expr = expr.trim();
+        let expanded = if expr.starts_with('@') {
+            expand_macro(&expr.to_lowercase()).ok_or(t!("error_cron_macro", expr => expr))?
+        } else {
+            expr
+        };
+        let parts: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = parts[..] else {
+            return Err(t!("error_cron_fields", expr => expr).into());
+        };
+        Ok(Self {
+            minutes: field(minute, "minute", 0, 59, &[])?,
+            hours: field(hour, "hour", 0, 23, &[])?,
+            days: field(day, "day", 1, 31, &[])?,
+            months: field(month, "month", 1, 12, &MONTH_NAMES)?,
+            weekdays: field(weekday, "weekday", 0, 6, &WEEKDAY_NAMES)?,
+            day_any: day.starts_with('*'),
+            weekday_any: weekday.starts_with('*'),
+        })
+    }
+
+    /// 解析并检查表达式在今后五年内至少会触发一次（排除 "0 0 30 2 *" 这类永不触发的写法）
+    pub fn validate(expr: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let cron = Self::parse(expr)?;
+        if cron.next_after(Local::now()).is_none() {
+            return Err(t!("error_cron_never", expr => expr).into());
+        }
+        Ok(cron)
+    }
+
+    /// 某天是否匹配日、月和星期字段
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if !self.months.contains(&date.month()) {
+            return false;
+        }
+        let day = self.days.contains(&date.day());
+        let weekday = self.weekdays.contains(&date.weekday().num_days_from_sunday());
+        // 日和周都不以 * 开头时任一满足即可，否则需要同时满足
+        if self.day_any || self.weekday_any { day && weekday } else { day || weekday }
+    }
+
+    /// 晚于 after 的下一次触发时间（本地时间），五年内都不触发时返回 None
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after.date_naive();
+        for offset in 0..366 * 5 {
+            let date = start + Duration::days(offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+            for &hour in &self.hours {
+                for &minute in &self.minutes {
+                    // 夏令时跳过的时刻不存在，重复的时刻取较早的一次
+                    let Some(time) = date.and_hms_opt(hour, minute, 0) else { continue };
+                    let Some(at) = Local.from_local_datetime(&time).earliest() else { continue };
+                    if at > after {
+                        return Some(at);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// 从 after 开始的接下来 count 次触发时间
+    pub fn upcoming(&self, after: DateTime<Local>, count: usize) -> Vec<DateTime<Local>> {
+        std::iter::successors(self.next_after(after), |at| self.next_after(*at)).take(count).collect()
+    }
+
+    /// 换算为 systemd 定时器的 OnCalendar= 值
+    ///
+    /// 日和周都有限制时 cron 取并集，而 systemd 取交集，因此拆成两条
+    pub fn to_on_calendar(&self) -> Vec<String> {
+        let time = format!("{}:{}:00", calendar_list(&self.hours, 0, 23), calendar_list(&self.minutes, 0, 59));
+        let months = calendar_list(&self.months, 1, 12);
+        let days = calendar_list(&self.days, 1, 31);
+        // 星期取满范围时省略，否则在每一条前面都加上星期限制
+        let weekdays = match restricted(&self.weekdays, 0, 6) {
+            Some(values) => format!("{} ", weekday_list(values)),
+            None => String::new(),
+        };
+        if self.day_any || self.weekday_any {
+            vec![format!("{}*-{}-{} {}", weekdays, months, days, time)]
+        } else {
+            vec![
+                format!("{}*-{}-* {}", weekdays, months, time),
+                format!("*-{}-{} {}", months, days, time),
+            ]
+        }
+    }
+
+    /// 换算为 launchd 的 StartCalendarInterval：每个字典内的键同时满足，数组中任一字典满足即触发
+    ///
+    /// 取满范围的字段省略（表示任意值），其余字段取笛卡尔积；日和周都有限制时分别生成
+    pub fn to_calendar_intervals(&self) -> Vec<Vec<(&'static str, u32)>> {
+        let fields: Vec<(&'static str, &[u32])> = [
+            ("Minute", restricted(&self.minutes, 0, 59)),
+            ("Hour", restricted(&self.hours, 0, 23)),
+            ("Month", restricted(&self.months, 1, 12)),
+        ]
+        .into_iter()
+        .filter_map(|(key, values)| Some((key, values?)))
+        .collect();
+        let day = restricted(&self.days, 1, 31).map(|d| ("Day", d));
+        let weekday = restricted(&self.weekdays, 0, 6).map(|w| ("Weekday", w));
+        // 日和周都不以 * 开头时 cron 取并集，拆成两组；否则两者同时满足
+        let variants: Vec<Vec<(&'static str, &[u32])>> = match (day, weekday) {
+            (Some(day), Some(weekday)) if !self.day_any && !self.weekday_any => vec![vec![day], vec![weekday]],
+            (Some(_), None) | (None, Some(_)) if !self.day_any && !self.weekday_any => vec![Vec::new()],
+            _ => vec![day.into_iter().chain(weekday).collect()],
+        };
+
+        let mut intervals = Vec::new();
+        for extra in variants {
+            let mut product: Vec<Vec<(&'static str, u32)>> = vec![Vec::new()];
+            for (key, values) in fields.iter().chain(extra.iter()) {
+                product = product
+                    .into_iter()
+                    .flat_map(|dict| {
+                        values.iter().map(move |v| {
+                            let mut dict = dict.clone();
+                            dict.push((*key, *v));
+                            dict
+                        })
+                    })
+                    .collect();
+            }
+            intervals.extend(product);
+        }
+        intervals
+    }
+}
+
+/// 取满范围时返回 None（任意值），否则返回取值列表
+fn restricted(values: &[u32], min: u32, max: u32) -> Option<&[u32]> {
+    (values.len() as u32 != max - min + 1).then_some(values)
+}
+
+/// OnCalendar 中的数值列表：取满范围时为 *，连续三个以上的值合并为 a..b
+fn calendar_list(values: &[u32], min: u32, max: u32) -> String {
+    if values.len() as u32 == max - min + 1 {
+        return "*".to_string();
+    }
+    runs(values).into_iter().map(|(a, b)| match b - a {
+        0 => format!("{:02}", a),
+        1 => format!("{:02},{:02}", a, b),
+        _ => format!("{:02}..{:02}", a, b),
+    }).collect::<Vec<_>>().join(",")
+}
+
+/// OnCalendar 中的星期列表，systemd 的一周从周一开始
+fn weekday_list(values: &[u32]) -> String {
+    const NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    // cron 的 0（周日）换到最后
+    let mut days: Vec<u32> = values.iter().map(|d| (d + 6) % 7).collect();
+    days.sort_unstable();
+    runs(&days).into_iter().map(|(a, b)| match b - a {
+        0 => NAMES[a as usize].to_string(),
+        1 => format!("{},{}", NAMES[a as usize], NAMES[b as usize]),
+        _ => format!("{}..{}", NAMES[a as usize], NAMES[b as usize]),
+    }).collect::<Vec<_>>().join(",")
+}
+
+/// 把有序的值分成连续的区间
+fn runs(values: &[u32]) -> Vec<(u32, u32)> {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for &v in values {
+        match runs.last_mut() {
+            Some((_, end)) if *end + 1 == v => *end = v,
+            _ => runs.push((v, v)),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn on_calendar() {
+        let cases: &[(&str, &[&str])] = &[
+            ("@daily", &["*-*-* 00:00:00"]),
+            ("@hourly", &["*-*-* *:00:00"]),
+            ("@weekly", &["Sun *-*-* 00:00:00"]),
+            ("@monthly", &["*-*-01 00:00:00"]),
+            ("*/15 9-17 * * 1-5", &["Mon..Fri *-*-* 09..17:00,15,30,45:00"]),
+            // 日和周都有限制时 cron 取并集，拆成两条
+            ("30 8 1,15 * 1", &["Mon *-*-* 08:30:00", "*-*-01,15 08:30:00"]),
+            // 任一字段以 * 开头时取交集，星期限制不能丢
+            ("0 0 1-15 * */2", &["Tue,Thu,Sat,Sun *-*-01..15 00:00:00"]),
+            ("0 0 */2 * */2", &["Tue,Thu,Sat,Sun *-*-01,03,05,07,09,11,13,15,17,19,21,23,25,27,29,31 00:00:00"]),
+            ("0 0 */2 * 1-3", &["Mon..Wed *-*-01,03,05,07,09,11,13,15,17,19,21,23,25,27,29,31 00:00:00"]),
+            // 跨过周日的星期范围：cron 的 0 和 7 都是周日，systemd 的一周从周一开始
+            ("0 12 * * 5-7", &["Fri..Sun *-*-* 12:00:00"]),
+            ("0 12 * * 0,6", &["Sat,Sun *-*-* 12:00:00"]),
+            ("0 12 * * 0-2", &["Mon,Tue,Sun *-*-* 12:00:00"]),
+            ("0 0 1 1,6 *", &["*-01,06-01 00:00:00"]),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(CronExpr::parse(expr).unwrap().to_on_calendar(), *expected, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn calendar_intervals() {
+        let intervals = |expr: &str| CronExpr::parse(expr).unwrap().to_calendar_intervals();
+        // 所有字段取满范围：一个空字典，表示每分钟
+        assert_eq!(intervals("* * * * *"), vec![Vec::new()]);
+        assert_eq!(intervals("@daily"), vec![vec![("Minute", 0), ("Hour", 0)]]);
+        // 日和周都有限制时取并集，分成两个字典
+        assert_eq!(
+            intervals("0 9 1 * 1"),
+            vec![vec![("Minute", 0), ("Hour", 9), ("Day", 1)], vec![("Minute", 0), ("Hour", 9), ("Weekday", 1)]]
+        );
+        // 任一字段以 * 开头时取交集，同一个字典里同时出现
+        assert_eq!(
+            intervals("0 0 1,15 * */3"),
+            [(1, 0), (1, 3), (1, 6), (15, 0), (15, 3), (15, 6)]
+                .map(|(day, weekday)| vec![("Minute", 0), ("Hour", 0), ("Day", day), ("Weekday", weekday)])
+        );
+        // 日和周的并集中有一方取满范围时就是每天
+        assert_eq!(intervals("0 0 1-31 * 1"), vec![vec![("Minute", 0), ("Hour", 0)]]);
+        // 笛卡尔积：4 个分钟 × 9 个小时 × 5 个工作日
+        assert_eq!(intervals("*/15 9-17 * * 1-5").len(), 180);
+    }
+}
@@ -0,0 +1,95 @@
+// current.rs — 当前壁纸状态模块
+// set / apply 之后更新 state_dir/current 符号链接和 state_dir/current.json，
+// 供 WezTerm、hyprlock、i3lock 等需要固定路径的工具引用；
+// 指定显示器时另外更新 state_dir/current-<显示器名>
+
+use crate::config::AppConfig;
+use crate::library::Library;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// current.json 的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurrentWallpaper {
+    /// 当前壁纸图片的绝对路径
+    pub path: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// 设置到的显示器，None 表示所有显示器
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<String>,
+    /// 转换前的原图，壁纸本身就是原图时与 path 相同
+    pub original: PathBuf,
+    /// 设置时间（RFC 3339）
+    pub set_at: String,
+}
+
+/// 固定路径的符号链接 state_dir/current
+pub fn link_path(config: &AppConfig) -> PathBuf {
+    config.state_dir.join("current")
+}
+
+/// 单个显示器的符号链接 state_dir/current-<monitor>
+pub fn monitor_link_path(config: &AppConfig, monitor: &str) -> PathBuf {
+    config.state_dir.join(format!("current-{}", monitor))
+}
+
+fn json_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("current.json")
+}
+
+/// 读取 current.json，从未设置过壁纸时返回 None
+pub fn load(config: &AppConfig) -> Option<CurrentWallpaper> {
+    let content = fs::read_to_string(json_path(&config.state_dir)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 记录 path 为当前壁纸：更新符号链接并写入 current.json
+///
+/// 来源、ID、主题和原图从壁纸库索引中查询，不在索引中的本地图片只记录路径；
+/// 指定 monitor 时同时更新该显示器的符号链接
+pub fn update(
+    config: &AppConfig,
+    path: &Path,
+    monitor: Option<&str>,
+) -> Result<CurrentWallpaper, Box<dyn std::error::Error>> {
+    let meta = Library::load(&config.state_dir).get(path).cloned().unwrap_or_default();
+    let path = fs::canonicalize(path)?;
+    let non_empty = |s: String| (!s.is_empty()).then_some(s);
+    let current = CurrentWallpaper {
+        original: meta.original.clone().unwrap_or_else(|| path.clone()),
+        path,
+        source: non_empty(meta.source),
+        id: non_empty(meta.id),
+        theme: meta.theme,
+        monitor: monitor.map(str::to_string),
+        set_at: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    };
+
+    replace_link(&current.path, &link_path(config))?;
+    if let Some(monitor) = monitor {
+        replace_link(&current.path, &monitor_link_path(config, monitor))?;
+    }
+    fs::write(json_path(&config.state_dir), serde_json::to_string_pretty(&current)?)?;
+    Ok(current)
+}
+
+/// 先在旁边创建新链接再重命名覆盖，读取方不会看到链接缺失的瞬间
+#[cfg(unix)]
+fn replace_link(target: &Path, link: &Path) -> std::io::Result<()> {
+    let tmp = link.with_extension("tmp");
+    let _ = fs::remove_file(&tmp);
+    std::os::unix::fs::symlink(target, &tmp)?;
+    fs::rename(&tmp, link)
+}
+
+/// 不支持符号链接的平台上复制一份图片
+#[cfg(not(unix))]
+fn replace_link(target: &Path, link: &Path) -> std::io::Result<()> {
+    fs::copy(target, link).map(|_| ())
+}
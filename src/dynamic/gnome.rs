@@ -0,0 +1,243 @@
+// dynamic/gnome.rs — GNOME 幻灯片 XML 的导入与导出
+// GNOME 的时间壁纸是 <background> XML：<starttime> 给出起点，依次排列的 <static>（显示时长 + 图片）
+// 和 <transition>（过渡时长）循环播放；这里只需要其中少量标签，手写一个简单的标签查找即可
+
+use super::DAY;
+use rust_i18n::t;
+use std::path::{Path, PathBuf};
+
+/// 取出第一个 <tag ...>...</tag> 的内容和结束位置
+fn element<'a>(text: &'a str, tag: &str) -> Option<(&'a str, usize)> {
+    let open = format!("<{}", tag);
+    let mut from = 0;
+    let start = loop {
+        let pos = from + text[from..].find(&open)?;
+        let after = pos + open.len();
+        // 排除 <static> 与 <staticfoo> 这类前缀相同的标签
+        if text[after..].starts_with(['>', ' ', '\t', '\r', '\n']) {
+            break pos;
+        }
+        from = after;
+    };
+    let content_start = start + text[start..].find('>')? + 1;
+    let close = format!("</{}>", tag);
+    let content_end = content_start + text[content_start..].find(&close)?;
+    Some((&text[content_start..content_end], content_end + close.len()))
+}
+
+/// 某个数值标签的值
+fn number(text: &str, tag: &str) -> Option<f64> {
+    element(text, tag)?.0.trim().parse().ok()
+}
+
+/// 还原 XML 转义
+fn unescape(s: &str) -> String {
+    s.trim()
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// 转义 XML 特殊字符
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// <static> 中的图片：直接是路径，或是多个 <size width= height=> 变体（取面积最大的）
+fn static_file(content: &str) -> Option<String> {
+    let (file, _) = element(content, "file")?;
+    if !file.contains("<size") {
+        return Some(unescape(file));
+    }
+    let mut best: Option<(u64, String)> = None;
+    let mut rest = file;
+    while let Some(start) = rest.find("<size") {
+        let tail = &rest[start..];
+        let (path, end) = element(tail, "size")?;
+        let tag = &tail[..tail.find('>')?];
+        let attr = |name: &str| -> u64 {
+            tag.split(&format!("{}=", name))
+                .nth(1)
+                .and_then(|v| v.trim_start_matches(['"', '\'']).split(['"', '\'']).next())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0)
+        };
+        let area = attr("width") * attr("height");
+        if best.as_ref().is_none_or(|(a, _)| area > *a) {
+            best = Some((area, unescape(path)));
+        }
+        rest = &tail[end..];
+    }
+    best.map(|(_, path)| path)
+}
+
+/// 解析 GNOME 幻灯片 XML，返回每张图片开始显示的时间（一天中的第几秒）和路径
+///
+/// 一轮不足一天的幻灯片会重复铺满 24 小时；相对路径相对于 XML 所在目录
+pub fn parse(xml: &str, base: &Path) -> Result<Vec<(u32, PathBuf)>, Box<dyn std::error::Error>> {
+    let (background, _) = element(xml, "background").ok_or(t!("error_dynamic_not_xml"))?;
+    let start = match element(background, "starttime") {
+        Some((starttime, _)) => {
+            let part = |tag: &str| number(starttime, tag).unwrap_or(0.0);
+            part("hour") * 3600.0 + part("minute") * 60.0 + part("second")
+        }
+        None => 0.0,
+    };
+
+    // 按出现顺序读取 <static> 与 <transition>
+    let mut cycle: Vec<(f64, PathBuf)> = Vec::new();
+    let mut offset = 0.0;
+    let mut rest = background;
+    loop {
+        let next_static = element(rest, "static").map(|(c, end)| (end - c.len(), c, end, true));
+        let next_transition = element(rest, "transition").map(|(c, end)| (end - c.len(), c, end, false));
+        let next = match (next_static, next_transition) {
+            (Some(s), Some(t)) => if s.0 < t.0 { s } else { t },
+            (Some(s), None) => s,
+            (None, Some(t)) => t,
+            (None, None) => break,
+        };
+        let (_, content, end, is_static) = next;
+        let duration = number(content, "duration").unwrap_or(0.0).max(0.0);
+        if is_static && let Some(file) = static_file(content) {
+            let path = PathBuf::from(&file);
+            let path = if path.is_absolute() { path } else { base.join(path) };
+            cycle.push((offset, path));
+        }
+        offset += duration;
+        rest = &rest[end..];
+    }
+    if cycle.is_empty() {
+        return Err(t!("error_dynamic_no_frames").into());
+    }
+
+    // 一轮的总时长为 0 时视为一天只显示第一张
+    let length = if offset > 0.0 { offset } else { DAY as f64 };
+    // 帧数上限 1440（平均每分钟一帧）
+    let repeats = ((DAY as f64 / length).ceil() as usize).min(1440 / cycle.len()).max(1);
+    let mut frames: Vec<(u32, PathBuf)> = Vec::new();
+    for round in 0..repeats {
+        for (at, path) in &cycle {
+            let seconds = *at + length * round as f64;
+            if round > 0 && seconds >= DAY as f64 {
+                break;
+            }
+            frames.push(((start + seconds).rem_euclid(DAY as f64) as u32, path.clone()));
+        }
+    }
+    frames.sort_by_key(|(start, _)| *start);
+    frames.dedup_by_key(|(start, _)| *start);
+    Ok(frames)
+}
+
+/// 生成 GNOME 幻灯片 XML：图片在一天中平均分配，从 start（一天中的第几秒）开始，相邻图片之间交叉过渡
+pub fn export(images: &[PathBuf], start: u32, transition: f64) -> String {
+    let slot = DAY as f64 / images.len() as f64;
+    let transition = transition.clamp(0.0, slot / 2.0);
+    let mut xml = String::from("<background>\n  <starttime>\n    <year>2011</year>\n    <month>11</month>\n    <day>24</day>\n");
+    xml.push_str(&format!(
+        "    <hour>{}</hour>\n    <minute>{}</minute>\n    <second>{}</second>\n  </starttime>\n",
+        start / 3600,
+        start / 60 % 60,
+        start % 60
+    ));
+    for (i, image) in images.iter().enumerate() {
+        let file = escape(&image.to_string_lossy());
+        let next = escape(&images[(i + 1) % images.len()].to_string_lossy());
+        xml.push_str(&format!(
+            "  <static>\n    <duration>{:.1}</duration>\n    <file>{}</file>\n  </static>\n",
+            slot - transition,
+            file
+        ));
+        if transition > 0.0 {
+            xml.push_str(&format!(
+                "  <transition type=\"overlay\">\n    <duration>{:.1}</duration>\n    <from>{}</from>\n    <to>{}</to>\n  </transition>\n",
+                transition, file, next
+            ));
+        }
+    }
+    xml.push_str("</background>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<background>
+  <starttime>
+    <year>2011</year>
+    <month>11</month>
+    <day>24</day>
+    <hour>7</hour>
+    <minute>0</minute>
+    <second>00</second>
+  </starttime>
+  <static>
+    <duration>43200.0</duration>
+    <file>
+      <size width="1920" height="1080">day-small.jpg</size>
+      <size width="3840" height="2160">day.jpg</size>
+    </file>
+  </static>
+  <transition type="overlay">
+    <duration>3600.0</duration>
+    <from>day.jpg</from>
+    <to>/usr/share/backgrounds/night &amp; stars.jpg</to>
+  </transition>
+  <static>
+    <duration>39600.0</duration>
+    <file>/usr/share/backgrounds/night &amp; stars.jpg</file>
+  </static>
+  <staticfoo><duration>1</duration></staticfoo>
+</background>
+"#;
+
+    #[test]
+    fn parse_timed_xml() {
+        let frames = parse(XML, Path::new("/themes/adwaita")).unwrap();
+        assert_eq!(
+            frames,
+            [
+                (7 * 3600, PathBuf::from("/themes/adwaita/day.jpg")),
+                (20 * 3600, PathBuf::from("/usr/share/backgrounds/night & stars.jpg")),
+            ]
+        );
+    }
+
+    #[test]
+    fn short_cycle_repeats() {
+        // 一轮 6 小时，重复 4 次铺满一天
+        let xml = "<background><static><duration>10800</duration><file>a.png</file></static>\
+                   <static><duration>10800</duration><file>b.png</file></static></background>";
+        let frames = parse(xml, Path::new("/w")).unwrap();
+        let starts: Vec<u32> = frames.iter().map(|(start, _)| *start).collect();
+        assert_eq!(starts, (0..8).map(|i| i * 10800).collect::<Vec<_>>());
+        assert_eq!(frames[3].1, PathBuf::from("/w/b.png"));
+    }
+
+    #[test]
+    fn rejects_invalid_xml() {
+        assert!(parse("<wallpapers></wallpapers>", Path::new("/")).is_err());
+        assert!(parse("<background><starttime></starttime></background>", Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn export_round_trip() {
+        let images = [PathBuf::from("/w/a.png"), PathBuf::from("/w/b <&>.png"), PathBuf::from("/w/c.png")];
+        let xml = export(&images, 6 * 3600 + 30 * 60, 600.0);
+        let frames = parse(&xml, Path::new("/elsewhere")).unwrap();
+        // 每张图 8 小时，从 06:30 开始
+        assert_eq!(
+            frames,
+            [(6 * 3600 + 1800, images[0].clone()), (14 * 3600 + 1800, images[1].clone()), (22 * 3600 + 1800, images[2].clone())]
+        );
+        assert!(xml.contains("<duration>28200.0</duration>"));
+        assert!(xml.contains("b &lt;&amp;&gt;.png"));
+    }
+}
@@ -0,0 +1,327 @@
+// dynamic/heic.rs — macOS 动态 HEIC 的导入
+// 动态 HEIC 在 XMP 元数据里用 apple_desktop:h24（按时间）或 apple_desktop:solar（按太阳位置）
+// 保存一个 base64 编码的二进制 plist，记录每张图片的显示时机；图片本身用 heif-convert 或 ImageMagick 导出
+
+use super::{DAY, FrameTime};
+use base64::Engine;
+use rust_i18n::t;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 二进制 plist 中用到的值类型
+#[derive(Debug)]
+enum Value {
+    Int(i64),
+    Real(f64),
+    Array(Vec<Value>),
+    Dict(Vec<(String, Value)>),
+    Str(String),
+    Other,
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(i) => Some(*i as f64),
+            Value::Real(r) => Some(*r),
+            _ => None,
+        }
+    }
+}
+
+/// 最小的 bplist00 解析器，只支持整数、浮点、字符串、数组和字典
+struct BPlist<'a> {
+    data: &'a [u8],
+    offsets: Vec<usize>,
+    ref_size: usize,
+}
+
+fn read_uint(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize)
+}
+
+impl<'a> BPlist<'a> {
+    fn parse(data: &'a [u8]) -> Option<Value> {
+        if data.len() < 40 || !data.starts_with(b"bplist00") {
+            return None;
+        }
+        let trailer = &data[data.len() - 32..];
+        let offset_size = trailer[6] as usize;
+        let ref_size = trailer[7] as usize;
+        let count = read_uint(&trailer[8..16]);
+        let top = read_uint(&trailer[16..24]);
+        let table = read_uint(&trailer[24..32]);
+        if offset_size == 0 || count > data.len() {
+            return None;
+        }
+        // 偏移和长度都来自文件，损坏的文件可能让它们溢出，一律用 checked 运算
+        let offsets = (0..count)
+            .map(|i| {
+                let from = table.checked_add(i.checked_mul(offset_size)?)?;
+                data.get(from..from.checked_add(offset_size)?).map(read_uint)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        BPlist { data, offsets, ref_size }.object(top, 0)
+    }
+
+    /// 对象长度：低 4 位为 0xF 时，后面紧跟一个整数对象给出实际长度
+    fn length(&self, pos: usize, low: u8) -> Option<(usize, usize)> {
+        if low != 0x0F {
+            return Some((low as usize, pos + 1));
+        }
+        let marker = *self.data.get(pos + 1)?;
+        let size = 1usize << (marker & 0x0F);
+        let len = read_uint(self.data.get(pos + 2..pos + 2 + size)?);
+        Some((len, pos + 2 + size))
+    }
+
+    fn object(&self, index: usize, depth: usize) -> Option<Value> {
+        if depth > 16 {
+            return None;
+        }
+        let pos = *self.offsets.get(index)?;
+        let marker = *self.data.get(pos)?;
+        let low = marker & 0x0F;
+        let value = match marker >> 4 {
+            0x1 => {
+                let size = 1usize << low;
+                let bytes = self.data.get(pos + 1..pos + 1 + size)?;
+                Value::Int(read_uint(bytes) as i64)
+            }
+            0x2 => {
+                let bytes = self.data.get(pos + 1..pos + 1 + (1usize << low))?;
+                match bytes.len() {
+                    4 => Value::Real(f32::from_be_bytes(bytes.try_into().ok()?) as f64),
+                    8 => Value::Real(f64::from_be_bytes(bytes.try_into().ok()?)),
+                    _ => Value::Other,
+                }
+            }
+            0x5 => {
+                let (len, start) = self.length(pos, low)?;
+                Value::Str(String::from_utf8_lossy(self.data.get(start..start.checked_add(len)?)?).into_owned())
+            }
+            0x6 => {
+                let (len, start) = self.length(pos, low)?;
+                let units: Vec<u16> = self
+                    .data
+                    .get(start..start.checked_add(len.checked_mul(2)?)?)?
+                    .chunks(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                Value::Str(String::from_utf16_lossy(&units))
+            }
+            0xA => {
+                let (len, start) = self.length(pos, low)?;
+                let items = (0..len)
+                    .map(|i| self.object(self.reference(start, i)?, depth + 1))
+                    .collect::<Option<Vec<_>>>()?;
+                Value::Array(items)
+            }
+            0xD => {
+                let (len, start) = self.length(pos, low)?;
+                let entries = (0..len)
+                    .map(|i| {
+                        let Value::Str(key) = self.object(self.reference(start, i)?, depth + 1)? else { return None };
+                        Some((key, self.object(self.reference(start, len.checked_add(i)?)?, depth + 1)?))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Value::Dict(entries)
+            }
+            _ => Value::Other,
+        };
+        Some(value)
+    }
+
+    fn reference(&self, start: usize, i: usize) -> Option<usize> {
+        let from = start.checked_add(i.checked_mul(self.ref_size)?)?;
+        Some(read_uint(self.data.get(from..from.checked_add(self.ref_size)?)?))
+    }
+}
+
+/// 在文件内容中查找 XMP 属性 apple_desktop:<key>，兼容属性和元素两种写法
+fn xmp_value(data: &[u8], key: &str) -> Option<String> {
+    let name = format!("apple_desktop:{}", key);
+    let pos = data.windows(name.len()).position(|w| w == name.as_bytes())?;
+    let rest = &data[pos + name.len()..];
+    // 属性形式 apple_desktop:h24="..."，元素形式 <apple_desktop:h24>...</apple_desktop:h24>
+    let (open, close) = match rest.first()? {
+        b'=' => (rest.iter().position(|b| *b == b'"' || *b == b'\'')? + 1, None),
+        b'>' => (1, Some(b'<')),
+        _ => return None,
+    };
+    let tail = &rest[open..];
+    let end = tail.iter().position(|b| Some(*b) == close || (close.is_none() && (*b == b'"' || *b == b'\'')))?;
+    let text: String = String::from_utf8_lossy(&tail[..end]).split_whitespace().collect();
+    Some(text)
+}
+
+/// 读取动态 HEIC 的时间元数据，返回每个图片序号对应的显示时机
+pub fn parse(data: &[u8]) -> Result<Vec<(usize, FrameTime)>, Box<dyn std::error::Error>> {
+    let (key, encoded) = ["h24", "solar"]
+        .iter()
+        .find_map(|key| Some((*key, xmp_value(data, key)?)))
+        .ok_or(t!("error_dynamic_no_metadata"))?;
+    let decoded = base64::engine::general_purpose::STANDARD.decode(encoded.as_bytes())?;
+    let plist = BPlist::parse(&decoded).ok_or(t!("error_dynamic_bad_metadata"))?;
+
+    let entries = |list: &str| match plist.get(list) {
+        Some(Value::Array(items)) => items.iter().collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    let index = |item: &Value| item.get("i").and_then(Value::as_f64).map(|i| i as usize);
+    let frames: Vec<(usize, FrameTime)> = if key == "h24" {
+        // ti: [{t: 一天中的比例, i: 图片序号}]
+        entries("ti")
+            .into_iter()
+            .filter_map(|item| {
+                let t = item.get("t")?.as_f64()?;
+                Some((index(item)?, FrameTime::Time { start: (t.rem_euclid(1.0) * DAY as f64) as u32 }))
+            })
+            .collect()
+    } else {
+        // si: [{a: 高度角, z: 方位角, i: 图片序号}]
+        entries("si")
+            .into_iter()
+            .filter_map(|item| {
+                let altitude = item.get("a")?.as_f64()?;
+                let azimuth = item.get("z")?.as_f64()?;
+                Some((index(item)?, FrameTime::Sun { altitude, azimuth }))
+            })
+            .collect()
+    };
+    if frames.is_empty() {
+        return Err(t!("error_dynamic_no_frames").into());
+    }
+    Ok(frames)
+}
+
+/// 把 HEIC 中的所有图片导出为 dir/frame-N.png，返回按序号排列的路径
+///
+/// 优先使用 libheif 的 heif-convert，失败时改用 ImageMagick
+pub fn extract(input: &Path, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let work = dir.join(".extract");
+    let _ = fs::remove_dir_all(&work);
+    fs::create_dir_all(&work)?;
+    let output = work.join("frame.png");
+    let converted = Command::new("heif-convert")
+        .arg(input)
+        .arg(&output)
+        .output()
+        .is_ok_and(|o| o.status.success())
+        || Command::new("magick")
+            .arg(input)
+            .arg(work.join("frame-%d.png"))
+            .output()
+            .is_ok_and(|o| o.status.success());
+    if !converted {
+        let _ = fs::remove_dir_all(&work);
+        return Err(t!("error_dynamic_extract").into());
+    }
+
+    // heif-convert 从 1 开始编号（只有一张时不编号），magick 从 0 开始，统一按编号排序
+    let mut files: Vec<(u64, PathBuf)> = fs::read_dir(&work)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "png"))
+        .map(|path| {
+            let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+            let number = stem.rsplit('-').next().and_then(|n| n.parse().ok()).unwrap_or(0);
+            (number, path)
+        })
+        .collect();
+    files.sort();
+
+    let mut frames = Vec::new();
+    for (i, (_, path)) in files.into_iter().enumerate() {
+        let target = dir.join(format!("frame-{:02}.png", i));
+        fs::rename(&path, &target)?;
+        frames.push(target);
+    }
+    let _ = fs::remove_dir_all(&work);
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// {ap: {d: 5, l: 0}, ti: [{i: 0, t: 0.25}, {i: 1, t: 0.75}, {i: 2, t: 1.0}]}
+    const H24: &str = "YnBsaXN0MDDSAQIDCFJhcFJ0adIEBQYHUWRRbBAFEACjCQ0Q0goLBwxRaVF0Iz/QAAAAAAAA0goLDg8QASM/6AAAAAAAANIKCxESEAIjP/AAAAAAAAAIDRATGBocHiAkKSstNjs9RktNAAAAAAAAAQEAAAAAAAAAEwAAAAAAAAAAAAAAAAAAAFY=";
+    /// {si: [{a: -10.5, z: 90.0, i: 0}, {a: 45.0, z: 180.0, i: 1}]}
+    const SOLAR: &str = "YnBsaXN0MDDRAQJSc2miAwrTBAUGBwgJUWFRaVF6I8AlAAAAAAAAEAAjQFaAAAAAAADTBAUGCwwNI0BGgAAAAAAAEAEjQGaAAAAAAAAICw4RGBocHicpMjlCRAAAAAAAAAEBAAAAAAAAAA4AAAAAAAAAAAAAAAAAAABN";
+
+    fn decode(encoded: &str) -> Vec<u8> {
+        base64::engine::general_purpose::STANDARD.decode(encoded).unwrap()
+    }
+
+    #[test]
+    fn h24_attribute() {
+        let xmp = format!("<rdf:Description xmlns:apple_desktop=\"http://ns.apple.com/namespace/1.0/\" apple_desktop:h24=\"{}\"/>", H24);
+        let frames = parse(xmp.as_bytes()).unwrap();
+        let starts: Vec<(usize, u32)> = frames
+            .iter()
+            .map(|(i, time)| match time {
+                FrameTime::Time { start } => (*i, *start),
+                FrameTime::Sun { .. } => panic!("{:?}", time),
+            })
+            .collect();
+        // t = 1.0 回绕到 0 点
+        assert_eq!(starts, [(0, 21600), (1, 64800), (2, 0)]);
+    }
+
+    #[test]
+    fn solar_element() {
+        // 元素形式，base64 中间有换行
+        let xmp = format!("<apple_desktop:solar>{}\n  {}</apple_desktop:solar>", &SOLAR[..40], &SOLAR[40..]);
+        let frames = parse(xmp.as_bytes()).unwrap();
+        assert!(matches!(frames[0], (0, FrameTime::Sun { altitude, azimuth }) if altitude == -10.5 && azimuth == 90.0));
+        assert!(matches!(frames[1], (1, FrameTime::Sun { altitude, azimuth }) if altitude == 45.0 && azimuth == 180.0));
+    }
+
+    #[test]
+    fn missing_metadata() {
+        assert!(parse(b"no xmp here").is_err());
+        assert!(parse(b"apple_desktop:h24=\"bm90IGEgcGxpc3Q=\"").is_err());
+    }
+
+    #[test]
+    fn corrupt_plist_does_not_panic() {
+        let plist = decode(H24);
+        assert!(BPlist::parse(&plist).is_some());
+        let len = plist.len();
+        // 偏移表位置、对象数量、引用和对象长度改成极大值
+        let mut cases = Vec::new();
+        let mut huge_table = plist.clone();
+        huge_table[len - 8..].fill(0xFF);
+        cases.push(huge_table);
+        let mut huge_offset_size = plist.clone();
+        huge_offset_size[len - 26] = 0xFF;
+        cases.push(huge_offset_size);
+        let mut huge_ref_size = plist.clone();
+        huge_ref_size[len - 25] = 0xFF;
+        cases.push(huge_ref_size);
+        // 顶层字典的长度改成 0xF 后跟一个 8 字节的极大整数
+        let mut huge_length = plist.clone();
+        huge_length[8] = 0xDF;
+        huge_length[9] = 0x13;
+        huge_length[10..18].fill(0xFF);
+        cases.push(huge_length);
+        cases.push(plist[..len - 1].to_vec());
+        for (i, data) in cases.iter().enumerate() {
+            assert!(BPlist::parse(data).is_none(), "case {}", i);
+        }
+        // 任意位置的单字节损坏都不会 panic
+        for pos in 0..len {
+            let mut data = plist.clone();
+            data[pos] ^= 0xFF;
+            let _ = BPlist::parse(&data);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+// dynamic/mod.rs — 动态壁纸模块
+// 动态壁纸是一组按一天中的时间切换的图片，可从 GNOME 幻灯片 XML 或 macOS 动态 HEIC 导入，
+// 保存在 wallpaper_dir/dynamic/<名称>/dynamic.json；启用后 schedule --run 或 dynamic apply --watch 按时间切换帧
+pub mod gnome;
+pub mod heic;
+
+use crate::config::AppConfig;
+use crate::solar;
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Timelike, Utc};
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 一天的秒数
+pub const DAY: u32 = 86400;
+
+/// 帧的开始时间
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FrameTime {
+    /// 一天中的第几秒（本地时间）
+    Time { start: u32 },
+    /// 太阳位置（度），每天按所在地的太阳轨迹换算成时间（macOS solar 动态壁纸）
+    Sun { altitude: f64, azimuth: f64 },
+}
+
+/// 动态壁纸中的一帧
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Frame {
+    /// 图片路径，相对路径相对于动态壁纸目录
+    pub file: PathBuf,
+    #[serde(flatten)]
+    pub time: FrameTime,
+}
+
+/// 一组按时间切换的图片
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DynamicSet {
+    pub name: String,
+    pub frames: Vec<Frame>,
+    /// 动态壁纸目录，不写入 dynamic.json
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+/// 所有动态壁纸的根目录 wallpaper_dir/dynamic
+pub fn root(config: &AppConfig) -> PathBuf {
+    config.wallpaper_dir.join("dynamic")
+}
+
+impl DynamicSet {
+    /// 读取 wallpaper_dir/dynamic/<name>/dynamic.json
+    pub fn load(config: &AppConfig, name: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let dir = root(config).join(name);
+        let content = fs::read_to_string(dir.join("dynamic.json"))
+            .map_err(|_| t!("error_dynamic_not_found", name => name))?;
+        let mut set: DynamicSet = serde_json::from_str(&content)?;
+        set.dir = dir;
+        Ok(set)
+    }
+
+    /// 写入 dynamic.json，目录不存在时创建
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join("dynamic.json"), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// 已导入的所有动态壁纸
+    pub fn list(config: &AppConfig) -> Vec<Self> {
+        let Ok(entries) = fs::read_dir(root(config)) else { return Vec::new() };
+        let mut sets: Vec<Self> = entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|name| Self::load(config, &name).ok())
+            .collect();
+        sets.sort_by(|a, b| a.name.cmp(&b.name));
+        sets
+    }
+
+    fn frame_path(&self, frame: &Frame) -> PathBuf {
+        if frame.file.is_absolute() { frame.file.clone() } else { self.dir.join(&frame.file) }
+    }
+
+    /// 某天各帧的开始时间（一天中的第几秒）和图片路径，按时间排序
+    ///
+    /// 按太阳位置定义的帧需要 [schedule] latitude / longitude，未配置时按方位角粗略估算
+    pub fn timeline(&self, date: NaiveDate, location: Option<(f64, f64)>) -> Vec<(u32, PathBuf)> {
+        let mut timeline: Vec<(u32, PathBuf)> = self
+            .frames
+            .iter()
+            .map(|frame| {
+                let start = match frame.time {
+                    FrameTime::Time { start } => start % DAY,
+                    FrameTime::Sun { altitude, azimuth } => sun_frame_start(date, location, altitude, azimuth),
+                };
+                (start, self.frame_path(frame))
+            })
+            .collect();
+        timeline.sort_by_key(|(start, _)| *start);
+        timeline
+    }
+
+    /// 当前应显示的帧，以及距离下一次切换的秒数
+    pub fn current_frame(&self, config: &AppConfig) -> Option<(PathBuf, u32)> {
+        let now = Local::now();
+        let location = config.schedule.latitude.zip(config.schedule.longitude);
+        let timeline = self.timeline(now.date_naive(), location);
+        let seconds = now.num_seconds_from_midnight();
+        // 开始时间不晚于现在的最后一帧；早于第一帧时仍显示前一天的最后一帧
+        let index = timeline.iter().rposition(|(start, _)| *start <= seconds).unwrap_or(timeline.len().checked_sub(1)?);
+        let next = timeline
+            .iter()
+            .map(|(start, _)| *start)
+            .find(|start| *start > seconds)
+            .unwrap_or(timeline[0].0 + DAY);
+        Some((timeline[index].1.clone(), next - seconds))
+    }
+}
+
+/// 按太阳位置定义的帧在当天的开始时间：太阳最接近该高度角和方位角的时刻
+fn sun_frame_start(date: NaiveDate, location: Option<(f64, f64)>, altitude: f64, azimuth: f64) -> u32 {
+    let Some((latitude, longitude)) = location else {
+        // 没有经纬度时按方位角估算：正东约 6 点，正南约 12 点，正西约 18 点
+        return ((12.0 + (azimuth - 180.0) / 15.0).rem_euclid(24.0) * 3600.0) as u32;
+    };
+    // 每 5 分钟采样一次太阳位置，取角距离最小的时刻
+    (0..DAY)
+        .step_by(300)
+        .min_by(|a, b| {
+            let distance = |seconds: u32| {
+                let Some(at) = local_to_utc(date, seconds) else { return f64::MAX };
+                let (alt, az) = solar::sun_position(at, latitude, longitude);
+                let d_az = ((az - azimuth + 180.0).rem_euclid(360.0) - 180.0) * alt.to_radians().cos();
+                (alt - altitude).powi(2) + d_az.powi(2)
+            };
+            distance(*a).total_cmp(&distance(*b))
+        })
+        .unwrap_or(0)
+}
+
+/// 本地日期和当天第几秒对应的 UTC 时刻
+fn local_to_utc(date: NaiveDate, seconds: u32) -> Option<DateTime<Utc>> {
+    let time = date.and_hms_opt(seconds / 3600, seconds / 60 % 60, seconds % 60)?;
+    Some(Local.from_local_datetime(&time).earliest()?.with_timezone(&Utc))
+}
+
+/// 当前启用的动态壁纸名称保存在 state_dir/dynamic
+fn active_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("dynamic")
+}
+
+/// 启用或停用（None）动态壁纸
+pub fn set_active(state_dir: &Path, name: Option<&str>) -> std::io::Result<()> {
+    match name {
+        Some(name) => fs::write(active_path(state_dir), name),
+        None => match fs::remove_file(active_path(state_dir)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// 当前启用的动态壁纸，未启用或已被删除时返回 None
+pub fn active(config: &AppConfig) -> Option<DynamicSet> {
+    let name = fs::read_to_string(active_path(&config.state_dir)).ok()?;
+    DynamicSet::load(config, name.trim()).ok()
+}
+
+/// 把 "HH:MM" 解析为一天中的第几秒
+pub fn parse_time_of_day(s: &str) -> Option<u32> {
+    let time = chrono::NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()?;
+    Some(time.num_seconds_from_midnight())
+}
+
@@ -2,8 +2,10 @@
 // 通过 std::process::Command 调用系统安装的 gowall 二进制文件
 
 use rust_i18n::t;
+use std::fmt;
 use std::path::Path; // 路径的不可变借用类型
 use std::process::Command; // 用于创建和执行子进程 // 引入翻译宏
+use std::str::FromStr;
 
 /// 检测系统是否已安装 gowall 命令行工具
 ///
@@ -131,3 +133,191 @@ pub fn list_themes() -> Result<Vec<String>, Box<dyn std::error::Error>> {
         Err(t!("error_gowall_list_failed", reason => stderr).into())
     }
 }
+
+/// gowall 支持的单图编辑操作
+///
+/// 文本形式为 `名称[:参数]`，用于命令行 `--op` 和配置文件，例如
+/// `invert`、`pixelate:15`、`upscale:4`、`brightness:1.2`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// 反色
+    Invert,
+    /// 像素化，scale 越大像素块越大
+    Pixelate { scale: u32 },
+    /// AI 放大（gowall 调用 Real-ESRGAN），scale 为放大倍数
+    Upscale { scale: u32 },
+    /// 移除背景
+    RemoveBackground,
+    /// 灰度
+    Grayscale,
+    /// 调整亮度，factor > 1 变亮，< 1 变暗
+    Brightness { factor: f32 },
+    /// 上下翻转
+    Flip,
+    /// 左右镜像
+    Mirror,
+}
+
+impl Operation {
+    /// 转换为 gowall 命令行参数（不含输入输出路径）
+    fn args(&self) -> Vec<String> {
+        match self {
+            Operation::Invert => vec!["invert".into()],
+            Operation::Pixelate { scale } => vec!["pixelate".into(), "--scale".into(), scale.to_string()],
+            Operation::Upscale { scale } => vec!["upscale".into(), "--scale".into(), scale.to_string()],
+            Operation::RemoveBackground => vec!["bg".into()],
+            Operation::Grayscale => vec!["effects".into(), "grayscale".into()],
+            Operation::Brightness { factor } => {
+                vec!["effects".into(), "br".into(), "--factor".into(), factor.to_string()]
+            }
+            Operation::Flip => vec!["effects".into(), "flip".into()],
+            Operation::Mirror => vec!["effects".into(), "mirror".into()],
+        }
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Operation::Invert => write!(f, "invert"),
+            Operation::Pixelate { scale } => write!(f, "pixelate:{}", scale),
+            Operation::Upscale { scale } => write!(f, "upscale:{}", scale),
+            Operation::RemoveBackground => write!(f, "remove_bg"),
+            Operation::Grayscale => write!(f, "grayscale"),
+            Operation::Brightness { factor } => write!(f, "brightness:{}", factor),
+            Operation::Flip => write!(f, "flip"),
+            Operation::Mirror => write!(f, "mirror"),
+        }
+    }
+}
+
+impl FromStr for Operation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, arg) = match s.split_once(':') {
+            Some((n, a)) => (n.trim(), Some(a.trim())),
+            None => (s.trim(), None),
+        };
+        let invalid = || t!("error_invalid_operation", op => s).to_string();
+        let int_arg = |default: u32| -> Result<u32, String> {
+            arg.map_or(Ok(default), |a| a.parse().map_err(|_| invalid()))
+        };
+        match name {
+            "invert" => Ok(Operation::Invert),
+            "pixelate" => Ok(Operation::Pixelate { scale: int_arg(15)? }),
+            "upscale" => Ok(Operation::Upscale { scale: int_arg(2)? }),
+            "remove_bg" | "bg" => Ok(Operation::RemoveBackground),
+            "grayscale" => Ok(Operation::Grayscale),
+            "brightness" => {
+                let factor = arg.ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+                Ok(Operation::Brightness { factor })
+            }
+            "flip" => Ok(Operation::Flip),
+            "mirror" => Ok(Operation::Mirror),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// 配置文件中以字符串形式读写，与命令行写法一致
+impl serde::Serialize for Operation {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Operation {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl schemars::JsonSchema for Operation {
+    fn schema_name() -> String {
+        "Operation".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(generator)
+    }
+}
+
+/// 调用 gowall 对图片执行一个编辑操作
+///
+/// # 参数
+/// - `image_path`: 输入图片路径
+/// - `op`: 编辑操作
+/// - `output_path`: 输出路径
+pub fn apply(
+    image_path: impl AsRef<Path>,
+    op: &Operation,
+    output_path: impl AsRef<Path>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let output = Command::new("gowall")
+        .args(op.args())
+        .arg(image_path.as_ref().as_os_str())
+        .arg("--output")
+        .arg(output_path.as_ref().as_os_str())
+        .output()?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        Err(t!("error_gowall_op_failed", op => op.to_string(), reason => stderr).into())
+    }
+}
+
+/// 依次执行多个编辑操作，最终结果写入 `output_path`
+///
+/// 第一个操作读取输入图片，之后每个操作读取上一步的结果；
+/// 中间结果写入同目录的临时文件再重命名，允许输入与输出为同一路径
+pub fn apply_chain(
+    image_path: impl AsRef<Path>,
+    ops: &[Operation],
+    output_path: impl AsRef<Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output_path = output_path.as_ref();
+    let ext = output_path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    let tmp = output_path.with_extension(format!("wallow-tmp.{}", ext));
+    let mut input = image_path.as_ref().to_path_buf();
+    for op in ops {
+        apply(&input, op, &tmp)?;
+        std::fs::rename(&tmp, output_path)?;
+        input = output_path.to_path_buf();
+    }
+    Ok(())
+}
+
+/// 调用 `gowall extract` 提取图片的主色
+///
+/// # 返回值
+/// - `Ok(Vec<String>)`: 十六进制颜色列表
+pub fn extract_palette(
+    image_path: impl AsRef<Path>,
+    colors: u32,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let output = Command::new("gowall")
+        .arg("extract")
+        .arg(image_path.as_ref().as_os_str())
+        .arg("--colors")
+        .arg(colors.to_string())
+        .output()?;
+
+    if output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        // 输出中可能夹杂说明文字，只保留形如 #rrggbb 的片段
+        let hex: Vec<String> = stdout
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .map(|s| s.trim_matches(|c: char| !c.is_ascii_hexdigit() && c != '#'))
+            .filter(|s| s.starts_with('#') && s.len() == 7)
+            .map(|s| s.to_string())
+            .collect();
+        Ok(hex)
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+        Err(t!("error_gowall_op_failed", op => "extract", reason => stderr).into())
+    }
+}
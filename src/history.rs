@@ -0,0 +1,167 @@
+// history.rs — 壁纸历史栈模块
+// 记录 set / apply 设置过的壁纸，保存在 state_dir/history.json，
+// prev / next 在栈中前后移动，undo 丢弃当前壁纸并回到上一张
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 历史栈最多保留的条数，超出时丢弃最旧的
+const MAX_ENTRIES: usize = 100;
+
+/// 历史栈：entries 从旧到新，cursor 指向当前壁纸
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    #[serde(default)]
+    entries: Vec<PathBuf>,
+    #[serde(default)]
+    cursor: usize,
+}
+
+impl History {
+    fn path(state_dir: &Path) -> PathBuf {
+        state_dir.join("history.json")
+    }
+
+    /// 读取历史文件，不存在或损坏时视为空
+    pub fn load(state_dir: &Path) -> Self {
+        fs::read_to_string(Self::path(state_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, state_dir: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(Self::path(state_dir), json)
+    }
+
+    /// 压入新设置的壁纸
+    ///
+    /// 与浏览器的前进 / 后退相同：在历史中间设置新壁纸时，当前位置之后的条目被丢弃
+    pub fn push(&mut self, path: PathBuf) {
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.cursor + 1);
+        }
+        if self.entries.last() != Some(&path) {
+            self.entries.push(path);
+        }
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+        self.cursor = self.entries.len() - 1;
+    }
+
+    /// 向前（-1）或向后（+1）移动，跳过已被删除的文件，返回新的当前壁纸
+    pub fn step(&mut self, delta: isize) -> Option<PathBuf> {
+        let mut index = self.cursor as isize;
+        loop {
+            index += delta;
+            let entry = self.entries.get(usize::try_from(index).ok()?)?;
+            if entry.exists() {
+                self.cursor = index as usize;
+                return Some(entry.clone());
+            }
+        }
+    }
+
+    /// 从历史中移除当前壁纸并回到上一张，返回上一张壁纸
+    pub fn undo(&mut self) -> Option<PathBuf> {
+        let current = self.cursor;
+        // 上一张的下标一定小于 current，移除 current 不影响新游标
+        let previous = self.step(-1)?;
+        self.entries.remove(current);
+        Some(previous)
+    }
+
+    /// 当前位置（从 1 开始）和总条数
+    pub fn position(&self) -> (usize, usize) {
+        (self.cursor + 1, self.entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 临时目录中的空文件，step 只接受存在的文件
+    fn files(names: &[&str]) -> (PathBuf, Vec<PathBuf>) {
+        let dir = std::env::temp_dir().join(format!("wallow-history-test-{}-{}", std::process::id(), names.join("")));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let paths = names
+            .iter()
+            .map(|name| {
+                let path = dir.join(name);
+                fs::write(&path, "").unwrap();
+                path
+            })
+            .collect();
+        (dir, paths)
+    }
+
+    fn history(paths: &[PathBuf]) -> History {
+        let mut history = History::default();
+        for path in paths {
+            history.push(path.clone());
+        }
+        history
+    }
+
+    #[test]
+    fn push_truncates_forward_history() {
+        let (dir, p) = files(&["a", "b", "c", "d"]);
+        let mut history = history(&p[..3]);
+        assert_eq!(history.step(-1), Some(p[1].clone()));
+        assert_eq!(history.step(-1), Some(p[0].clone()));
+        history.push(p[3].clone());
+        assert_eq!(history.entries, [p[0].clone(), p[3].clone()]);
+        assert_eq!(history.position(), (2, 2));
+        assert_eq!(history.step(1), None);
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn push_skips_consecutive_duplicates() {
+        let (dir, p) = files(&["e", "f"]);
+        let history = history(&[p[0].clone(), p[0].clone(), p[1].clone(), p[1].clone(), p[0].clone()]);
+        assert_eq!(history.entries, [p[0].clone(), p[1].clone(), p[0].clone()]);
+        assert_eq!(history.position(), (3, 3));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn push_keeps_max_entries() {
+        let history = history(&(0..MAX_ENTRIES + 5).map(|i| PathBuf::from(format!("/missing/{}", i))).collect::<Vec<_>>());
+        assert_eq!(history.entries.len(), MAX_ENTRIES);
+        assert_eq!(history.entries[0], PathBuf::from("/missing/5"));
+        assert_eq!(history.position(), (MAX_ENTRIES, MAX_ENTRIES));
+    }
+
+    #[test]
+    fn step_skips_missing_files() {
+        let (dir, p) = files(&["g", "h", "i"]);
+        let mut history = history(&p);
+        fs::remove_file(&p[1]).unwrap();
+        assert_eq!(history.step(-1), Some(p[0].clone()));
+        assert_eq!(history.step(-1), None);
+        // 越界时游标保持不变
+        assert_eq!(history.position(), (1, 3));
+        assert_eq!(history.step(1), Some(p[2].clone()));
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn undo_drops_current() {
+        let (dir, p) = files(&["j", "k", "l"]);
+        let mut history = history(&p);
+        assert_eq!(history.undo(), Some(p[1].clone()));
+        assert_eq!(history.entries, [p[0].clone(), p[1].clone()]);
+        assert_eq!(history.position(), (2, 2));
+        assert_eq!(history.undo(), Some(p[0].clone()));
+        // 只剩一张时无法撤销
+        assert_eq!(history.undo(), None);
+        assert_eq!(history.entries, [p[0].clone()]);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,248 @@
+// library.rs — 壁纸库索引模块
+// 记录 wallow 写入的每个文件及其元数据（来源、ID、分辨率、搜索词、主题、日期），保存在 state_dir/library.json
+// 文件名模板可以去掉 wallow- 前缀，clean / list 依靠索引识别 wallow 管理的文件
+
+use crate::source::WallpaperInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 下载壁纸的默认文件名模板
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "wallow-{source}-{id}";
+/// 转换后壁纸的默认文件名模板
+pub const DEFAULT_CONVERTED_TEMPLATE: &str = "wallow-{theme}-{name}";
+
+/// 单个文件的元数据，也是文件名模板占位符的取值来源
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileMeta {
+    #[serde(default)]
+    pub source: String,
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub resolution: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// 下载日期 YYYY-MM-DD
+    #[serde(default)]
+    pub date: String,
+    /// 转换 / 处理前的原图，下载的原图本身为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original: Option<PathBuf>,
+}
+
+impl FileMeta {
+    /// 由搜索结果生成下载文件的元数据，日期取当天
+    pub fn from_info(info: &WallpaperInfo, query: Option<&str>) -> Self {
+        Self {
+            source: info.source.clone(),
+            id: info.id.clone(),
+            resolution: info.resolution.clone(),
+            query: query.map(str::to_string),
+            theme: None,
+            date: chrono::Local::now().format("%Y-%m-%d").to_string(),
+            original: None,
+        }
+    }
+
+    /// 来源 + ID 的 8 位短哈希（FNV-1a），跨版本稳定
+    pub fn short_hash(&self) -> String {
+        fnv_hex(self.source.bytes().chain([b':']).chain(self.id.bytes()))
+    }
+
+    /// 按模板生成文件名（不含扩展名）
+    ///
+    /// 占位符: {source} {id} {theme} {resolution} {date} {query} {hash} {name}，
+    /// 其中 {name} 为输入文件名去掉 wallow- 前缀和扩展名后的部分。
+    /// 值中的路径分隔符、空白等字符替换为 "-"；取值为空的占位符连同多余的分隔符一起去掉
+    pub fn render(&self, template: &str, name: &str) -> String {
+        // 不在索引中的本地图片没有来源和 ID，改用文件名区分
+        let hash = if self.source.is_empty() && self.id.is_empty() {
+            fnv_hex(name.bytes())
+        } else {
+            self.short_hash()
+        };
+        let values = [
+            ("{source}", self.source.as_str()),
+            ("{id}", self.id.as_str()),
+            ("{theme}", self.theme.as_deref().unwrap_or("")),
+            ("{resolution}", self.resolution.as_str()),
+            ("{date}", self.date.as_str()),
+            ("{query}", self.query.as_deref().unwrap_or("")),
+            ("{hash}", hash.as_str()),
+            ("{name}", name),
+        ];
+        let mut result = template.to_string();
+        for (key, value) in values {
+            result = result.replace(key, &sanitize(value));
+        }
+        while result.contains("--") {
+            result = result.replace("--", "-");
+        }
+        let result = result.trim_matches(['-', '_']);
+        if result.is_empty() { "wallow".to_string() } else { result.to_string() }
+    }
+}
+
+/// 替换文件名中不安全的字符
+fn sanitize(value: &str) -> String {
+    value
+        .trim()
+        .chars()
+        .map(|c| if c.is_whitespace() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '-' } else { c })
+        .collect()
+}
+
+/// FNV-1a 哈希的前 8 位十六进制
+fn fnv_hex(bytes: impl Iterator<Item = u8>) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)[..8].to_string()
+}
+
+/// 输入文件名去掉 wallow- 前缀和扩展名，作为 {name} 占位符的值
+pub fn base_name(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+    stem.strip_prefix("wallow-").unwrap_or(stem).to_string()
+}
+
+/// 壁纸库索引，键为文件的完整路径
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Library {
+    #[serde(default)]
+    files: BTreeMap<PathBuf, FileMeta>,
+}
+
+impl Library {
+    fn path(state_dir: &Path) -> PathBuf {
+        state_dir.join("library.json")
+    }
+
+    /// 读取索引文件，不存在或损坏时视为空
+    pub fn load(state_dir: &Path) -> Self {
+        fs::read_to_string(Self::path(state_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// 写回索引文件，顺便去掉已被删除的文件
+    pub fn save(&mut self, state_dir: &Path) -> std::io::Result<()> {
+        self.files.retain(|path, _| path.exists());
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(Self::path(state_dir), json)
+    }
+
+    /// 添加或更新一个文件，需要调用 save 写回
+    pub fn insert(&mut self, path: PathBuf, meta: FileMeta) {
+        self.files.insert(path, meta);
+    }
+
+    /// 记录一个文件并立即写回索引
+    pub fn record(state_dir: &Path, path: &Path, meta: FileMeta) -> std::io::Result<()> {
+        let mut library = Self::load(state_dir);
+        library.insert(path.to_path_buf(), meta);
+        library.save(state_dir)
+    }
+
+    /// 查询文件的元数据，原路径查不到时再按规范化后的路径查询
+    pub fn get(&self, path: &Path) -> Option<&FileMeta> {
+        self.files
+            .get(path)
+            .or_else(|| fs::canonicalize(path).ok().and_then(|p| self.files.get(&p)))
+    }
+
+    /// 文件是否由 wallow 管理：在索引中，或文件名带 wallow- 前缀（兼容索引出现之前的文件）
+    pub fn is_managed(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+            || path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("wallow-"))
+    }
+
+    /// 索引中仍然存在的文件
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.keys().map(PathBuf::as_path).filter(|p| p.exists())
+    }
+
+    /// 从索引中移除文件
+    pub fn remove(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> FileMeta {
+        FileMeta {
+            source: "wallhaven".to_string(),
+            id: "abc123".to_string(),
+            resolution: "2560x1440".to_string(),
+            query: Some("city night".to_string()),
+            theme: Some("catppuccin".to_string()),
+            date: "2024-01-15".to_string(),
+            original: None,
+        }
+    }
+
+    #[test]
+    fn render_placeholders() {
+        let meta = meta();
+        assert_eq!(meta.render(DEFAULT_FILENAME_TEMPLATE, ""), "wallow-wallhaven-abc123");
+        assert_eq!(meta.render(DEFAULT_CONVERTED_TEMPLATE, "wallhaven-abc123"), "wallow-catppuccin-wallhaven-abc123");
+        assert_eq!(
+            meta.render("{date}_{query}_{resolution}", ""),
+            "2024-01-15_city-night_2560x1440"
+        );
+        assert_eq!(meta.render("{hash}", ""), meta.short_hash());
+        assert_eq!(meta.short_hash().len(), 8);
+        // 值中的路径分隔符被替换，不会写到其他目录
+        let meta = FileMeta { query: Some("a/b\\c:d".to_string()), ..meta };
+        assert_eq!(meta.render("{query}", ""), "a-b-c-d");
+    }
+
+    #[test]
+    fn render_drops_empty_placeholders() {
+        let meta = FileMeta { theme: None, query: None, ..meta() };
+        assert_eq!(meta.render("wallow-{theme}-{name}", "photo"), "wallow-photo");
+        assert_eq!(meta.render("{query}-{source}-", ""), "wallhaven");
+        assert_eq!(FileMeta::default().render("{theme}", ""), "wallow");
+        // 没有来源和 ID 时按文件名区分 {hash}
+        assert_ne!(FileMeta::default().render("{hash}", "a"), FileMeta::default().render("{hash}", "b"));
+    }
+
+    #[test]
+    fn base_name_strips_prefix() {
+        assert_eq!(base_name(Path::new("/w/wallow-wallhaven-abc.jpg")), "wallhaven-abc");
+        assert_eq!(base_name(Path::new("/w/photo.png")), "photo");
+    }
+
+    #[test]
+    fn managed_files() {
+        let dir = Path::new("/w");
+        let meta = meta();
+        // 默认模板渲染出的文件名总是带 wallow- 前缀，不在索引中也能识别
+        let library = Library::default();
+        for name in [meta.render(DEFAULT_FILENAME_TEMPLATE, ""), meta.render(DEFAULT_CONVERTED_TEMPLATE, "x")] {
+            assert!(library.is_managed(&dir.join(format!("{}.jpg", name))), "{}", name);
+        }
+        // 去掉前缀的模板只能靠索引识别，用户自己的文件不受影响
+        let custom = dir.join(format!("{}.jpg", meta.render("{theme}-{id}", "")));
+        let foreign = dir.join("catppuccin-holiday.jpg");
+        assert!(!library.is_managed(&custom));
+        let mut library = Library::default();
+        library.insert(custom.clone(), meta);
+        assert!(library.is_managed(&custom));
+        assert!(!library.is_managed(&foreign));
+        assert!(!library.is_managed(&dir.join("my-wallow-photo.jpg")));
+    }
+}
@@ -3,20 +3,42 @@
 
 extern crate libc;
 
+mod appearance; // 系统浅色 / 深色模式
 mod cli; // 声明 cli 模块，对应 src/cli.rs
 mod config; // 声明 config 模块，对应 src/config.rs
+mod cron; // cron 表达式解析
+mod current; // 当前壁纸符号链接与状态文件
+mod dynamic; // 按时间切换的动态壁纸
 mod gowall; // 声明 gowall 模块，对应 src/gowall.rs
+mod history; // 壁纸历史栈（prev / next / undo）
+mod library; // 壁纸库索引与文件名模板
+mod output; // 转换目录的输出与旧文件清理
+mod palette; // 终端配色提取与导出
+mod pipeline; // 图片后处理流水线
+mod recolor; // 内置主题转换引擎
+mod rotation; // 加权随机与轮询选择
+mod schedule; // 定时任务后端（crontab / systemd / launchd）
+mod screen; // 屏幕分辨率与显示器检测
+mod select; // 搜索结果的选图策略
 mod setter;
+mod slots; // 定时任务的时间段
+mod solar; // 日出日落计算
 mod source;
+mod themes; // 用户自定义主题
 
 // 初始化多语言支持，嵌入 locales 目录下的所有翻译
 rust_i18n::i18n!("locales");
 
+use appearance::{ColorScheme, SchemeChoice};
 use clap::{CommandFactory, Parser}; // 引入 Parser trait 的 parse() 方法; CommandFactory 用于生成补全脚本
 use clap_complete::generate; // 引入补全脚本生成函数
 use cli::{Cli, Commands}; // 引入 CLI 结构体和子命令枚举
-use config::{AppConfig, StringOrVec}; // 引入应用配置
+use config::{AppConfig, ConvertBackend, MonitorLayout, ScheduleBackend, StringOrVec, WallpaperMode}; // 引入应用配置
+use cron::CronExpr;
+use history::History;
+use library::{FileMeta, Library};
 use rust_i18n::t; // 引入翻译宏
+use select::SelectionStrategy;
 use source::{SearchOptions, WallpaperSource};
 use source::wallhaven::WallhavenClient;
 use source::unsplash::UnsplashClient; // 引入 Unsplash API 客户端
@@ -51,6 +73,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             sorting,
             count,
             source,
+            select,
         } => {
             handle_fetch(
                 &config,
@@ -60,7 +83,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 purity.as_deref(),
                 sorting.as_deref(),
                 *count,
-                source.as_deref().unwrap_or(&config.default_source),
+                &resolve_source(&config, source.as_deref()),
+                *select,
             )
             .await?;
         }
@@ -70,17 +94,50 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             theme,
             output,
         } => {
-            gowall::check_installed()?;
+            ensure_convert_backend(&config)?;
             handle_convert(&config, image, theme, output.as_deref())?;
         }
 
-        Commands::Themes => {
+        Commands::Edit {
+            image,
+            ops,
+            extract,
+            output,
+        } => {
             gowall::check_installed()?;
-            handle_themes()?;
+            handle_edit(&config, image, ops, *extract, output.as_deref())?;
+        }
+
+        Commands::Palette {
+            image,
+            format,
+            output,
+        } => {
+            handle_palette(image, *format, output.as_deref())?;
+        }
+
+        Commands::Themes => {
+            ensure_convert_backend(&config)?;
+            handle_themes(&config)?;
         }
 
-        Commands::Schedule { cron } => {
-            handle_schedule(&mut config, cron.as_deref()).await?;
+        Commands::Schedule { action, cron, run, slots, backend, print, remove } => {
+            if *run {
+                // 记录本次结果供 schedule status 查看，记录失败不影响退出码
+                let started_at = chrono::Local::now();
+                let previous = current::load(&config).map(|c| (c.set_at, c.path));
+                let result = handle_scheduled_run(&config).await;
+                schedule::finish_run(&config, started_at, previous, result.as_ref().err().map(|e| e.to_string()));
+                result?;
+            } else if *slots {
+                handle_slots(&config)?;
+            } else if *remove || matches!(action, Some(cli::ScheduleAction::Remove)) {
+                handle_schedule_remove()?;
+            } else if matches!(action, Some(cli::ScheduleAction::Status)) {
+                handle_schedule_status(&config)?;
+            } else {
+                handle_schedule(&mut config, cron.as_deref(), *backend, *print).await?;
+            }
         }
 
         Commands::Completions { shell } => {
@@ -100,37 +157,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             purity,
             sorting,
             source,
+            select,
         } => {
-            gowall::check_installed()?;
+            ensure_convert_backend(&config)?;
             handle_run(
                 &config,
                 query.as_deref(),
                 theme.as_deref(),
-                resolution.as_deref(),
+                resolution.as_deref().map(|r| resolve_resolution(&config, r)),
                 categories.as_deref(),
                 purity.as_deref(),
                 sorting.as_deref(),
-                source.as_deref().unwrap_or(&config.default_source),
+                &resolve_source(&config, source.as_deref()),
+                *select,
             )
             .await?;
         }
 
-        Commands::Set { query, theme, source } => {
-            let image_path = handle_run(
+        Commands::Set { query, theme, source, select, monitor, layout, mode } => {
+            handle_set(
                 &config,
                 query.as_deref(),
                 theme.as_deref(),
-                None,
-                None,
-                None,
-                None,
-                source.as_deref().unwrap_or(&config.default_source),
+                &resolve_source(&config, source.as_deref()),
+                *select,
+                monitor.as_deref(),
+                layout.unwrap_or(config.setter.layout),
+                mode.unwrap_or(config.setter.mode),
             )
             .await?;
-
-            println!("{}", t!("setting_wallpaper"));
-            setter::set_from_path(&image_path)?;
-            println!("{}", t!("set_done"));
         }
         Commands::Config { action } => {
             handle_config(&mut config, action)?;
@@ -144,34 +199,71 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         Commands::Uninstall { keep_wallpapers } => {
             handle_uninstall(&config, *keep_wallpapers)?;
         }
-        Commands::List { fzf } => {
-            handle_list(&config, *fzf)?;
+        Commands::List { fzf, mode } => {
+            handle_list(&config, *fzf, mode.unwrap_or(config.setter.mode))?;
+        }
+        Commands::Apply { image, monitor, layout, mode } => {
+            handle_apply(
+                &config,
+                image,
+                monitor.as_deref(),
+                layout.unwrap_or(config.setter.layout),
+                mode.unwrap_or(config.setter.mode),
+            )?;
+        }
+        Commands::Current { json, path } => {
+            handle_current(&config, *json, *path)?;
+        }
+        Commands::Prev => {
+            handle_history(&config, HistoryMove::Prev)?;
+        }
+        Commands::Next => {
+            handle_history(&config, HistoryMove::Next)?;
         }
-        Commands::Apply { image } => {
-            handle_apply(image)?;
+        Commands::Undo => {
+            handle_history(&config, HistoryMove::Undo)?;
+        }
+        Commands::Mode { scheme, watch, interval } => {
+            handle_mode(&config, *scheme, *watch, *interval)?;
+        }
+        Commands::Dynamic { action } => {
+            handle_dynamic(&config, action)?;
         }
     }
 
     Ok(())
 }
+/// 解析本次使用的壁纸来源：命令行参数 > 配置（支持加权随机 / 轮询）
+fn resolve_source(config: &AppConfig, source: Option<&str>) -> String {
+    match source {
+        Some(s) => s.to_string(),
+        None => rotation::pick(config, "source", &config.default_source).unwrap_or("wallhaven").to_string(),
+    }
+}
+
 /// 处理 list 子命令：列出已下载的壁纸，可选 fzf 交互预览
-fn handle_list(config: &AppConfig, use_fzf: bool) -> Result<(), Box<dyn std::error::Error>> {
+fn handle_list(config: &AppConfig, use_fzf: bool, mode: WallpaperMode) -> Result<(), Box<dyn std::error::Error>> {
     // 收集壁纸目录和转换目录中的所有图片文件
     let mut images: Vec<std::path::PathBuf> = Vec::new();
-    for dir in std::iter::once(&config.wallpaper_dir).chain(config.converted_dirs.iter()) {
+    for dir in std::iter::once(&config.wallpaper_dir).chain(config.converted_dirs.iter().map(|d| &d.path)) {
         if dir.exists() {
             for entry in std::fs::read_dir(dir)? {
                 let path = entry?.path();
-                if path.is_file() {
-                    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                        if matches!(ext.to_lowercase().as_str(), "jpg" | "jpeg" | "png" | "webp") {
-                            images.push(path);
-                        }
-                    }
+                if path.is_file()
+                    && let Some(ext) = path.extension().and_then(|e| e.to_str())
+                    && matches!(ext.to_lowercase().as_str(), "jpg" | "jpeg" | "png" | "webp")
+                {
+                    images.push(path);
                 }
             }
         }
     }
+    // 通过 convert -o 等方式写到其他目录的文件由壁纸库索引记录
+    for path in Library::load(&config.state_dir).paths() {
+        if !images.iter().any(|p| p == path) {
+            images.push(path.to_path_buf());
+        }
+    }
     if images.is_empty() {
         println!("{}", t!("no_wallpapers"));
         return Ok(());
@@ -220,17 +312,12 @@ fn handle_list(config: &AppConfig, use_fzf: bool) -> Result<(), Box<dyn std::err
         .arg(&shell_cmd)
         .status()
         .map_err(|_| t!("fzf_error"))?;
-    if status.success() {
-        if tmp.exists() {
-            let selected = std::fs::read_to_string(&tmp)?;
-            let selected = selected.trim().to_string();
-            let _ = std::fs::remove_file(&tmp);
-            if !selected.is_empty() {
-                println!("{}", t!("setting_wallpaper"));
-                let path = std::path::PathBuf::from(&selected);
-                setter::set_from_path(&path)?;
-                println!("{}", t!("set_done"));
-            }
+    if status.success() && tmp.exists() {
+        let selected = std::fs::read_to_string(&tmp)?;
+        let selected = selected.trim().to_string();
+        let _ = std::fs::remove_file(&tmp);
+        if !selected.is_empty() {
+            apply_wallpaper(config, std::path::Path::new(&selected), None, mode, true)?;
         }
     }
     Ok(())
@@ -315,22 +402,259 @@ fn which_exists(cmd: &str) -> bool {
 }
 
 /// 处理 apply 子命令：将本地文件设为壁纸
-fn handle_apply(image: &str) -> Result<(), Box<dyn std::error::Error>> {
+///
+/// span 布局把图片切成每个显示器一块；per_monitor 只有一张图片可用，与 same 相同
+fn handle_apply(
+    config: &AppConfig,
+    image: &str,
+    monitor: Option<&str>,
+    layout: MonitorLayout,
+    mode: WallpaperMode,
+) -> Result<(), Box<dyn std::error::Error>> {
     let path = std::path::PathBuf::from(image);
     if !path.exists() {
-        return Err(format!("文件不存在: {}", image).into());
+        return Err(t!("error_file_not_found", path => image).into());
+    }
+    if monitor.is_none() && layout == MonitorLayout::Span {
+        let monitors = require_monitors(config)?;
+        let outputs = setter::render::span_slices(config, &path, &monitors)?;
+        return apply_outputs(config, &outputs, WallpaperMode::Crop);
+    }
+    apply_wallpaper(config, &path, monitor, mode, true)
+}
+
+/// 处理 set 子命令：按多显示器布局下载、转换并设置壁纸
+///
+/// - 指定 --monitor：下载一张不低于该显示器分辨率的图片，只设置该显示器
+/// - same：下载一张设置到所有显示器
+/// - per_monitor：每个显示器分别下载一张不低于各自分辨率的图片
+/// - span：下载一张不低于所有显示器拼成的整体分辨率的图片，切开后分别设置
+#[allow(clippy::too_many_arguments)]
+async fn handle_set(
+    config: &AppConfig,
+    query: Option<&str>,
+    theme: Option<&str>,
+    source: &str,
+    selection: Option<SelectionStrategy>,
+    monitor: Option<&str>,
+    layout: MonitorLayout,
+    mode: WallpaperMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // 显示器尺寸作为最低分辨率搜索，更大的图片设置时会被裁剪
+    let run = |resolution: Option<String>| async move {
+        let resolution = resolution.map(|r| (r, true));
+        handle_run(config, query, theme, resolution, None, None, None, source, selection).await
+    };
+
+    if let Some(name) = monitor {
+        // 检测不到显示器时仍然尝试设置，由后端决定是否认识这个名字
+        let resolution = setter::monitors(config)?.into_iter().find(|m| m.name == name).map(|m| m.resolution());
+        let image_path = run(resolution).await?;
+        return apply_wallpaper(config, &image_path, Some(name), mode, true);
+    }
+    match layout {
+        MonitorLayout::Same => {
+            let image_path = run(None).await?;
+            apply_wallpaper(config, &image_path, None, mode, true)
+        }
+        MonitorLayout::PerMonitor => {
+            let mut outputs = Vec::new();
+            for m in require_monitors(config)? {
+                println!("[{}]", m.name);
+                outputs.push((run(Some(m.resolution())).await?, m.name));
+            }
+            apply_outputs(config, &outputs, mode)
+        }
+        MonitorLayout::Span => {
+            let monitors = require_monitors(config)?;
+            let (_, _, width, height) = screen::bounding_box(&monitors).ok_or(t!("error_no_monitors"))?;
+            let image_path = run(Some(format!("{}x{}", width, height))).await?;
+            let outputs = setter::render::span_slices(config, &image_path, &monitors)?;
+            apply_outputs(config, &outputs, WallpaperMode::Crop)
+        }
+    }
+}
+
+/// 获取显示器列表，一个都没有时报错
+fn require_monitors(config: &AppConfig) -> Result<Vec<screen::Monitor>, Box<dyn std::error::Error>> {
+    let monitors = setter::monitors(config)?;
+    if monitors.is_empty() {
+        return Err(t!("error_no_monitors").into());
     }
+    Ok(monitors)
+}
+
+/// 设置系统壁纸并更新当前壁纸状态；record 为 true 时压入历史栈（prev / next 移动时不压入）
+///
+/// monitor 为 Some 时只设置该显示器
+fn apply_wallpaper(
+    config: &AppConfig,
+    path: &std::path::Path,
+    monitor: Option<&str>,
+    mode: WallpaperMode,
+    record: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", t!("setting_wallpaper"));
-    setter::set_from_path(&path)?;
+    setter::set_from_path(config, path, monitor, mode)?;
+    let current = current::update(config, path, monitor)?;
+    if record {
+        let mut history = History::load(&config.state_dir);
+        history.push(current.path);
+        history.save(&config.state_dir)?;
+    }
     println!("{}", t!("set_done"));
     Ok(())
 }
 
+/// 为多个显示器分别设置壁纸并更新各自的当前壁纸链接
+///
+/// 每个显示器的图片不同，不压入历史栈
+fn apply_outputs(
+    config: &AppConfig,
+    outputs: &[(std::path::PathBuf, String)],
+    mode: WallpaperMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", t!("setting_wallpaper"));
+    setter::set_outputs(config, outputs, mode)?;
+    for (path, monitor) in outputs {
+        current::update(config, path, Some(monitor))?;
+    }
+    println!("{}", t!("set_done"));
+    Ok(())
+}
+
+/// 历史栈操作
+enum HistoryMove {
+    Prev,
+    Next,
+    Undo,
+}
+
+/// 处理 prev / next / undo 子命令：在历史栈中移动并设置对应壁纸
+fn handle_history(config: &AppConfig, action: HistoryMove) -> Result<(), Box<dyn std::error::Error>> {
+    let mut history = History::load(&config.state_dir);
+    let target = match action {
+        HistoryMove::Prev => history.step(-1).ok_or(t!("history_at_oldest"))?,
+        HistoryMove::Next => history.step(1).ok_or(t!("history_at_newest"))?,
+        HistoryMove::Undo => history.undo().ok_or(t!("history_at_oldest"))?,
+    };
+    apply_wallpaper(config, &target, None, config.setter.mode, false)?;
+    history.save(&config.state_dir)?;
+    let (position, total) = history.position();
+    println!("{}", t!("history_position", position => position, total => total));
+    Ok(())
+}
+
+/// 处理 mode 子命令：查看或手动设置配色方案，--watch 时持续监视并在切换时更新壁纸
+fn handle_mode(
+    config: &AppConfig,
+    choice: Option<SchemeChoice>,
+    watch: bool,
+    interval: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let before = appearance::detect(&config.state_dir).map(|(scheme, _)| scheme);
+    if let Some(choice) = choice {
+        let scheme = match choice {
+            SchemeChoice::Light => Some(ColorScheme::Light),
+            SchemeChoice::Dark => Some(ColorScheme::Dark),
+            SchemeChoice::Auto => None,
+        };
+        appearance::set_override(&config.state_dir, scheme)?;
+        match scheme {
+            Some(scheme) => println!("{}", t!("mode_set", scheme => scheme.as_str())),
+            None => println!("{}", t!("mode_auto")),
+        }
+    }
+    let mut last = appearance::detect(&config.state_dir);
+    match last {
+        Some((scheme, source)) => println!("{}", t!("mode_current", scheme => scheme.as_str(), source => source)),
+        None => println!("{}", t!("mode_unknown")),
+    }
+    if let Some((scheme, _)) = last
+        && choice.is_some()
+        && before != Some(scheme)
+    {
+        refresh_for_scheme(config, scheme)?;
+    }
+    if !watch {
+        return Ok(());
+    }
+
+    println!("{}", t!("mode_watching", interval => interval));
+    loop {
+        std::thread::sleep(std::time::Duration::from_secs(interval.max(1)));
+        let now = appearance::detect(&config.state_dir);
+        if now.map(|(scheme, _)| scheme) != last.map(|(scheme, _)| scheme)
+            && let Some((scheme, _)) = now
+        {
+            println!("{}", t!("mode_changed", scheme => scheme.as_str()));
+            // 转换或设置失败时继续监视，下次切换时再试
+            if let Err(e) = refresh_for_scheme(config, scheme) {
+                eprintln!("{}", e);
+            }
+        }
+        last = now;
+    }
+}
+
+/// 按配色方案对应的主题重新转换当前壁纸的原图并设置
+///
+/// 对应配色方案没有主题时（theme_light / theme_dark 和 theme 都未配置）恢复原图
+fn refresh_for_scheme(config: &AppConfig, scheme: ColorScheme) -> Result<(), Box<dyn std::error::Error>> {
+    if !appearance::is_configured(config) {
+        println!("{}", t!("mode_not_configured"));
+        return Ok(());
+    }
+    let Some(current) = current::load(config) else { return Ok(()) };
+    if !current.original.exists() {
+        return Err(t!("error_file_not_found", path => current.original.display()).into());
+    }
+    let target = match appearance::pick_theme(config, Some(scheme)) {
+        Some(theme) => {
+            ensure_convert_backend(config)?;
+            let original = current.original.to_str().ok_or(t!("error_utf8"))?;
+            handle_convert(config, original, theme, None)?
+        }
+        None => current.original.clone(),
+    };
+    apply_wallpaper(config, &target, current.monitor.as_deref(), config.setter.mode, false)
+}
+
+/// 处理 current 子命令：输出当前壁纸信息
+fn handle_current(config: &AppConfig, json: bool, path_only: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let current = current::load(config).ok_or(t!("current_none"))?;
+    if path_only {
+        println!("{}", current.path.display());
+    } else if json {
+        println!("{}", serde_json::to_string_pretty(&current)?);
+    } else {
+        println!("{}: {}", t!("current_path"), current.path.display());
+        println!("{}: {}", t!("current_link"), current::link_path(config).display());
+        if let Some(source) = &current.source {
+            let id = current.id.as_deref().unwrap_or("-");
+            println!("{}: {} ({})", t!("current_source"), source, id);
+        }
+        if let Some(monitor) = &current.monitor {
+            println!("{}: {}", t!("current_monitor"), monitor);
+        }
+        if let Some(theme) = &current.theme {
+            println!("{}: {}", t!("current_theme"), theme);
+        }
+        if current.original != current.path {
+            println!("{}: {}", t!("current_original"), current.original.display());
+        }
+        println!("{}: {}", t!("current_set_at"), current.set_at);
+    }
+    Ok(())
+}
+
 /// 处理 clean 子命令：清理所有以 wallow- 开头的文件
 fn handle_clean(config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
-    let dirs: Vec<&std::path::PathBuf> = std::iter::once(&config.wallpaper_dir).chain(config.converted_dirs.iter()).collect();
+    let dirs: Vec<&std::path::PathBuf> = std::iter::once(&config.wallpaper_dir).chain(config.converted_dirs.iter().map(|d| &d.path)).collect();
 
     let mut deleted_count = 0;
+    // 索引中的文件即使去掉了 wallow- 前缀也会被清理
+    let mut library = Library::load(&config.state_dir);
 
     for dir in dirs {
         if !dir.exists() {
@@ -343,23 +667,25 @@ fn handle_clean(config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
             let entry = entry?;
             let path = entry.path();
 
-            if path.is_file() {
-                if let Some(filename) = path.file_name().and_then(|n| n.to_str()) {
-                    if filename.starts_with("wallow-") {
-                        std::fs::remove_file(&path)?;
-                        deleted_count += 1;
-                        println!("  {} {}", t!("deleted"), filename);
-                    }
-                }
+            if path.is_file()
+                && library.is_managed(&path)
+                && let Some(filename) = path.file_name().and_then(|n| n.to_str())
+            {
+                std::fs::remove_file(&path)?;
+                library.remove(&path);
+                deleted_count += 1;
+                println!("  {} {}", t!("deleted"), filename);
             }
         }
     }
+    let _ = library.save(&config.state_dir);
 
     println!("{}", t!("clean_done", count => deleted_count));
     Ok(())
 }
 
 /// 处理 fetch 子命令：搜索并下载壁纸
+#[allow(clippy::too_many_arguments)]
 async fn handle_fetch(
     config: &AppConfig,
     query: Option<&str>,
@@ -369,37 +695,35 @@ async fn handle_fetch(
     sorting: Option<&str>,
     count: usize,
     source: &str,
+    selection: Option<SelectionStrategy>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // 获取有效的 query：优先使用命令行参数，否则从配置中获取（支持随机选择）
-    let effective_query = query.or_else(|| {
-        config.search_defaults.query.as_ref().map(|q| q.pick_random())
-    });
+    // 获取有效的 query：优先使用命令行参数，否则从配置中获取（支持加权随机 / 轮询，区分浅色 / 深色模式）
+    let scheme = appearance::current(config);
+    let effective_query = query.or_else(|| appearance::pick_query(config, scheme));
 
+    let (resolution, atleast) = resolve_resolution(config, resolution.unwrap_or(&config.search_defaults.resolution));
     let options = SearchOptions {
         query: effective_query,
-        resolution: resolution.unwrap_or(&config.search_defaults.resolution),
+        resolution: &resolution,
+        atleast,
         categories: categories.unwrap_or(&config.search_defaults.categories),
         purity: purity.unwrap_or(&config.search_defaults.purity),
         sorting: sorting.unwrap_or(&config.search_defaults.sorting),
     };
 
     // 根据 source 参数选择对应的壁纸源客户端
-    let wallpapers: Vec<source::WallpaperInfo> = match source {
-        "unsplash" => {
-            let key = config.unsplash_access_key.clone()
-                .ok_or("Unsplash Access Key 未配置，请在 config.toml 的 [source.unsplash] 中设置 access_key，或设置 UNSPLASH_ACCESS_KEY 环境变量")?;
-            UnsplashClient::new(key).search(options).await?
-        }
-        _ => {
-            WallhavenClient::new(config.api_key.clone()).search(options).await?
-        }
-    };
+    let client = build_source(config, source)?;
+    let strategy = selection.unwrap_or(config.search_defaults.selection);
+    let target = selection_target(config, strategy, options.resolution);
+    let wallpapers = client.search(options).await?;
 
     if wallpapers.is_empty() {
         println!("{}", t!("no_wallpapers"));
         return Ok(());
     }
 
+    // 按选择策略排序后取前 count 张
+    let wallpapers = select::order(wallpapers, strategy, target, &config.state_dir);
     let selected: Vec<&source::WallpaperInfo> = wallpapers.iter().take(count).collect();
     let total = count.min(wallpapers.len());
 
@@ -415,15 +739,7 @@ async fn handle_fetch(
             )
         );
 
-        let save_path = match source {
-            "unsplash" => {
-                let key = config.unsplash_access_key.clone().unwrap();
-                UnsplashClient::new(key).download(wallpaper, &config.wallpaper_dir).await?
-            }
-            _ => {
-                WallhavenClient::new(config.api_key.clone()).download(wallpaper, &config.wallpaper_dir).await?
-            }
-        };
+        let save_path = download_wallpaper(config, client.as_ref(), wallpaper, effective_query).await?;
         println!("{}", t!("save_path", path => save_path.display()));
     }
 
@@ -431,134 +747,375 @@ async fn handle_fetch(
     Ok(())
 }
 
-/// 处理 convert 子命令：调用 gowall 转换壁纸主题
+/// 按 filename_template 命名并下载壁纸到 wallpaper_dir，记录到壁纸库索引和已看过历史
+async fn download_wallpaper(
+    config: &AppConfig,
+    client: &dyn WallpaperSource,
+    wallpaper: &source::WallpaperInfo,
+    query: Option<&str>,
+) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    let meta = FileMeta::from_info(wallpaper, query);
+    let template = config.filename_template.as_deref().unwrap_or(library::DEFAULT_FILENAME_TEMPLATE);
+    let save_path = client.download(wallpaper, &config.wallpaper_dir, &meta.render(template, "")).await?;
+    let _ = Library::record(&config.state_dir, &save_path, meta);
+    let _ = select::SeenHistory::record(&config.state_dir, wallpaper);
+    Ok(save_path)
+}
+
+/// 根据来源名称创建对应的壁纸源客户端
+fn build_source(
+    config: &AppConfig,
+    source: &str,
+) -> Result<Box<dyn WallpaperSource>, Box<dyn std::error::Error>> {
+    match source {
+        "unsplash" => {
+            let key = config.unsplash_access_key.clone()
+                .ok_or("Unsplash Access Key 未配置，请在 config.toml 的 [source.unsplash] 中设置 access_key，或设置 UNSPLASH_ACCESS_KEY 环境变量")?;
+            Ok(Box::new(UnsplashClient::new(key)))
+        }
+        _ => Ok(Box::new(WallhavenClient::new(config.api_key.clone()))),
+    }
+}
+
+/// 选择策略需要的目标屏幕分辨率
+/// 只有 closest_aspect 需要检测屏幕，检测失败时退回搜索分辨率
+fn selection_target(config: &AppConfig, strategy: SelectionStrategy, resolution: &str) -> (u32, u32) {
+    if strategy == SelectionStrategy::ClosestAspect {
+        screen::screen_resolution(config.search_defaults.screen.as_deref())
+            .unwrap_or_else(|| source::parse_resolution(resolution))
+    } else {
+        (0, 0)
+    }
+}
+
+/// 解析搜索分辨率，返回 (分辨率, 是否作为最低分辨率)
+///
+/// "auto" 使用屏幕分辨率作为最低分辨率，检测不到时不限制分辨率；其他值原样精确匹配
+fn resolve_resolution(config: &AppConfig, resolution: &str) -> (String, bool) {
+    if !resolution.eq_ignore_ascii_case("auto") {
+        return (resolution.to_string(), false);
+    }
+    match screen::screen_resolution(config.search_defaults.screen.as_deref()) {
+        Some((w, h)) => (format!("{}x{}", w, h), true),
+        None => {
+            eprintln!("{}", t!("resolution_auto_failed"));
+            (String::new(), true)
+        }
+    }
+}
+
+/// 使用 gowall 转换引擎时检查 gowall 是否已安装，内置引擎无需检查
+fn ensure_convert_backend(config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
+    match config.convert.backend {
+        ConvertBackend::Gowall => gowall::check_installed(),
+        ConvertBackend::Native => Ok(()),
+    }
+}
+
+/// 处理 convert 子命令：调用转换引擎转换壁纸主题
+///
+/// 未指定 output 时写入所有 converted_dirs，返回第一个目录中的路径
 fn handle_convert(
     config: &AppConfig,
     image: &str,
     theme: &str,
     output: Option<&str>,
 ) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    let Some(out) = output else { return convert_to_dirs(config, image, Some(theme), &[]) };
     println!("{}", t!("convert_start", image => image, theme => theme));
 
     let input_path = std::path::Path::new(image);
-    let original_filename = input_path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("image.jpg");
-
-    // 生成带主题前缀的文件名
-    // 如果原名是 wallow-wallhaven-xxx.jpg，改为 wallow-catppuccin-wallhaven-xxx.jpg
-    let new_filename = if original_filename.starts_with("wallow-") {
-        format!("wallow-{}-{}", theme, &original_filename[7..])
-    } else {
-        format!("wallow-{}-{}", theme, original_filename)
-    };
+    let ext = input_path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    let mut library = Library::load(&config.state_dir);
+    let meta = converted_meta(&library, input_path, Some(theme));
 
     // 确定输出完整路径
-    let output_file_path = if let Some(out) = output {
-        let p = std::path::PathBuf::from(out);
-        if p.is_dir() { p.join(new_filename) } else { p }
+    let p = std::path::PathBuf::from(out);
+    let output_file_path = if p.is_dir() {
+        let default_template = config.converted_template.as_deref().unwrap_or(library::DEFAULT_CONVERTED_TEMPLATE);
+        p.join(format!("{}.{}", meta.render(default_template, &library::base_name(input_path)), ext))
     } else {
-        // 向所有 converted_dirs 写入，返回第一个目录的路径
-        let first = config.converted_dirs.first()
-            .ok_or("converted_dirs 为空")?;
-        let primary = first.join(&new_filename);
-        gowall::convert(image, theme, Some(primary.to_str().unwrap()))?;
-        // 将转换结果复制到其余目录
-        for extra_dir in config.converted_dirs.iter().skip(1) {
-            let dest = extra_dir.join(&new_filename);
-            std::fs::copy(&primary, &dest)?;
-        }
-        println!("{}", t!("convert_done", path => primary.display()));
-        return Ok(primary);
+        p
     };
 
-    gowall::convert(image, theme, Some(output_file_path.to_str().unwrap()))?;
+    themes::convert(config, image, theme, &output_file_path)?;
+    library.insert(output_file_path.clone(), meta);
+    let _ = library.save(&config.state_dir);
 
     println!("{}", t!("convert_done", path => output_file_path.display()));
     Ok(output_file_path)
 }
 
-/// 处理 themes 子命令：列出所有可用主题
-fn handle_themes() -> Result<(), Box<dyn std::error::Error>> {
-    let themes = gowall::list_themes()?;
-    println!("{}", t!("themes_title", count => themes.len()));
+/// 转换结果的元数据：输入文件在壁纸库索引中时沿用其元数据（来源、ID 等），供文件名模板使用
+fn converted_meta(library: &Library, input_path: &std::path::Path, theme: Option<&str>) -> FileMeta {
+    let base_meta = library.get(input_path).cloned().unwrap_or_default();
+    FileMeta {
+        theme: theme.map(str::to_string),
+        original: base_meta.original.clone().or_else(|| std::fs::canonicalize(input_path).ok()),
+        ..base_meta
+    }
+}
+
+/// 把图片写入所有 converted_dirs，每个目录可以有自己的主题、尺寸、格式和文件名，返回第一个目录中的路径
+///
+/// 每个主题只转换一次，再执行 steps（[[pipeline]]），结果暂存在 state_dir/tmp，最后按各目录的设置写出。
+/// theme 为 None 且目录也没有指定主题时不转换，只执行 steps
+fn convert_to_dirs(
+    config: &AppConfig,
+    image: &str,
+    theme: Option<&str>,
+    steps: &[pipeline::StepConfig],
+) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
+    if let Some(theme) = theme {
+        println!("{}", t!("convert_start", image => image, theme => theme));
+    }
+    if config.converted_dirs.is_empty() {
+        return Err(t!("error_converted_dirs_empty").into());
+    }
+
+    let input_path = std::path::Path::new(image);
+    let ext = input_path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    // 去掉 wallow- 前缀的原文件名作为 {name}
+    // 如果原名是 wallow-wallhaven-xxx.jpg，默认输出为 wallow-catppuccin-wallhaven-xxx.jpg
+    let name = library::base_name(input_path);
+    let default_template = config.converted_template.as_deref().unwrap_or(library::DEFAULT_CONVERTED_TEMPLATE);
+    let mut library = Library::load(&config.state_dir);
+    let work_dir = config.state_dir.join("tmp");
+    std::fs::create_dir_all(&work_dir)?;
+
+    let mut prepared: std::collections::HashMap<Option<&str>, std::path::PathBuf> = std::collections::HashMap::new();
+    let mut tmp_files = Vec::new();
+    let mut written = Vec::new();
+    let result = config.converted_dirs.iter().try_for_each(|dir| -> Result<(), Box<dyn std::error::Error>> {
+        let dir_theme = dir.variant.theme.as_deref().or(theme);
+        let src = match prepared.get(&dir_theme) {
+            Some(p) => p.clone(),
+            None => {
+                // 中间文件与输入文件不同名，流水线不会覆盖转换结果，更不会覆盖下载的原图
+                let key = dir_theme.unwrap_or("original").replace(['/', '\\'], "_");
+                let mut p = input_path.to_path_buf();
+                if let Some(dir_theme) = dir_theme {
+                    p = work_dir.join(format!("convert-{}.{}", key, ext));
+                    tmp_files.push(p.clone());
+                    themes::convert(config, image, dir_theme, &p)?;
+                }
+                if !steps.is_empty() {
+                    p = pipeline::run(config, steps, &p, &work_dir, &format!("processed-{}", key))?;
+                    tmp_files.push(p.clone());
+                }
+                prepared.insert(dir_theme, p.clone());
+                p
+            }
+        };
+        let template = dir.variant.filename.as_deref().unwrap_or(default_template);
+        let meta = converted_meta(&library, input_path, dir_theme);
+        // 只执行了流水线、没有主题时 {theme} 渲染为 processed，避免与下载的原图同名
+        let stem = match dir_theme {
+            Some(_) => meta.render(template, &name),
+            None => FileMeta { theme: Some("processed".to_string()), ..meta.clone() }.render(template, &name),
+        };
+        let dest = output::write_destination(config, &src, dir, &stem)?;
+        println!("{}", t!("convert_done", path => dest.display()));
+        library.insert(dest.clone(), meta);
+        if let Some(keep) = dir.variant.keep {
+            // 刚写入的文件总是保留，keep = 0 时也不会删掉要设置的壁纸
+            let removed = output::prune(&dir.path, template, keep, &library, &dest)?;
+            if removed > 0 {
+                println!("{}", t!("convert_pruned", count => removed, dir => dir.path.display()));
+            }
+        }
+        written.push(dest);
+        Ok(())
+    });
+    for tmp in &tmp_files {
+        let _ = std::fs::remove_file(tmp);
+    }
+    result?;
+    let primary = written[0].clone();
+    let _ = library.save(&config.state_dir);
+    Ok(primary)
+}
+
+/// 处理 edit 子命令：串联执行 gowall 编辑操作，或提取主色
+fn handle_edit(
+    config: &AppConfig,
+    image: &str,
+    ops: &[gowall::Operation],
+    extract: Option<u32>,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(count) = extract {
+        for color in gowall::extract_palette(image, count)? {
+            println!("{}", color);
+        }
+    }
+    if ops.is_empty() {
+        if extract.is_none() {
+            return Err(t!("error_no_operation").into());
+        }
+        return Ok(());
+    }
+
+    let input_path = std::path::Path::new(image);
+    let original_filename = input_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("image.jpg");
+    let new_filename = match original_filename.strip_prefix("wallow-") {
+        Some(rest) => format!("wallow-edited-{}", rest),
+        None => format!("wallow-edited-{}", original_filename),
+    };
+    let output_path = match output {
+        Some(out) => {
+            let p = std::path::PathBuf::from(out);
+            if p.is_dir() { p.join(new_filename) } else { p }
+        }
+        None => config.converted_dirs.first().ok_or(t!("error_converted_dirs_empty"))?.path.join(new_filename),
+    };
+
+    let op_names: Vec<String> = ops.iter().map(|op| op.to_string()).collect();
+    println!("{}", t!("edit_start", image => image, ops => op_names.join(" → ")));
+    gowall::apply_chain(image, ops, &output_path)?;
+    println!("{}", t!("edit_done", path => output_path.display()));
+    Ok(())
+}
+
+/// 处理 palette 子命令：提取配色并输出为指定格式
+fn handle_palette(
+    image: &str,
+    format: palette::PaletteFormat,
+    output: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let scheme = palette::extract(image)?;
+    let content = palette::render(&scheme, format);
+    match output {
+        Some(path) => {
+            std::fs::write(path, content)?;
+            println!("{}", t!("palette_written", path => path));
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+/// 按 [palette] 配置自动导出最终壁纸的终端配色
+/// 导出失败只打印警告，不影响壁纸本身的下载与设置
+fn auto_export_palette(config: &AppConfig, image: &std::path::Path) {
+    if !config.palette.auto {
+        return;
+    }
+    match palette::export(image, &config.palette.formats, &config.palette_dir()) {
+        Ok(paths) => {
+            for path in paths {
+                println!("{}", t!("palette_written", path => path.display()));
+            }
+        }
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// 处理 themes 子命令：列出当前转换引擎可用的所有主题
+fn handle_themes(config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let themes = match config.convert.backend {
+        ConvertBackend::Gowall => {
+            let themes = gowall::list_themes()?;
+            println!("{}", t!("themes_title", count => themes.len()));
+            themes
+        }
+        ConvertBackend::Native => {
+            let themes: Vec<String> = recolor::builtin_palettes().into_iter().map(|p| p.name).collect();
+            println!("{}", t!("themes_native_title", count => themes.len()));
+            themes
+        }
+    };
     println!("{}", "-".repeat(30));
     for theme in themes.iter() {
         println!("  {}", theme);
     }
+
+    let custom = themes::load_custom(config);
+    if !custom.is_empty() {
+        println!();
+        println!("{}", t!("themes_custom_title", count => custom.len()));
+        println!("{}", "-".repeat(30));
+        for palette in custom.iter() {
+            println!("  {} ({})", palette.name, t!("themes_color_count", count => palette.colors.len()));
+        }
+    }
     Ok(())
 }
 
 /// 处理 run 子命令：一键下载 + 转换
+///
+/// resolution 为 (分辨率, 是否作为最低分辨率)，None 时按 [search_defaults] resolution 解析
+#[allow(clippy::too_many_arguments)]
 async fn handle_run(
     config: &AppConfig,
     query: Option<&str>,
     theme: Option<&str>,
-    resolution: Option<&str>,
+    resolution: Option<(String, bool)>,
     categories: Option<&str>,
     purity: Option<&str>,
     sorting: Option<&str>,
     source: &str,
+    selection: Option<SelectionStrategy>,
 ) -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
-    // 获取有效的 query：优先使用命令行参数，否则从配置中获取（支持随机选择）
-    let effective_query = query.or_else(|| {
-        config.search_defaults.query.as_ref().map(|q| q.pick_random())
-    });
+    // 获取有效的 query：优先使用命令行参数，否则从配置中获取（支持加权随机 / 轮询，区分浅色 / 深色模式）
+    let scheme = appearance::current(config);
+    let effective_query = query.or_else(|| appearance::pick_query(config, scheme));
 
+    let (resolution, atleast) =
+        resolution.unwrap_or_else(|| resolve_resolution(config, &config.search_defaults.resolution));
     let options = SearchOptions {
         query: effective_query,
-        resolution: resolution.unwrap_or(&config.search_defaults.resolution),
+        resolution: &resolution,
+        atleast,
         categories: categories.unwrap_or(&config.search_defaults.categories),
         purity: purity.unwrap_or(&config.search_defaults.purity),
         sorting: sorting.unwrap_or(&config.search_defaults.sorting),
     };
-    let (wallpapers, save_path) = match source {
-        "unsplash" => {
-            let key = config.unsplash_access_key.clone()
-                .ok_or("Unsplash Access Key 未配置")?;
-            let client = UnsplashClient::new(key);
-            let wallpapers = client.search(options).await?;
-            let wallpaper = wallpapers.first().ok_or(t!("error_no_wallpapers"))?;
-            println!(
-                "{}",
-                t!("download_info", current => 1, total => 1,
-                   id => wallpaper.id, res => wallpaper.resolution)
-            );
-            let path = client.download(wallpaper, &config.wallpaper_dir).await?;
-            (wallpapers, path)
-        }
-        _ => {
-            let client = WallhavenClient::new(config.api_key.clone());
-            let wallpapers = client.search(options).await?;
-            let wallpaper = wallpapers.first().ok_or(t!("error_no_wallpapers"))?;
-            println!(
-                "{}",
-                t!("download_info", current => 1, total => 1,
-                   id => wallpaper.id, res => wallpaper.resolution)
-            );
-            let path = client.download(wallpaper, &config.wallpaper_dir).await?;
-            (wallpapers, path)
-        }
-    };
-    let _ = wallpapers; // 防止 unused 警告
+    let client = build_source(config, source)?;
+    let strategy = selection.unwrap_or(config.search_defaults.selection);
+    let target = selection_target(config, strategy, options.resolution);
+    let wallpapers = client.search(options).await?;
+    let wallpapers = select::order(wallpapers, strategy, target, &config.state_dir);
+    let wallpaper = wallpapers.first().ok_or(t!("error_no_wallpapers"))?;
+    println!(
+        "{}",
+        t!("download_info", current => 1, total => 1,
+           id => wallpaper.id, res => wallpaper.resolution)
+    );
+    let save_path = download_wallpaper(config, client.as_ref(), wallpaper, effective_query).await?;
     println!("{}", t!("save_path", path => save_path.display()));
-    // theme 优先级：命令行参数 > config.default_theme > 不转换
-    let effective_theme = theme.or(config.default_theme.as_deref());
-    if let Some(theme_name) = effective_theme {
+    // theme 优先级：命令行参数 > 当前配色方案的 theme_light / theme_dark > config.default_theme > 不转换
+    let effective_theme = theme.or_else(|| appearance::pick_theme(config, scheme));
+    // 有主题、[[pipeline]] 或某个 converted_dirs 目录有自己的设置时，转换、后处理后写入所有 converted_dirs，
+    // 否则直接使用下载的原图
+    let needs_output = effective_theme.is_some()
+        || !config.pipeline.is_empty()
+        || config.converted_dirs.iter().any(|dir| dir.has_settings());
+    let final_path = if needs_output {
         let image_str = save_path.to_str().ok_or(t!("error_utf8"))?;
-        let converted_path = handle_convert(config, image_str, theme_name, None)?;
-        println!("{}", t!("all_done", theme => theme_name));
-        Ok(converted_path)
+        let converted_path = convert_to_dirs(config, image_str, effective_theme, &config.pipeline)?;
+        if let Some(theme_name) = effective_theme {
+            println!("{}", t!("all_done", theme => theme_name));
+        }
+        converted_path
     } else {
-        Ok(save_path)
-    }
+        save_path
+    };
+    auto_export_palette(config, &final_path);
+    Ok(final_path)
 }
 
-/// 处理 schedule 子命令：注册或更新 crontab 定时任务
+/// 处理 schedule 子命令：按 [schedule] backend 注册 crontab 条目、systemd 用户定时器或 launchd agent
+///
+/// 切换后端时移除另一个后端中的旧任务，避免重复触发
 async fn handle_schedule(
     config: &mut AppConfig,
     cron_arg: Option<&str>,
+    backend_arg: Option<ScheduleBackend>,
+    print: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // 确定最终使用的 cron 表达式：命令行参数 > toml 配置
     let cron = match cron_arg {
@@ -569,60 +1126,343 @@ async fn handle_schedule(
             .clone()
             .ok_or("请提供 cron 表达式，或在 config.toml 的 [schedule] 节中设置 cron 字段")?,
     };
+    // 写入配置或注册任务之前先检查表达式
+    CronExpr::validate(&cron)?;
+    let backend = backend_arg.unwrap_or(config.schedule.backend);
+    // 获取当前可执行文件路径，用于构造定时执行的命令
+    let bin_path = std::env::current_exe()?;
+
+    if print {
+        match backend {
+            ScheduleBackend::Cron => println!("{}", schedule::crontab_entry(&cron, &bin_path)),
+            ScheduleBackend::Systemd => {
+                let (service, timer) = schedule::systemd_units(&cron, &bin_path)?;
+                let (service_path, timer_path) = schedule::systemd_unit_paths();
+                println!("# {}\n{}\n# {}\n{}", service_path.display(), service, timer_path.display(), timer);
+            }
+            ScheduleBackend::Launchd => print!("{}", schedule::launchd_plist(&cron, &bin_path)?),
+        }
+        return Ok(());
+    }
 
     // 如果是通过命令行传入的，写入配置文件持久化
     if cron_arg.is_some() {
         config.set_cron(cron.clone())?;
         println!("已将 cron 表达式 '{}' 写入配置文件", cron);
     }
+    if backend != config.schedule.backend {
+        config.set_schedule_backend(backend)?;
+    }
 
-    // 获取当前可执行文件路径，用于构造 crontab 条目
-    let bin_path = std::env::current_exe()?;
-    let bin_str = bin_path.to_string_lossy();
-    // crontab 条目格式: "<cron表达式> <可执行文件> schedule --run"
-    // 用 --run 标志区分「注册模式」和「执行模式」，避免 crontab 触发时再次进入注册逻辑
-    let cron_entry = format!("{} {} schedule --run", cron, bin_str);
-
-    // 读取当前 crontab 内容
-    let existing = std::process::Command::new("crontab")
-        .arg("-l")
-        .output();
-
-    let current = match existing {
-        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout).to_string(),
-        // crontab -l 在无任何条目时返回非零退出码，视为空内容
-        _ => String::new(),
-    };
+    match backend {
+        ScheduleBackend::Cron => {
+            let cron_entry = schedule::crontab_entry(&cron, &bin_path);
+            schedule::install_cron(&cron_entry)?;
+            println!("定时任务已注册: {}", cron_entry);
+        }
+        ScheduleBackend::Systemd => {
+            let (service, timer) = schedule::systemd_units(&cron, &bin_path)?;
+            schedule::install_systemd(&service, &timer)?;
+            let (_, timer_path) = schedule::systemd_unit_paths();
+            println!("{}", t!("schedule_systemd_installed", path => timer_path.display()));
+        }
+        ScheduleBackend::Launchd => {
+            schedule::install_launchd(&schedule::launchd_plist(&cron, &bin_path)?)?;
+            println!("{}", t!("schedule_launchd_installed", path => schedule::launchd_plist_path().display()));
+        }
+    }
+    // 其他后端中的旧任务一并移除，这里的失败不影响新任务
+    for other in ScheduleBackend::ALL.into_iter().filter(|b| *b != backend) {
+        if let Ok(true) = schedule::remove(other) {
+            println!("{}", t!("schedule_removed", backend => other.as_str()));
+        }
+    }
+    Ok(())
+}
 
-    // 移除旧的 wallow schedule 条目（避免重复），再追加新条目
-    let filtered: String = current
-        .lines()
-        .filter(|line| !line.contains("wallow") || !line.contains("schedule"))
-        .map(|line| format!("{}
-", line))
+/// 处理 schedule --remove：移除所有后端中的 wallow 定时任务
+fn handle_schedule_remove() -> Result<(), Box<dyn std::error::Error>> {
+    let mut removed = false;
+    for backend in ScheduleBackend::ALL {
+        if schedule::remove(backend)? {
+            println!("{}", t!("schedule_removed", backend => backend.as_str()));
+            removed = true;
+        }
+    }
+    if !removed {
+        println!("{}", t!("schedule_not_installed"));
+    }
+    Ok(())
+}
+
+/// 处理 schedule status：后端、表达式、已注册的任务、接下来的触发时间、上一次执行结果和最近的日志
+fn handle_schedule_status(config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}: {}", t!("schedule_status_backend"), config.schedule.backend.as_str());
+    let cron = config.schedule.cron.as_deref();
+    println!("{}: {}", t!("schedule_status_cron"), cron.unwrap_or("-"));
+
+    let installed: Vec<(ScheduleBackend, String)> = ScheduleBackend::ALL
+        .into_iter()
+        .filter_map(|backend| Some((backend, schedule::installed(backend)?)))
         .collect();
-    let new_crontab = format!("{}{}
-", filtered, cron_entry);
-
-    // 通过 `crontab -` 写入新的 crontab
-    let mut child = std::process::Command::new("crontab")
-        .arg("-")
-        .stdin(std::process::Stdio::piped())
-        .spawn()?;
-    {
-        use std::io::Write;
-        let stdin = child.stdin.as_mut().ok_or("无法获取 crontab stdin")?;
-        stdin.write_all(new_crontab.as_bytes())?;
+    if installed.is_empty() {
+        println!("{}: {}", t!("schedule_status_installed"), t!("schedule_status_none"));
+    } else {
+        println!("{}:", t!("schedule_status_installed"));
+        for (backend, detail) in installed {
+            println!("  {}: {}", backend.as_str(), detail);
+        }
+    }
+
+    if let Some(cron) = cron {
+        match CronExpr::parse(cron) {
+            Ok(expr) => {
+                println!("{}:", t!("schedule_status_next"));
+                for at in expr.upcoming(chrono::Local::now(), 5) {
+                    println!("  {}", at.format("%Y-%m-%d %H:%M (%a)"));
+                }
+            }
+            Err(e) => println!("{}: {}", t!("schedule_status_next"), e),
+        }
     }
-    let status = child.wait()?;
-    if !status.success() {
-        return Err("写入 crontab 失败".into());
+
+    match schedule::LastRun::load(&config.state_dir) {
+        Some(last) => {
+            let result = match (&last.error, &last.path) {
+                (Some(error), _) => format!("{}: {}", t!("schedule_status_failed"), error),
+                (None, Some(_)) => t!("schedule_status_ok").to_string(),
+                (None, None) => t!("schedule_status_unchanged").to_string(),
+            };
+            println!("{}: {} ({}, {:.1}s)", t!("schedule_status_last"), last.at, result, last.duration_secs);
+            if let Some(path) = &last.path {
+                println!("  {}: {}", t!("current_path"), path.display());
+            }
+            if let Some(source) = &last.source {
+                println!("  {}: {} ({})", t!("current_source"), source, last.id.as_deref().unwrap_or("-"));
+            }
+            if let Some(query) = &last.query {
+                println!("  {}: {}", t!("schedule_status_query"), query);
+            }
+            if let Some(theme) = &last.theme {
+                println!("  {}: {}", t!("current_theme"), theme);
+            }
+        }
+        None => println!("{}: -", t!("schedule_status_last")),
     }
 
-    println!("定时任务已注册: {}", cron_entry);
+    let log = schedule::log_tail(&config.state_dir, 5);
+    if !log.is_empty() {
+        println!("{}: {}", t!("schedule_status_log"), schedule::log_path(&config.state_dir).display());
+        for line in log {
+            println!("  {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// 处理 schedule --run：定时任务触发时执行，按当前时段更换壁纸
+///
+/// 时段配置了 folder 时从本地目录选图，否则用时段的 query / theme 下载；没有配置时段时与 set 相同
+async fn handle_scheduled_run(config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
+    // 启用了动态壁纸时由动态壁纸决定图片，不再按时段更换
+    if let Some(set) = dynamic::active(config) {
+        return apply_dynamic_frame(config, &set).map(|_| ());
+    }
+    let source = resolve_source(config, None);
+    let (layout, mode) = (config.setter.layout, config.setter.mode);
+    let Some(slot) = slots::active(&config.schedule, chrono::Local::now())? else {
+        return handle_set(config, None, None, &source, None, None, layout, mode).await;
+    };
+    let key = slots::slot_key(&slot);
+    if config.schedule.on_slot_change && slots::last_run(&config.state_dir).as_deref() == Some(key.as_str()) {
+        println!("{}", t!("slot_unchanged", name => slot.name));
+        return Ok(());
+    }
+    println!("{}", t!("slot_active", name => slot.name));
+
+    // 轮询游标按时段分开
+    let theme = slot.slot.theme.as_ref().and_then(|t| rotation::pick(config, &format!("slot_{}_theme", slot.name), t));
+    if let Some(folder) = &slot.slot.folder {
+        let current = current::load(config).map(|c| c.original);
+        let image = slots::pick_image(&config::resolve_home_path(folder), current.as_deref())?;
+        let path = match theme {
+            Some(theme) => {
+                ensure_convert_backend(config)?;
+                handle_convert(config, image.to_str().ok_or(t!("error_utf8"))?, theme, None)?
+            }
+            None => image,
+        };
+        apply_wallpaper(config, &path, None, mode, true)?;
+    } else {
+        let query = slot.slot.query.as_ref().and_then(|q| rotation::pick(config, &format!("slot_{}_query", slot.name), q));
+        handle_set(config, query, theme, &source, None, None, layout, mode).await?;
+    }
+    slots::record_run(&config.state_dir, &slot)?;
+    Ok(())
+}
+
+/// 处理 schedule --slots：列出今天各时段的开始时间，标出当前所在时段
+fn handle_slots(config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
+    if config.schedule.slots.is_empty() {
+        println!("{}", t!("slot_none"));
+        return Ok(());
+    }
+    let now = chrono::Local::now();
+    let active = slots::active(&config.schedule, now)?.map(|slot| slot.name);
+    for slot in slots::boundaries(&config.schedule, now.date_naive())? {
+        let marker = if Some(slot.name) == active { "*" } else { " " };
+        println!("{} {}  {}", marker, slot.start.format("%H:%M"), slot.name);
+    }
+    Ok(())
+}
+
+/// 处理 dynamic 子命令：导入、列出、启用、停用和导出动态壁纸
+fn handle_dynamic(config: &AppConfig, action: &cli::DynamicAction) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        cli::DynamicAction::Import { file, name } => {
+            let set = import_dynamic(config, &config::resolve_home_path(file), name.as_deref())?;
+            println!("{}", t!("dynamic_imported", name => set.name, count => set.frames.len()));
+        }
+        cli::DynamicAction::List => {
+            let sets = dynamic::DynamicSet::list(config);
+            if sets.is_empty() {
+                println!("{}", t!("dynamic_none"));
+            }
+            let active = dynamic::active(config).map(|set| set.name);
+            let location = config.schedule.latitude.zip(config.schedule.longitude);
+            for set in sets {
+                let marker = if active.as_ref() == Some(&set.name) { "*" } else { " " };
+                println!("{} {}", marker, set.name);
+                for (start, path) in set.timeline(chrono::Local::now().date_naive(), location) {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    println!("    {:02}:{:02}  {}", start / 3600, start / 60 % 60, name);
+                }
+            }
+        }
+        cli::DynamicAction::Apply { name, watch } => {
+            let set = dynamic::DynamicSet::load(config, name)?;
+            dynamic::set_active(&config.state_dir, Some(&set.name))?;
+            println!("{}", t!("dynamic_enabled", name => set.name));
+            let mut wait = apply_dynamic_frame(config, &set)?;
+            if *watch {
+                println!("{}", t!("dynamic_watching"));
+                loop {
+                    // 最多睡 10 分钟，休眠唤醒或改时区后也能及时切换
+                    std::thread::sleep(std::time::Duration::from_secs(wait.clamp(1, 600) as u64));
+                    // 被 dynamic off 停用或换成其他动态壁纸时退出
+                    if dynamic::active(config).map(|s| s.name).as_ref() != Some(&set.name) {
+                        break;
+                    }
+                    wait = match apply_dynamic_frame(config, &set) {
+                        Ok(wait) => wait,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            60
+                        }
+                    };
+                }
+            }
+        }
+        cli::DynamicAction::Off => {
+            dynamic::set_active(&config.state_dir, None)?;
+            println!("{}", t!("dynamic_disabled"));
+        }
+        cli::DynamicAction::Export { folder, output, start, transition } => {
+            let folder = std::fs::canonicalize(config::resolve_home_path(folder))?;
+            let images = slots::folder_images(&folder)?;
+            if images.is_empty() {
+                return Err(t!("error_slot_folder_empty", path => folder.display()).into());
+            }
+            let start = dynamic::parse_time_of_day(start).ok_or(t!("error_dynamic_time", value => start))?;
+            let output = match output {
+                Some(output) => config::resolve_home_path(output),
+                None => {
+                    let name = folder.file_name().unwrap_or_default().to_string_lossy();
+                    folder.join(format!("{}.xml", name))
+                }
+            };
+            std::fs::write(&output, dynamic::gnome::export(&images, start, *transition))?;
+            println!("{}", t!("dynamic_exported", count => images.len(), path => output.display()));
+        }
+    }
     Ok(())
 }
 
+/// 导入 GNOME 幻灯片 XML 或 macOS 动态 HEIC，图片复制 / 导出到 wallpaper_dir/dynamic/<名称>/
+fn import_dynamic(
+    config: &AppConfig,
+    file: &std::path::Path,
+    name: Option<&str>,
+) -> Result<dynamic::DynamicSet, Box<dyn std::error::Error>> {
+    let name = name
+        .map(str::to_string)
+        .or_else(|| file.file_stem().map(|s| s.to_string_lossy().to_lowercase()))
+        .ok_or(t!("error_utf8"))?
+        .replace(|c: char| c.is_whitespace() || c == '/' || c == '\\', "-");
+    let dir = dynamic::root(config).join(&name);
+    let extension = file.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+
+    let frames = match extension.as_str() {
+        "xml" => {
+            let xml = std::fs::read_to_string(file)?;
+            let timeline = dynamic::gnome::parse(&xml, file.parent().unwrap_or(std::path::Path::new(".")))?;
+            std::fs::create_dir_all(&dir)?;
+            // 同一张图片在一天中出现多次时只复制一次
+            let mut copied: Vec<(std::path::PathBuf, std::path::PathBuf)> = Vec::new();
+            let mut frames = Vec::new();
+            for (start, source) in timeline {
+                let file = match copied.iter().find(|(s, _)| *s == source) {
+                    Some((_, file)) => file.clone(),
+                    None => {
+                        let ext = source.extension().and_then(|e| e.to_str()).unwrap_or("png");
+                        let file = std::path::PathBuf::from(format!("frame-{:02}.{}", copied.len(), ext));
+                        std::fs::copy(&source, dir.join(&file))
+                            .map_err(|e| format!("{}: {}", source.display(), e))?;
+                        copied.push((source, file.clone()));
+                        file
+                    }
+                };
+                frames.push(dynamic::Frame { file, time: dynamic::FrameTime::Time { start } });
+            }
+            frames
+        }
+        "heic" | "heif" => {
+            let timings = dynamic::heic::parse(&std::fs::read(file)?)?;
+            std::fs::create_dir_all(&dir)?;
+            let images = dynamic::heic::extract(file, &dir)?;
+            // 元数据引用的序号超出导出的图片数时跳过该帧
+            let frames: Vec<dynamic::Frame> = timings
+                .into_iter()
+                .filter_map(|(index, time)| {
+                    let file = images.get(index)?.file_name()?.into();
+                    Some(dynamic::Frame { file, time })
+                })
+                .collect();
+            if frames.is_empty() {
+                return Err(t!("error_dynamic_no_frames").into());
+            }
+            frames
+        }
+        _ => return Err(t!("error_dynamic_format", path => file.display()).into()),
+    };
+
+    let set = dynamic::DynamicSet { name, frames, dir };
+    set.save()?;
+    Ok(set)
+}
+
+/// 设置动态壁纸当前时间对应的图片（已是当前壁纸时跳过），返回距离下一次切换的秒数
+fn apply_dynamic_frame(config: &AppConfig, set: &dynamic::DynamicSet) -> Result<u32, Box<dyn std::error::Error>> {
+    let (frame, wait) = set.current_frame(config).ok_or(t!("error_dynamic_no_frames"))?;
+    let unchanged = current::load(config).is_some_and(|c| c.path == frame || c.original == frame);
+    if unchanged {
+        println!("{}", t!("dynamic_unchanged", name => set.name));
+    } else {
+        apply_wallpaper(config, &frame, None, config.setter.mode, false)?;
+    }
+    Ok(wait)
+}
+
 /// 处理 config 子命令：查看或修改配置
 fn handle_config(
     config: &mut AppConfig,
@@ -649,7 +1489,14 @@ fn handle_config(
                 t!("config_res", res => config.search_defaults.resolution)
             );
             println!("{}", t!("config_sorting", sorting => config.search_defaults.sorting));
-            println!("  source: {}", config.default_source);
+            println!("{}", t!("config_source", source => config.default_source.to_display_string()));
+            if let Some(v) = clap::ValueEnum::to_possible_value(&config.search_defaults.selection) {
+                println!("{}", t!("config_selection", selection => v.get_name()));
+            }
+            if let Some(v) = clap::ValueEnum::to_possible_value(&config.setter.backend) {
+                println!("{}", t!("config_setter", setter => v.get_name()));
+            }
+            println!("{}", t!("config_mode", mode => config.setter.mode.as_str()));
         }
         cli::ConfigAction::Schema => {
             println!("{}", AppConfig::get_schema());
@@ -662,6 +1509,18 @@ fn handle_config(
                 "query" => config.search_defaults.query = Some(StringOrVec::Single(value.clone())),
                 "res" | "resolution" => config.search_defaults.resolution = value.clone(),
                 "sorting" => config.search_defaults.sorting = value.clone(),
+                "selection" => {
+                    config.search_defaults.selection = <SelectionStrategy as clap::ValueEnum>::from_str(value, true)
+                        .map_err(|_| t!("config_error_invalid_value", key => key, value => value))?
+                }
+                "setter" => {
+                    config.setter.backend = <config::SetterBackend as clap::ValueEnum>::from_str(value, true)
+                        .map_err(|_| t!("config_error_invalid_value", key => key, value => value))?
+                }
+                "mode" => {
+                    config.setter.mode = <WallpaperMode as clap::ValueEnum>::from_str(value, true)
+                        .map_err(|_| t!("config_error_invalid_value", key => key, value => value))?
+                }
                 _ => return Err(t!("config_error_unknown_key", key => key).into()),
             }
             config.save()?;
@@ -769,9 +1628,17 @@ fn handle_uninstall(
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", t!("uninstall_start"));
 
+    // 0. 移除所有后端中的定时任务（crontab 条目、systemd 定时器、launchd agent），
+    //    否则卸载后它们仍会按时调用已删除的可执行文件
+    for backend in ScheduleBackend::ALL {
+        if schedule::remove(backend)? {
+            println!("{}", t!("schedule_removed", backend => backend.as_str()));
+        }
+    }
+
     // 1. 删除壁纸缓存目录（除非用户指定 --keep-wallpapers）
     if !keep_wallpapers {
-        for dir in std::iter::once(&config.wallpaper_dir).chain(config.converted_dirs.iter()) {
+        for dir in std::iter::once(&config.wallpaper_dir).chain(config.converted_dirs.iter().map(|d| &d.path)) {
             if dir.exists() {
                 std::fs::remove_dir_all(dir)?;
                 println!("{}", t!("uninstall_removed_dir", path => dir.display()));
@@ -783,14 +1650,20 @@ fn handle_uninstall(
 
     // 2. 删除配置目录 ~/.config/wallow/
     // config_path 是 ~/.config/wallow/config.toml，取其父目录
-    if let Some(config_dir) = config.config_path.parent() {
-        if config_dir.exists() {
-            std::fs::remove_dir_all(config_dir)?;
-            println!("{}", t!("uninstall_removed_dir", path => config_dir.display()));
-        }
+    if let Some(config_dir) = config.config_path.parent()
+        && config_dir.exists()
+    {
+        std::fs::remove_dir_all(config_dir)?;
+        println!("{}", t!("uninstall_removed_dir", path => config_dir.display()));
+    }
+
+    // 3. 删除状态目录 ~/.local/state/wallow/（轮询游标、壁纸库索引、历史、日志等）
+    if config.state_dir.exists() {
+        std::fs::remove_dir_all(&config.state_dir)?;
+        println!("{}", t!("uninstall_removed_dir", path => config.state_dir.display()));
     }
 
-    // 3. 删除当前可执行文件本身
+    // 4. 删除当前可执行文件本身
     // 在 Unix 上，正在运行的进程可以删除自身的 inode，进程仍可继续运行直到退出
     let current_exe = std::env::current_exe()?;
     std::fs::remove_file(&current_exe)?;
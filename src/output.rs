@@ -0,0 +1,231 @@
+// output.rs — 转换目录输出模块
+// 按 [common] converted_dirs 中每个目录的尺寸、格式和处理选项写出图片，
+// 并按文件名模板清理旧文件
+
+use crate::config::{AppConfig, ConvertedDir, OutputOptions};
+use crate::library::Library;
+use crate::pipeline::{resolve_pair, scale_brightness, PipelineContext};
+use crate::recolor;
+use image::DynamicImage;
+use rust_i18n::t;
+use std::path::{Path, PathBuf};
+
+/// 按输出目录的处理选项处理图片：先模糊，再按亮度系数压暗，最后限制最大亮度
+pub fn apply_output_options(img: DynamicImage, options: &OutputOptions) -> DynamicImage {
+    let mut img = img;
+    if let Some(radius) = options.blur.filter(|r| *r > 0.0) {
+        img = img.fast_blur(radius as f32);
+    }
+    if let Some(brightness) = options.brightness {
+        let factor = brightness.clamp(0.0, 1.0) as f32;
+        img = scale_brightness(img, |_, _| factor);
+    }
+    if let Some(target) = options.max_luminance {
+        // 用 95 分位亮度代表高光，避免个别极亮像素把整张图压得过暗
+        let rgba = img.to_rgba8();
+        let mut lum: Vec<f32> = rgba
+            .pixels()
+            .map(|p| (0.2126 * p[0] as f32 + 0.7152 * p[1] as f32 + 0.0722 * p[2] as f32) / 255.0)
+            .collect();
+        if !lum.is_empty() {
+            let idx = (lum.len() - 1) * 95 / 100;
+            let (_, p95, _) = lum.select_nth_unstable_by(idx, |a, b| a.total_cmp(b));
+            let target = target.clamp(0.0, 1.0) as f32;
+            if *p95 > target {
+                let factor = target / *p95;
+                img = scale_brightness(DynamicImage::ImageRgba8(rgba), |_, _| factor);
+            }
+        }
+    }
+    img
+}
+
+/// 按输出目录的尺寸、格式和处理选项写出 src，文件名为 `stem` 加扩展名，返回写入路径
+///
+/// 没有任何需要重新编码的设置时直接复制文件
+pub fn write_destination(
+    config: &AppConfig,
+    src: &Path,
+    dir: &ConvertedDir,
+    stem: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let src_ext = src.extension().and_then(|e| e.to_str()).unwrap_or("png").to_lowercase();
+    let format = match &dir.variant.format {
+        Some(f) => {
+            let f = f.trim_start_matches('.').to_lowercase();
+            if !matches!(f.as_str(), "png" | "jpg" | "jpeg" | "webp") {
+                return Err(t!("error_pipeline_value", value => f).into());
+            }
+            f
+        }
+        None => src_ext.clone(),
+    };
+    let dest = dir.path.join(format!("{}.{}", stem, format));
+
+    if dir.variant.size.is_none() && format == src_ext && dir.options.is_empty() {
+        std::fs::copy(src, &dest)?;
+        return Ok(dest);
+    }
+
+    let mut ctx = PipelineContext::new(config, format);
+    let mut img = image::open(src).map_err(|e| t!("error_pipeline_failed", step => "output", reason => e.to_string()))?;
+    if let Some(size) = &dir.variant.size {
+        let (w, h) = resolve_pair(size, &mut ctx)?;
+        img = img.resize_to_fill(w, h, image::imageops::FilterType::Lanczos3);
+    }
+    recolor::save_image(apply_output_options(img, &dir.options), &dest)?;
+    Ok(dest)
+}
+
+/// 文件名是否符合模板：占位符 {xxx} 匹配任意字符，其余部分按顺序原样匹配
+fn matches_template(name: &str, template: &str) -> bool {
+    let mut parts = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else { break };
+        parts.push(&rest[..start]);
+        rest = &rest[start + len + 1..];
+    }
+    parts.push(rest);
+
+    let last = parts.len() - 1;
+    let mut remaining = name;
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            let Some(r) = remaining.strip_prefix(part) else { return false };
+            remaining = r;
+        } else if i == last {
+            // 最后一段之后只允许跟扩展名
+            let Some(pos) = remaining.rfind('.') else { return false };
+            return remaining[..pos].ends_with(part);
+        } else {
+            let Some(pos) = remaining.find(part) else { return false };
+            remaining = &remaining[pos + part.len()..];
+        }
+    }
+    // 模板中没有占位符：文件名去掉扩展名后应与模板完全相同
+    remaining.starts_with('.') && !remaining[1..].contains('.')
+}
+
+/// 只保留目录中最新的 `keep` 个符合文件名模板的文件（按修改时间），返回删除的文件数
+///
+/// 占位符几乎能匹配任何文件名，只考虑 wallow 写入的文件（见 [`Library::is_managed`]），
+/// 不会删除用户放在同一目录下的其他文件。刚写入的 `current` 总是保留并计入 keep 个之内，
+/// keep = 0 时等同于 1
+pub fn prune(
+    dir: &Path,
+    template: &str,
+    keep: usize,
+    library: &Library,
+    current: &Path,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let keep = keep.saturating_sub(1);
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+        .filter(|e| e.path() != current)
+        .filter(|e| library.is_managed(&e.path()))
+        .filter(|e| matches_template(&e.file_name().to_string_lossy(), template))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    if files.len() <= keep {
+        return Ok(0);
+    }
+    // 新的在前，跳过前 keep 个，其余删除
+    files.sort_by_key(|f| std::cmp::Reverse(f.0));
+    let mut removed = 0;
+    for (_, path) in files.into_iter().skip(keep) {
+        if std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::FileMeta;
+
+    #[test]
+    fn template_matching() {
+        assert!(matches_template("wallow-nord-abc.png", "wallow-{theme}-{name}"));
+        assert!(!matches_template("other-nord-abc.png", "wallow-{theme}-{name}"));
+        assert!(matches_template("bg.jpg", "bg"));
+        assert!(!matches_template("bg.old.jpg", "bg"));
+        // 只有占位符的模板匹配任何带扩展名的文件，需要靠 is_managed 兜底
+        assert!(matches_template("my-photo.jpg", "{theme}"));
+    }
+
+    /// 空的临时目录
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wallow-{}-test-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 写入文件并把修改时间设为 age 秒之前
+    fn write(dir: &Path, name: &str, age: u64) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, name).unwrap();
+        let mtime = std::time::SystemTime::now() - std::time::Duration::from_secs(age);
+        std::fs::File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+        path
+    }
+
+    #[test]
+    fn prune_skips_foreign_files() {
+        let dir = temp_dir("prune");
+        // 用户自己的文件，最旧，也符合模板
+        let foreign = write(&dir, "my-photo.jpg", 300);
+        // 去掉 wallow- 前缀、只在索引中记录的文件
+        let indexed = write(&dir, "nord-old.png", 200);
+        let prefixed = write(&dir, "wallow-nord-mid.png", 100);
+        let newest = write(&dir, "nord-new.png", 0);
+        let mut library = Library::default();
+        library.insert(indexed.clone(), FileMeta::default());
+        library.insert(newest.clone(), FileMeta::default());
+
+        assert_eq!(prune(&dir, "{theme}-{name}", 1, &library, &newest).unwrap(), 2);
+        assert!(foreign.exists());
+        assert!(newest.exists());
+        assert!(!indexed.exists());
+        assert!(!prefixed.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prune_skips_other_templates() {
+        let dir = temp_dir("prune-template");
+        // wallow 写入、但由另一个模板生成的文件
+        let downloaded = write(&dir, "wallow-wallhaven-abc.jpg", 300);
+        let old = write(&dir, "term-nord.png", 200);
+        let new = write(&dir, "term-gruvbox.png", 0);
+        let mut library = Library::default();
+        library.insert(old.clone(), FileMeta::default());
+        library.insert(new.clone(), FileMeta::default());
+
+        assert_eq!(prune(&dir, "term-{theme}", 1, &library, &new).unwrap(), 1);
+        assert!(downloaded.exists());
+        assert!(new.exists());
+        assert!(!old.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn prune_keeps_current_file() {
+        let dir = temp_dir("prune-current");
+        let old = write(&dir, "wallow-nord-old.png", 200);
+        // 修改时间比旧文件还早（如复制时保留了时间戳）的新文件也不会被删除
+        let current = write(&dir, "wallow-nord-new.png", 300);
+        let library = Library::default();
+
+        assert_eq!(prune(&dir, "wallow-{theme}-{name}", 0, &library, &current).unwrap(), 1);
+        assert!(current.exists());
+        assert!(!old.exists());
+        assert_eq!(prune(&dir, "wallow-{theme}-{name}", 0, &library, &current).unwrap(), 0);
+        assert!(current.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
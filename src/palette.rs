@@ -0,0 +1,334 @@
+// palette.rs — 壁纸配色提取与终端配色导出模块
+// 用 k-means 从图片中提取主色，生成 16 色终端调色板及前景 / 背景色，
+// 并导出为 pywal JSON 以及 Alacritty / Kitty / WezTerm / foot / Ghostty 配置片段
+
+use rust_i18n::t;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+type Rgb = [u8; 3];
+
+/// k-means 聚类数：1 个背景 + 6 个强调色 + 1 个前景
+const CLUSTERS: usize = 8;
+/// k-means 最大迭代次数
+const ITERATIONS: usize = 12;
+/// 采样前将图片缩小到的最大边长
+const SAMPLE_SIZE: u32 = 128;
+
+/// 终端配色导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum PaletteFormat {
+    /// pywal 兼容的 colors.json
+    Pywal,
+    /// Alacritty TOML 配置片段
+    Alacritty,
+    /// Kitty 配置片段
+    Kitty,
+    /// WezTerm 配色方案 TOML
+    Wezterm,
+    /// foot INI 配置片段
+    Foot,
+    /// Ghostty 配置片段
+    Ghostty,
+}
+
+impl PaletteFormat {
+    /// 自动导出时使用的文件名
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            PaletteFormat::Pywal => "colors.json",
+            PaletteFormat::Alacritty => "alacritty.toml",
+            PaletteFormat::Kitty => "kitty.conf",
+            PaletteFormat::Wezterm => "wezterm.toml",
+            PaletteFormat::Foot => "foot.ini",
+            PaletteFormat::Ghostty => "ghostty",
+        }
+    }
+}
+
+/// 终端配色方案
+#[derive(Debug, Clone)]
+pub struct Scheme {
+    /// 生成配色的源图片
+    pub wallpaper: PathBuf,
+    pub background: Rgb,
+    pub foreground: Rgb,
+    pub cursor: Rgb,
+    /// ANSI 0–15 号颜色
+    pub colors: [Rgb; 16],
+}
+
+/// Rec. 709 相对亮度（0–255）
+fn luminance(c: [f32; 3]) -> f32 {
+    0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]
+}
+
+fn to_f32(c: Rgb) -> [f32; 3] {
+    [c[0] as f32, c[1] as f32, c[2] as f32]
+}
+
+fn to_rgb(c: [f32; 3]) -> Rgb {
+    [
+        c[0].round().clamp(0.0, 255.0) as u8,
+        c[1].round().clamp(0.0, 255.0) as u8,
+        c[2].round().clamp(0.0, 255.0) as u8,
+    ]
+}
+
+/// 将颜色 a 向 b 混合 amount（0–1）
+fn mix(a: Rgb, b: Rgb, amount: f32) -> Rgb {
+    let (a, b) = (to_f32(a), to_f32(b));
+    to_rgb([
+        a[0] + (b[0] - a[0]) * amount,
+        a[1] + (b[1] - a[1]) * amount,
+        a[2] + (b[2] - a[2]) * amount,
+    ])
+}
+
+/// 计算色相（0–360°）
+fn hue(c: Rgb) -> f32 {
+    let [r, g, b] = to_f32(c).map(|v| v / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    if delta == 0.0 {
+        return 0.0;
+    }
+    let h = if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    h * 60.0
+}
+
+fn hex(c: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2])
+}
+
+/// 对采样像素做 k-means 聚类，返回按亮度从暗到亮排序的聚类中心
+///
+/// 初始中心取亮度分位点，结果对同一张图片是确定的
+fn kmeans(pixels: &[[f32; 3]], k: usize) -> Vec<[f32; 3]> {
+    if pixels.is_empty() {
+        return vec![[0.0; 3]; k];
+    }
+    let mut sorted: Vec<[f32; 3]> = pixels.to_vec();
+    sorted.sort_by(|a, b| luminance(*a).total_cmp(&luminance(*b)));
+    let mut centers: Vec<[f32; 3]> = (0..k)
+        .map(|i| sorted[(i * 2 + 1) * (sorted.len() - 1) / (k * 2)])
+        .collect();
+
+    for _ in 0..ITERATIONS {
+        let mut sums = vec![[0f32; 3]; k];
+        let mut counts = vec![0usize; k];
+        for p in pixels {
+            let nearest = (0..k)
+                .min_by(|a, b| {
+                    let da: f32 = (0..3).map(|c| (p[c] - centers[*a][c]).powi(2)).sum();
+                    let db: f32 = (0..3).map(|c| (p[c] - centers[*b][c]).powi(2)).sum();
+                    da.total_cmp(&db)
+                })
+                .unwrap_or(0);
+            for c in 0..3 {
+                sums[nearest][c] += p[c];
+            }
+            counts[nearest] += 1;
+        }
+        // 空聚类保留原中心
+        for i in 0..k {
+            if counts[i] > 0 {
+                centers[i] = sums[i].map(|v| v / counts[i] as f32);
+            }
+        }
+    }
+    centers.sort_by(|a, b| luminance(*a).total_cmp(&luminance(*b)));
+    centers
+}
+
+/// 把 6 个强调色分配到 ANSI 1–6（红、绿、黄、蓝、品红、青），
+/// 每次取色相距离最近的一对，尽量让 "红色" 槽位真的偏红
+fn assign_accents(accents: &[Rgb]) -> [Rgb; 6] {
+    const SLOT_HUES: [f32; 6] = [0.0, 120.0, 60.0, 240.0, 300.0, 180.0];
+    let mut result = [[0u8; 3]; 6];
+    let mut used_slot = [false; 6];
+    let mut used_color = vec![false; accents.len()];
+    for _ in 0..accents.len().min(6) {
+        let mut best: Option<(usize, usize, f32)> = None;
+        for (ci, color) in accents.iter().enumerate() {
+            if used_color[ci] {
+                continue;
+            }
+            for (si, slot_hue) in SLOT_HUES.iter().enumerate() {
+                if used_slot[si] {
+                    continue;
+                }
+                let d = (hue(*color) - slot_hue).abs();
+                let d = d.min(360.0 - d);
+                if best.is_none_or(|(_, _, bd)| d < bd) {
+                    best = Some((ci, si, d));
+                }
+            }
+        }
+        if let Some((ci, si, _)) = best {
+            result[si] = accents[ci];
+            used_slot[si] = true;
+            used_color[ci] = true;
+        }
+    }
+    result
+}
+
+/// 保证颜色与背景有足够的亮度差，不够时逐步向白色混合
+fn ensure_contrast(color: Rgb, background: Rgb, min_diff: f32) -> Rgb {
+    let mut c = color;
+    let bg = luminance(to_f32(background));
+    for _ in 0..8 {
+        if luminance(to_f32(c)) - bg >= min_diff {
+            break;
+        }
+        c = mix(c, [255, 255, 255], 0.15);
+    }
+    c
+}
+
+/// 从图片提取终端配色方案
+pub fn extract(image_path: impl AsRef<Path>) -> Result<Scheme, Box<dyn std::error::Error>> {
+    let path = image_path.as_ref();
+    let img = image::open(path)
+        .map_err(|e| t!("error_palette_failed", reason => e.to_string()))?
+        .thumbnail(SAMPLE_SIZE, SAMPLE_SIZE)
+        .to_rgb8();
+    let pixels: Vec<[f32; 3]> = img.pixels().map(|p| to_f32(p.0)).collect();
+    let centers: Vec<Rgb> = kmeans(&pixels, CLUSTERS).into_iter().map(to_rgb).collect();
+
+    // 最暗的聚类压暗作为背景，最亮的聚类提亮作为前景
+    let background = mix(centers[0], [0, 0, 0], 0.4);
+    let foreground = mix(centers[CLUSTERS - 1], [255, 255, 255], 0.6);
+    let accents: Vec<Rgb> = centers[1..CLUSTERS - 1]
+        .iter()
+        .map(|c| ensure_contrast(*c, background, 70.0))
+        .collect();
+    let normal = assign_accents(&accents);
+
+    let mut colors = [[0u8; 3]; 16];
+    colors[0] = background;
+    colors[1..7].copy_from_slice(&normal);
+    colors[7] = mix(foreground, background, 0.2);
+    colors[8] = mix(background, foreground, 0.3);
+    for i in 0..6 {
+        colors[9 + i] = mix(normal[i], [255, 255, 255], 0.2);
+    }
+    colors[15] = foreground;
+
+    Ok(Scheme {
+        wallpaper: path.to_path_buf(),
+        background,
+        foreground,
+        cursor: foreground,
+        colors,
+    })
+}
+
+/// 将配色方案渲染为指定格式的文本
+pub fn render(scheme: &Scheme, format: PaletteFormat) -> String {
+    let c = |i: usize| hex(scheme.colors[i]);
+    let bg = hex(scheme.background);
+    let fg = hex(scheme.foreground);
+    let cursor = hex(scheme.cursor);
+    const NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+    match format {
+        PaletteFormat::Pywal => {
+            let colors: serde_json::Map<String, serde_json::Value> = (0..16)
+                .map(|i| (format!("color{}", i), serde_json::Value::String(c(i))))
+                .collect();
+            let json = serde_json::json!({
+                "wallpaper": scheme.wallpaper.to_string_lossy(),
+                "alpha": "100",
+                "special": { "background": bg, "foreground": fg, "cursor": cursor },
+                "colors": colors,
+            });
+            serde_json::to_string_pretty(&json).unwrap_or_default() + "\n"
+        }
+        PaletteFormat::Alacritty => {
+            let mut out = format!(
+                "[colors.primary]\nbackground = \"{}\"\nforeground = \"{}\"\n\n[colors.cursor]\ncursor = \"{}\"\ntext = \"{}\"\n",
+                bg, fg, cursor, bg
+            );
+            for (section, offset) in [("normal", 0), ("bright", 8)] {
+                out.push_str(&format!("\n[colors.{}]\n", section));
+                for (i, name) in NAMES.iter().enumerate() {
+                    out.push_str(&format!("{} = \"{}\"\n", name, c(offset + i)));
+                }
+            }
+            out
+        }
+        PaletteFormat::Kitty => {
+            let mut out = format!("background {}\nforeground {}\ncursor {}\n", bg, fg, cursor);
+            for i in 0..16 {
+                out.push_str(&format!("color{} {}\n", i, c(i)));
+            }
+            out
+        }
+        PaletteFormat::Wezterm => {
+            let list = |range: std::ops::Range<usize>| {
+                range.map(|i| format!("\"{}\"", c(i))).collect::<Vec<_>>().join(", ")
+            };
+            format!(
+                "[colors]\nbackground = \"{}\"\nforeground = \"{}\"\ncursor_bg = \"{}\"\ncursor_border = \"{}\"\ncursor_fg = \"{}\"\nansi = [{}]\nbrights = [{}]\n\n[metadata]\nname = \"wallow\"\n",
+                bg,
+                fg,
+                cursor,
+                cursor,
+                bg,
+                list(0..8),
+                list(8..16)
+            )
+        }
+        PaletteFormat::Foot => {
+            // foot 的颜色不带 # 前缀
+            let bare = |s: String| s.trim_start_matches('#').to_string();
+            let mut out = format!("[colors]\nbackground={}\nforeground={}\n", bare(bg), bare(fg));
+            for i in 0..8 {
+                out.push_str(&format!("regular{}={}\n", i, bare(c(i))));
+            }
+            for i in 0..8 {
+                out.push_str(&format!("bright{}={}\n", i, bare(c(8 + i))));
+            }
+            out
+        }
+        PaletteFormat::Ghostty => {
+            let mut out = format!(
+                "background = {}\nforeground = {}\ncursor-color = {}\n",
+                bg, fg, cursor
+            );
+            for i in 0..16 {
+                out.push_str(&format!("palette = {}={}\n", i, c(i)));
+            }
+            out
+        }
+    }
+}
+
+/// 提取配色并按多个格式写入目录，返回写出的文件路径
+pub fn export(
+    image_path: impl AsRef<Path>,
+    formats: &[PaletteFormat],
+    output_dir: &Path,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let scheme = extract(image_path)?;
+    fs::create_dir_all(output_dir)?;
+    let mut written = Vec::new();
+    for format in formats {
+        let path = output_dir.join(format.file_name());
+        fs::write(&path, render(&scheme, *format))?;
+        written.push(path);
+    }
+    Ok(written)
+}
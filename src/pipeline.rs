@@ -0,0 +1,380 @@
+// pipeline.rs — 图片后处理流水线模块
+// 按 [[pipeline]] 配置的顺序对下载后的壁纸执行裁剪、缩放、模糊、压暗、暗角、
+// 主题转换、gowall 编辑和格式转换等步骤
+
+use crate::config::{AppConfig, ConvertBackend};
+use crate::gowall::{self, Operation};
+use crate::recolor;
+use crate::screen;
+use crate::themes;
+use image::DynamicImage;
+use rust_i18n::t;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// [[pipeline]] 中单个步骤的配置，step 字段决定步骤类型
+///
+/// 示例:
+/// ```toml
+/// [[pipeline]]
+/// step = "crop"
+/// [[pipeline]]
+/// step = "dim"
+/// amount = 0.4
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum StepConfig {
+    /// 居中裁剪到目标宽高比
+    Crop {
+        /// "auto"（当前屏幕）、"16:9" 或 "3840x2160"，默认 auto
+        #[serde(default = "default_auto")]
+        aspect: String,
+    },
+    /// 缩放
+    Resize {
+        /// "auto"（当前屏幕）或 "1920x1080"
+        #[serde(default = "default_auto")]
+        size: String,
+        /// true: 保持比例缩放到目标尺寸以内；false: 缩放并裁剪填满目标尺寸（默认）
+        #[serde(default)]
+        fit: bool,
+    },
+    /// 高斯模糊
+    Blur {
+        /// 模糊半径（sigma）
+        radius: f64,
+    },
+    /// 整体压暗，提升终端文字可读性
+    Dim {
+        /// 压暗比例 0–1，0.4 表示亮度降到 60%
+        amount: f64,
+    },
+    /// 暗角
+    Vignette {
+        /// 边缘压暗强度 0–1
+        #[serde(default = "default_vignette")]
+        strength: f64,
+    },
+    /// 主题转换，使用 [convert] backend 指定的引擎
+    Theme { theme: String },
+    /// 输出格式转换
+    Format {
+        /// png / jpg / webp
+        format: String,
+    },
+    /// 调用 gowall 编辑操作，写法与 `wallow edit --op` 相同
+    Gowall { op: Operation },
+}
+
+fn default_auto() -> String {
+    "auto".to_string()
+}
+
+fn default_vignette() -> f64 {
+    0.5
+}
+
+/// 流水线执行上下文
+pub struct PipelineContext<'a> {
+    pub config: &'a AppConfig,
+    /// 输出格式（扩展名），由 format 步骤修改
+    pub format: String,
+    /// 需要借助文件的步骤（gowall）使用的临时文件目录
+    pub work_dir: PathBuf,
+    /// 缓存的屏幕分辨率，首次需要时检测
+    screen: Option<Option<(u32, u32)>>,
+}
+
+impl<'a> PipelineContext<'a> {
+    /// 创建上下文，format 为初始输出格式（扩展名）
+    pub fn new(config: &'a AppConfig, format: String) -> Self {
+        Self {
+            config,
+            format,
+            work_dir: config.state_dir.join("tmp"),
+            screen: None,
+        }
+    }
+
+    /// 当前屏幕分辨率，检测失败时退回搜索默认分辨率
+    pub fn screen(&mut self) -> Option<(u32, u32)> {
+        let config = self.config;
+        *self.screen.get_or_insert_with(|| {
+            screen::screen_resolution(config.search_defaults.screen.as_deref()).or_else(|| {
+                match crate::source::parse_resolution(&config.search_defaults.resolution) {
+                    (0, _) | (_, 0) => None,
+                    res => Some(res),
+                }
+            })
+        })
+    }
+
+    /// 将图片写入临时文件交给外部工具处理，再读回内存
+    fn round_trip(
+        &self,
+        img: DynamicImage,
+        f: impl FnOnce(&Path, &Path) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(&self.work_dir)?;
+        let input = self.work_dir.join("pipeline-in.png");
+        let output = self.work_dir.join("pipeline-out.png");
+        img.save(&input)?;
+        f(&input, &output)?;
+        let result = image::open(&output)?;
+        let _ = std::fs::remove_file(&input);
+        let _ = std::fs::remove_file(&output);
+        Ok(result)
+    }
+}
+
+/// 流水线步骤
+pub trait PipelineStep {
+    /// 用于日志输出的步骤描述
+    fn describe(&self) -> String;
+
+    /// 处理图片并返回结果
+    fn apply(
+        &self,
+        img: DynamicImage,
+        ctx: &mut PipelineContext,
+    ) -> Result<DynamicImage, Box<dyn std::error::Error>>;
+}
+
+/// 解析 "16:9" 或 "3840x2160" 形式的比例 / 尺寸
+fn parse_pair(value: &str) -> Option<(u32, u32)> {
+    let (a, b) = value.split_once([':', 'x'])?;
+    let a: u32 = a.trim().parse().ok()?;
+    let b: u32 = b.trim().parse().ok()?;
+    (a > 0 && b > 0).then_some((a, b))
+}
+
+/// 解析 "auto" 或具体数值，auto 时使用屏幕分辨率
+pub fn resolve_pair(value: &str, ctx: &mut PipelineContext) -> Result<(u32, u32), Box<dyn std::error::Error>> {
+    let pair = if value == "auto" { ctx.screen() } else { parse_pair(value) };
+    pair.ok_or_else(|| t!("error_pipeline_value", value => value).into())
+}
+
+struct Crop {
+    aspect: String,
+}
+
+impl PipelineStep for Crop {
+    fn describe(&self) -> String {
+        format!("crop {}", self.aspect)
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut PipelineContext) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let (aw, ah) = resolve_pair(&self.aspect, ctx)?;
+        Ok(crop_to_aspect(img, aw, ah))
+    }
+}
+
+/// 居中裁剪到 aw:ah 的宽高比
+pub fn crop_to_aspect(img: DynamicImage, aw: u32, ah: u32) -> DynamicImage {
+    let (w, h) = (img.width() as u64, img.height() as u64);
+    let (aw, ah) = (aw as u64, ah as u64);
+    // 比较 w/h 与 aw/ah，过宽则裁左右，过高则裁上下
+    let (cw, ch) = if w * ah > h * aw {
+        (h * aw / ah, h)
+    } else {
+        (w, w * ah / aw)
+    };
+    let (x, y) = ((w - cw) / 2, (h - ch) / 2);
+    img.crop_imm(x as u32, y as u32, cw.max(1) as u32, ch.max(1) as u32)
+}
+
+struct Resize {
+    size: String,
+    fit: bool,
+}
+
+impl PipelineStep for Resize {
+    fn describe(&self) -> String {
+        format!("resize {}", self.size)
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut PipelineContext) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let (w, h) = resolve_pair(&self.size, ctx)?;
+        let filter = image::imageops::FilterType::Lanczos3;
+        Ok(if self.fit {
+            img.resize(w, h, filter)
+        } else {
+            img.resize_to_fill(w, h, filter)
+        })
+    }
+}
+
+struct Blur {
+    radius: f64,
+}
+
+impl PipelineStep for Blur {
+    fn describe(&self) -> String {
+        format!("blur {}", self.radius)
+    }
+
+    fn apply(&self, img: DynamicImage, _ctx: &mut PipelineContext) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        Ok(if self.radius > 0.0 { img.fast_blur(self.radius as f32) } else { img })
+    }
+}
+
+/// 按像素位置计算亮度系数并相乘，alpha 不变
+pub fn scale_brightness(img: DynamicImage, factor: impl Fn(u32, u32) -> f32) -> DynamicImage {
+    let mut rgba = img.to_rgba8();
+    for (x, y, p) in rgba.enumerate_pixels_mut() {
+        let f = factor(x, y).clamp(0.0, 1.0);
+        for c in 0..3 {
+            p[c] = (p[c] as f32 * f).round() as u8;
+        }
+    }
+    DynamicImage::ImageRgba8(rgba)
+}
+
+struct Dim {
+    amount: f64,
+}
+
+impl PipelineStep for Dim {
+    fn describe(&self) -> String {
+        format!("dim {}", self.amount)
+    }
+
+    fn apply(&self, img: DynamicImage, _ctx: &mut PipelineContext) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let factor = 1.0 - self.amount.clamp(0.0, 1.0) as f32;
+        Ok(scale_brightness(img, |_, _| factor))
+    }
+}
+
+struct Vignette {
+    strength: f64,
+}
+
+impl PipelineStep for Vignette {
+    fn describe(&self) -> String {
+        format!("vignette {}", self.strength)
+    }
+
+    fn apply(&self, img: DynamicImage, _ctx: &mut PipelineContext) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let (cx, cy) = (img.width() as f32 / 2.0, img.height() as f32 / 2.0);
+        let max = (cx * cx + cy * cy).sqrt().max(1.0);
+        let strength = self.strength.clamp(0.0, 1.0) as f32;
+        // 亮度随到中心距离的平方衰减
+        Ok(scale_brightness(img, |x, y| {
+            let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+            let d = (dx * dx + dy * dy).sqrt() / max;
+            1.0 - strength * d * d
+        }))
+    }
+}
+
+struct Theme {
+    theme: String,
+}
+
+impl PipelineStep for Theme {
+    fn describe(&self) -> String {
+        format!("theme {}", self.theme)
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut PipelineContext) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let config = ctx.config;
+        match config.convert.backend {
+            // 内置引擎直接在内存中转换
+            ConvertBackend::Native => {
+                let palette = themes::find_native(config, &self.theme)
+                    .ok_or_else(|| t!("error_unknown_theme", theme => self.theme))?;
+                let options = recolor::RecolorOptions {
+                    dither: config.convert.dither,
+                    preserve_luminance: config.convert.preserve_luminance,
+                };
+                Ok(DynamicImage::ImageRgba8(recolor::recolor_image(&img.to_rgba8(), &palette, options)))
+            }
+            ConvertBackend::Gowall => {
+                ctx.round_trip(img, |input, output| themes::convert(config, input, &self.theme, output))
+            }
+        }
+    }
+}
+
+struct Format {
+    format: String,
+}
+
+impl PipelineStep for Format {
+    fn describe(&self) -> String {
+        format!("format {}", self.format)
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut PipelineContext) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        let format = self.format.trim_start_matches('.').to_lowercase();
+        if !matches!(format.as_str(), "png" | "jpg" | "jpeg" | "webp") {
+            return Err(t!("error_pipeline_value", value => self.format).into());
+        }
+        ctx.format = format;
+        Ok(img)
+    }
+}
+
+struct Gowall {
+    op: Operation,
+}
+
+impl PipelineStep for Gowall {
+    fn describe(&self) -> String {
+        format!("gowall {}", self.op)
+    }
+
+    fn apply(&self, img: DynamicImage, ctx: &mut PipelineContext) -> Result<DynamicImage, Box<dyn std::error::Error>> {
+        gowall::check_installed()?;
+        ctx.round_trip(img, |input, output| gowall::apply(input, &self.op, output).map(|_| ()))
+    }
+}
+
+impl StepConfig {
+    /// 根据配置创建对应的步骤
+    pub fn build(&self) -> Box<dyn PipelineStep> {
+        match self {
+            StepConfig::Crop { aspect } => Box::new(Crop { aspect: aspect.clone() }),
+            StepConfig::Resize { size, fit } => Box::new(Resize { size: size.clone(), fit: *fit }),
+            StepConfig::Blur { radius } => Box::new(Blur { radius: *radius }),
+            StepConfig::Dim { amount } => Box::new(Dim { amount: *amount }),
+            StepConfig::Vignette { strength } => Box::new(Vignette { strength: *strength }),
+            StepConfig::Theme { theme } => Box::new(Theme { theme: theme.clone() }),
+            StepConfig::Format { format } => Box::new(Format { format: format.clone() }),
+            StepConfig::Gowall { op } => Box::new(Gowall { op: op.clone() }),
+        }
+    }
+}
+
+/// 依次执行所有步骤，结果写入 `output_dir` 中的 `stem` 加扩展名，返回输出文件路径
+///
+/// 扩展名由 format 步骤决定（默认与输入相同）；调用方应给出与输入不同的 stem，避免覆盖输入文件
+pub fn run(
+    config: &AppConfig,
+    steps: &[StepConfig],
+    input: &Path,
+    output_dir: &Path,
+    stem: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let format = input
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("png")
+        .to_lowercase();
+    let mut ctx = PipelineContext::new(config, format);
+
+    let mut img = image::open(input).map_err(|e| t!("error_pipeline_failed", step => "load", reason => e.to_string()))?;
+    for step in steps.iter().map(StepConfig::build) {
+        let name = step.describe();
+        println!("{}", t!("pipeline_step", step => name));
+        img = step
+            .apply(img, &mut ctx)
+            .map_err(|e| t!("error_pipeline_failed", step => name, reason => e.to_string()))?;
+    }
+
+    let output = output_dir.join(format!("{}.{}", stem, ctx.format));
+    recolor::save_image(img, &output)?;
+    Ok(output)
+}
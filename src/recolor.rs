@@ -0,0 +1,249 @@
+// recolor.rs — 内置主题转换引擎
+// 不依赖外部 gowall，将每个像素映射到调色板中最接近的颜色
+// 可选 Floyd–Steinberg 抖动与亮度保持
+
+use image::{DynamicImage, RgbaImage};
+use rust_i18n::t;
+use std::path::Path;
+
+/// 一个命名调色板
+#[derive(Debug, Clone)]
+pub struct Palette {
+    /// 主题名称（如 "catppuccin"）
+    pub name: String,
+    /// 调色板颜色，RGB 三元组
+    pub colors: Vec<[u8; 3]>,
+}
+
+/// 内置调色板：(名称, 十六进制颜色列表)
+const BUILTIN_PALETTES: &[(&str, &[&str])] = &[
+    (
+        "catppuccin",
+        &[
+            "#f5e0dc", "#f2cdcd", "#f5c2e7", "#cba6f7", "#f38ba8", "#eba0ac", "#fab387",
+            "#f9e2af", "#a6e3a1", "#94e2d5", "#89dceb", "#74c7ec", "#89b4fa", "#b4befe",
+            "#cdd6f4", "#bac2de", "#a6adc8", "#9399b2", "#7f849c", "#6c7086", "#585b70",
+            "#45475a", "#313244", "#1e1e2e", "#181825", "#11111b",
+        ],
+    ),
+    (
+        "dracula",
+        &[
+            "#21222c", "#282a36", "#44475a", "#6272a4", "#f8f8f2", "#8be9fd", "#50fa7b",
+            "#ffb86c", "#ff79c6", "#bd93f9", "#ff5555", "#f1fa8c",
+        ],
+    ),
+    (
+        "nord",
+        &[
+            "#2e3440", "#3b4252", "#434c5e", "#4c566a", "#d8dee9", "#e5e9f0", "#eceff4",
+            "#8fbcbb", "#88c0d0", "#81a1c1", "#5e81ac", "#bf616a", "#d08770", "#ebcb8b",
+            "#a3be8c", "#b48ead",
+        ],
+    ),
+    (
+        "gruvbox",
+        &[
+            "#1d2021", "#282828", "#32302f", "#3c3836", "#504945", "#665c54", "#7c6f64",
+            "#928374", "#a89984", "#bdae93", "#d5c4a1", "#ebdbb2", "#fbf1c7", "#cc241d",
+            "#fb4934", "#98971a", "#b8bb26", "#d79921", "#fabd2f", "#458588", "#83a598",
+            "#b16286", "#d3869b", "#689d6a", "#8ec07c", "#d65d0e", "#fe8019",
+        ],
+    ),
+    (
+        "tokyo-night",
+        &[
+            "#16161e", "#1a1b26", "#24283b", "#292e42", "#414868", "#565f89", "#9aa5ce",
+            "#a9b1d6", "#c0caf5", "#7aa2f7", "#7dcfff", "#2ac3de", "#b4f9f8", "#bb9af7",
+            "#9ece6a", "#73daca", "#e0af68", "#ff9e64", "#f7768e", "#db4b4b",
+        ],
+    ),
+];
+
+/// 转换选项
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecolorOptions {
+    /// 使用 Floyd–Steinberg 误差扩散抖动，渐变区域更平滑
+    pub dither: bool,
+    /// 保留原图亮度，只替换色相与饱和度，明暗细节不丢失
+    pub preserve_luminance: bool,
+}
+
+/// 解析 "#rrggbb" 或 "rrggbb" 格式的颜色
+pub fn parse_hex(hex: &str) -> Option<[u8; 3]> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// 列出所有内置调色板
+pub fn builtin_palettes() -> Vec<Palette> {
+    BUILTIN_PALETTES
+        .iter()
+        .map(|(name, colors)| Palette {
+            name: name.to_string(),
+            colors: colors.iter().filter_map(|c| parse_hex(c)).collect(),
+        })
+        .collect()
+}
+
+/// 按名称查找内置调色板（忽略大小写）
+pub fn find_builtin(name: &str) -> Option<Palette> {
+    builtin_palettes()
+        .into_iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+/// 感知加权的 RGB 距离（"redmean" 近似），比直接欧氏距离更接近人眼感受
+fn distance(a: [f32; 3], b: [u8; 3]) -> f32 {
+    let rmean = (a[0] + b[0] as f32) / 2.0;
+    let dr = a[0] - b[0] as f32;
+    let dg = a[1] - b[1] as f32;
+    let db = a[2] - b[2] as f32;
+    (2.0 + rmean / 256.0) * dr * dr + 4.0 * dg * dg + (2.0 + (255.0 - rmean) / 256.0) * db * db
+}
+
+/// Rec. 709 相对亮度（0–255）
+fn luminance(c: [f32; 3]) -> f32 {
+    0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]
+}
+
+/// 最近颜色查找表：每个通道量化为 5 位，共 32768 项
+/// 4K 图片有八百万像素，逐像素遍历调色板太慢，查表后每个像素只需一次索引
+struct NearestLut {
+    table: Vec<u8>,
+}
+
+impl NearestLut {
+    fn new(colors: &[[u8; 3]]) -> Self {
+        let mut table = vec![0u8; 32 * 32 * 32];
+        for (i, slot) in table.iter_mut().enumerate() {
+            // 取量化格子的中心点作为代表色
+            let r = ((i >> 10) & 31) as f32 * 8.0 + 4.0;
+            let g = ((i >> 5) & 31) as f32 * 8.0 + 4.0;
+            let b = (i & 31) as f32 * 8.0 + 4.0;
+            *slot = colors
+                .iter()
+                .enumerate()
+                .min_by(|(_, x), (_, y)| distance([r, g, b], **x).total_cmp(&distance([r, g, b], **y)))
+                .map(|(idx, _)| idx as u8)
+                .unwrap_or(0);
+        }
+        Self { table }
+    }
+
+    fn lookup(&self, c: [f32; 3]) -> usize {
+        let q = |v: f32| (v.clamp(0.0, 255.0) as usize) >> 3;
+        self.table[(q(c[0]) << 10) | (q(c[1]) << 5) | q(c[2])] as usize
+    }
+}
+
+/// 对内存中的图片应用调色板，返回新图片（保留 alpha 通道）
+pub fn recolor_image(img: &RgbaImage, palette: &Palette, options: RecolorOptions) -> RgbaImage {
+    let (width, height) = img.dimensions();
+    let mut out = RgbaImage::new(width, height);
+    if palette.colors.is_empty() {
+        return img.clone();
+    }
+    // 调色板超过 256 色时只使用前 256 色，查找表以 u8 存储索引
+    let colors: Vec<[u8; 3]> = palette.colors.iter().take(256).copied().collect();
+    let lut = NearestLut::new(&colors);
+
+    // 抖动需要把误差传给后续像素，因此使用浮点缓冲区
+    let mut buffer: Vec<[f32; 3]> = img
+        .pixels()
+        .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+        .collect();
+    let w = width as usize;
+    let h = height as usize;
+
+    for y in 0..h {
+        for x in 0..w {
+            let idx = y * w + x;
+            let current = buffer[idx];
+            let target = colors[lut.lookup(current)];
+            let mut mapped = [target[0] as f32, target[1] as f32, target[2] as f32];
+
+            if options.dither {
+                let err = [
+                    current[0] - mapped[0],
+                    current[1] - mapped[1],
+                    current[2] - mapped[2],
+                ];
+                // Floyd–Steinberg 权重：右 7/16，左下 3/16，下 5/16，右下 1/16
+                let mut spread = |dx: isize, dy: usize, factor: f32| {
+                    let nx = x as isize + dx;
+                    let ny = y + dy;
+                    if nx >= 0 && (nx as usize) < w && ny < h {
+                        let n = &mut buffer[ny * w + nx as usize];
+                        for c in 0..3 {
+                            n[c] += err[c] * factor;
+                        }
+                    }
+                };
+                spread(1, 0, 7.0 / 16.0);
+                spread(-1, 1, 3.0 / 16.0);
+                spread(0, 1, 5.0 / 16.0);
+                spread(1, 1, 1.0 / 16.0);
+            }
+
+            if options.preserve_luminance {
+                // 平移映射色的亮度到原像素亮度，色相保持调色板的颜色
+                let original = img.get_pixel(x as u32, y as u32);
+                let original = [original[0] as f32, original[1] as f32, original[2] as f32];
+                let shift = luminance(original) - luminance(mapped);
+                for c in mapped.iter_mut() {
+                    *c += shift;
+                }
+            }
+
+            let alpha = img.get_pixel(x as u32, y as u32)[3];
+            out.put_pixel(
+                x as u32,
+                y as u32,
+                image::Rgba([
+                    mapped[0].round().clamp(0.0, 255.0) as u8,
+                    mapped[1].round().clamp(0.0, 255.0) as u8,
+                    mapped[2].round().clamp(0.0, 255.0) as u8,
+                    alpha,
+                ]),
+            );
+        }
+    }
+    out
+}
+
+/// 读取图片、应用调色板并写出
+///
+/// 输出格式由 `output_path` 的扩展名决定；JPEG 不支持透明度，写出前会丢弃 alpha 通道
+pub fn convert(
+    image_path: impl AsRef<Path>,
+    palette: &Palette,
+    output_path: impl AsRef<Path>,
+    options: RecolorOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let img = image::open(image_path.as_ref())
+        .map_err(|e| t!("error_native_convert_failed", reason => e.to_string()))?
+        .to_rgba8();
+    let result = recolor_image(&img, palette, options);
+    save_image(DynamicImage::ImageRgba8(result), output_path.as_ref())
+}
+
+/// 按扩展名保存图片，JPEG 先转为 RGB
+pub fn save_image(img: DynamicImage, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let is_jpeg = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| matches!(e.to_lowercase().as_str(), "jpg" | "jpeg"))
+        .unwrap_or(false);
+    let img = if is_jpeg {
+        DynamicImage::ImageRgb8(img.to_rgb8())
+    } else {
+        img
+    };
+    img.save(path)
+        .map_err(|e| t!("error_native_convert_failed", reason => e.to_string()).into())
+}
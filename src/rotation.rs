@@ -0,0 +1,57 @@
+// rotation.rs — 多值配置项轮换模块
+// 为 query / source / theme 等数组配置提供加权随机与跨运行轮询两种选择方式
+
+use crate::config::{AppConfig, RotationMode, StringOrVec};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 轮询游标的持久化状态，保存在 state_dir/rotation.json
+/// 键为配置项名称（如 "query"），值为下一次要使用的游标
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct RotationState {
+    #[serde(default)]
+    cursors: BTreeMap<String, usize>,
+}
+
+impl RotationState {
+    fn path(state_dir: &Path) -> PathBuf {
+        state_dir.join("rotation.json")
+    }
+
+    /// 读取状态文件，不存在或损坏时视为初始状态
+    fn load(state_dir: &Path) -> Self {
+        fs::read_to_string(Self::path(state_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, state_dir: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(Self::path(state_dir), json)
+    }
+}
+
+/// 按配置的轮换策略从候选列表中选择一个值
+///
+/// # 参数
+/// - `key`: 状态文件中的游标名称，不同配置项各自独立轮询
+/// - `list`: 候选列表
+///
+/// 轮询模式下每次调用都会推进游标并立即写回状态文件，
+/// 因此定时任务每次触发都会取到周期中的下一个值，而不是随机重复。
+/// 没有可选的值（空列表、权重全为 0）时返回 None
+pub fn pick<'a>(config: &AppConfig, key: &str, list: &'a StringOrVec) -> Option<&'a str> {
+    match config.rotation {
+        RotationMode::Random => list.pick_random(),
+        RotationMode::RoundRobin => {
+            let mut state = RotationState::load(&config.state_dir);
+            let cursor = state.cursors.get(key).copied().unwrap_or(0);
+            state.cursors.insert(key.to_string(), cursor.wrapping_add(1));
+            // 状态写入失败不影响本次选择，只是下次会重复同一个值
+            let _ = state.save(&config.state_dir);
+            list.pick_round_robin(cursor)
+        }
+    }
+}
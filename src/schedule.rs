@@ -0,0 +1,523 @@
+// schedule.rs — 定时任务后端
+// 把 [schedule] cron 注册为 crontab 条目、systemd 用户定时器或 launchd agent，定时执行 `wallow schedule --run`；
+// 每次执行的结果写入 state_dir/last_run.json 和轮转的 state_dir/schedule.log
+
+use crate::config::{AppConfig, ScheduleBackend};
+use crate::cron::CronExpr;
+use crate::current;
+use crate::library::Library;
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// systemd 单元的名称
+const UNIT_NAME: &str = "wallow";
+
+/// 定时执行的命令: "<可执行文件> schedule --run"
+///
+/// 用 --run 标志区分「注册模式」和「执行模式」，避免定时触发时再次进入注册逻辑
+fn run_command(bin: &Path) -> String {
+    format!("{} schedule --run", bin.to_string_lossy())
+}
+
+/// crontab 条目: "<cron表达式> <可执行文件> schedule --run"
+pub fn crontab_entry(cron: &str, bin: &Path) -> String {
+    format!("{} {}", cron, run_command(bin))
+}
+
+/// crontab 中 wallow 条目上一行的标记注释，按标记识别条目，不会误删其他含 wallow 字样的任务
+const CRON_MARKER: &str = "# wallow-schedule: managed by `wallow schedule`, do not edit the next line";
+
+/// 旧版本写入的无标记条目：命令恰好是当前可执行文件的 schedule --run
+fn is_legacy_entry(line: &str) -> bool {
+    std::env::current_exe().is_ok_and(|bin| line.trim_end().ends_with(&format!(" {}", run_command(&bin))))
+}
+
+/// 读取当前 crontab 内容
+fn read_crontab() -> String {
+    match Command::new("crontab").arg("-l").output() {
+        Ok(out) if out.status.success() => String::from_utf8_lossy(&out.stdout).to_string(),
+        // crontab -l 在无任何条目时返回非零退出码，视为空内容
+        _ => String::new(),
+    }
+}
+
+/// 通过 `crontab -` 写入新的 crontab
+fn write_crontab(content: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut child = Command::new("crontab")
+        .arg("-")
+        .stdin(std::process::Stdio::piped())
+        .spawn()?;
+    {
+        use std::io::Write;
+        let stdin = child.stdin.as_mut().ok_or("无法获取 crontab stdin")?;
+        stdin.write_all(content.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err("写入 crontab 失败".into());
+    }
+    Ok(())
+}
+
+/// 当前 crontab 去掉 wallow 条目（标记行及其下一行、旧版本的无标记条目）后的内容，以及是否有被去掉的条目
+fn crontab_without_wallow() -> (String, bool) {
+    let current = read_crontab();
+    let mut removed = false;
+    let mut filtered = String::new();
+    let mut lines = current.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == CRON_MARKER {
+            lines.next();
+            removed = true;
+        } else if is_legacy_entry(line) {
+            removed = true;
+        } else {
+            filtered.push_str(line);
+            filtered.push('\n');
+        }
+    }
+    (filtered, removed)
+}
+
+/// 注册 crontab 条目（带标记注释），替换旧的 wallow 条目（避免重复）
+pub fn install_cron(entry: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (filtered, _) = crontab_without_wallow();
+    write_crontab(&format!("{}{}\n{}\n", filtered, CRON_MARKER, entry))
+}
+
+/// 当前 crontab 中的 wallow 条目
+fn installed_cron() -> Option<String> {
+    let current = read_crontab();
+    let mut lines = current.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() == CRON_MARKER {
+            return lines.next().map(str::to_string);
+        }
+        if is_legacy_entry(line) {
+            return Some(line.to_string());
+        }
+    }
+    None
+}
+
+/// 移除 crontab 中的 wallow 条目，没有条目或没有安装 cron 时返回 false
+pub fn remove_cron() -> Result<bool, Box<dyn std::error::Error>> {
+    let (filtered, removed) = crontab_without_wallow();
+    if removed {
+        write_crontab(&filtered)?;
+    }
+    Ok(removed)
+}
+
+/// systemd 用户单元目录: $XDG_CONFIG_HOME/systemd/user，默认 ~/.config/systemd/user
+pub fn systemd_dir() -> PathBuf {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| crate::config::resolve_home_path(".config"));
+    config_home.join("systemd").join("user")
+}
+
+/// 生成 wallow.service 和 wallow.timer 的内容
+///
+/// 定时器的 OnCalendar= 由 cron 表达式换算；Persistent=true 让关机期间错过的触发在开机后补上
+pub fn systemd_units(cron: &str, bin: &Path) -> Result<(String, String), Box<dyn std::error::Error>> {
+    let calendars = CronExpr::parse(cron)?.to_on_calendar();
+    // systemd 会展开 ExecStart 中的 % 说明符和 $ 环境变量，需要写成 %% 和 $$；
+    // 带空格、引号或反斜杠的路径加引号，引号内的 " 和 \ 再转义
+    let bin = bin.to_string_lossy().replace('%', "%%").replace('$', "$$");
+    let exec = if bin.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        format!("\"{}\" schedule --run", bin.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        format!("{} schedule --run", bin)
+    };
+    let service = format!(
+        "[Unit]\nDescription=Change the wallpaper with wallow\n\n[Service]\nType=oneshot\nExecStart={}\n",
+        exec
+    );
+    let on_calendar: String = calendars.iter().map(|c| format!("OnCalendar={}\n", c)).collect();
+    let timer = format!(
+        "[Unit]\nDescription=Change the wallpaper with wallow ({})\n\n[Timer]\n{}Persistent=true\n\n[Install]\nWantedBy=timers.target\n",
+        cron, on_calendar
+    );
+    Ok((service, timer))
+}
+
+/// wallow.service 和 wallow.timer 的路径
+pub fn systemd_unit_paths() -> (PathBuf, PathBuf) {
+    let dir = systemd_dir();
+    (dir.join(format!("{}.service", UNIT_NAME)), dir.join(format!("{}.timer", UNIT_NAME)))
+}
+
+/// 执行 systemctl --user，失败时将 stderr 作为错误信息
+fn systemctl(args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new("systemctl")
+        .arg("--user")
+        .args(args)
+        .output()
+        .map_err(|e| t!("error_systemctl", reason => e.to_string()))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(t!("error_systemctl", reason => stderr).into());
+    }
+    Ok(())
+}
+
+/// 写入单元文件并启用定时器
+pub fn install_systemd(service: &str, timer: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let (service_path, timer_path) = systemd_unit_paths();
+    fs::create_dir_all(systemd_dir())?;
+    fs::write(&service_path, service)?;
+    fs::write(&timer_path, timer)?;
+    systemctl(&["daemon-reload"])?;
+    // 定时器已在运行时 enable --now 不会重新加载 OnCalendar，需要 restart
+    systemctl(&["enable", &format!("{}.timer", UNIT_NAME)])?;
+    systemctl(&["restart", &format!("{}.timer", UNIT_NAME)])
+}
+
+/// 停用定时器并删除单元文件，没有安装时返回 false
+pub fn remove_systemd() -> Result<bool, Box<dyn std::error::Error>> {
+    let (service_path, timer_path) = systemd_unit_paths();
+    if !service_path.exists() && !timer_path.exists() {
+        return Ok(false);
+    }
+    // 定时器可能已被手动停用，忽略 disable 的错误
+    let _ = systemctl(&["disable", "--now", &format!("{}.timer", UNIT_NAME)]);
+    for path in [&service_path, &timer_path] {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    systemctl(&["daemon-reload"])?;
+    Ok(true)
+}
+
+/// launchd agent 的 Label
+const LAUNCHD_LABEL: &str = "com.wallow.schedule";
+
+/// ~/Library/LaunchAgents/com.wallow.schedule.plist
+pub fn launchd_plist_path() -> PathBuf {
+    crate::config::resolve_home_path("Library/LaunchAgents").join(format!("{}.plist", LAUNCHD_LABEL))
+}
+
+/// 转义 plist 中的 XML 特殊字符
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// StartCalendarInterval 条目数超过该值时给出警告（如 "*/15 9-17 * * 1-5" 会展开为 180 个）
+const LAUNCHD_INTERVAL_WARN: usize = 100;
+
+/// 生成 launchd plist：cron 表达式换算为 StartCalendarInterval 字典数组
+pub fn launchd_plist(cron: &str, bin: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let intervals = CronExpr::parse(cron)?.to_calendar_intervals();
+    if intervals.len() > LAUNCHD_INTERVAL_WARN {
+        eprintln!("{}", t!("warn_launchd_intervals", count => intervals.len()));
+    }
+    let mut plist = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n<dict>\n",
+    );
+    plist.push_str(&format!("    <key>Label</key>\n    <string>{}</string>\n", LAUNCHD_LABEL));
+    plist.push_str("    <key>ProgramArguments</key>\n    <array>\n");
+    for arg in [bin.to_string_lossy().as_ref(), "schedule", "--run"] {
+        plist.push_str(&format!("        <string>{}</string>\n", xml_escape(arg)));
+    }
+    plist.push_str("    </array>\n    <key>StartCalendarInterval</key>\n    <array>\n");
+    for interval in intervals {
+        if interval.is_empty() {
+            // 空字典表示每分钟
+            plist.push_str("        <dict/>\n");
+            continue;
+        }
+        plist.push_str("        <dict>\n");
+        for (key, value) in interval {
+            plist.push_str(&format!("            <key>{}</key>\n            <integer>{}</integer>\n", key, value));
+        }
+        plist.push_str("        </dict>\n");
+    }
+    plist.push_str("    </array>\n</dict>\n</plist>\n");
+    Ok(plist)
+}
+
+/// 当前用户的 launchd 域 gui/<uid>
+fn launchd_domain() -> String {
+    // SAFETY: getuid 没有前置条件，总是成功
+    #[cfg(unix)]
+    let uid = unsafe { libc::getuid() };
+    // launchd 只存在于 macOS，其他平台上不会真正调用 launchctl
+    #[cfg(not(unix))]
+    let uid = 0;
+    format!("gui/{}", uid)
+}
+
+/// 执行 launchctl，失败时将 stderr 作为错误信息
+fn launchctl(args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new("launchctl")
+        .args(args)
+        .output()
+        .map_err(|e| t!("error_launchctl", reason => e.to_string()))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(t!("error_launchctl", reason => stderr).into());
+    }
+    Ok(())
+}
+
+/// 写入 plist 并加载 agent，已加载的旧 agent 先卸载
+pub fn install_launchd(plist: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let path = launchd_plist_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let domain = launchd_domain();
+    let _ = launchctl(&["bootout", &format!("{}/{}", domain, LAUNCHD_LABEL)]);
+    fs::write(&path, plist)?;
+    launchctl(&["bootstrap", &domain, &path.to_string_lossy()])
+}
+
+/// 卸载 agent 并删除 plist，没有安装时返回 false
+pub fn remove_launchd() -> Result<bool, Box<dyn std::error::Error>> {
+    let path = launchd_plist_path();
+    if !path.exists() {
+        return Ok(false);
+    }
+    // agent 可能未加载，忽略 bootout 的错误
+    let _ = launchctl(&["bootout", &format!("{}/{}", launchd_domain(), LAUNCHD_LABEL)]);
+    fs::remove_file(&path)?;
+    Ok(true)
+}
+
+/// 移除指定后端中的 wallow 定时任务，没有安装时返回 false
+pub fn remove(backend: ScheduleBackend) -> Result<bool, Box<dyn std::error::Error>> {
+    match backend {
+        ScheduleBackend::Cron => remove_cron(),
+        ScheduleBackend::Systemd => remove_systemd(),
+        ScheduleBackend::Launchd => remove_launchd(),
+    }
+}
+
+/// 指定后端中已注册的 wallow 定时任务：crontab 条目或单元 / plist 文件路径
+pub fn installed(backend: ScheduleBackend) -> Option<String> {
+    match backend {
+        ScheduleBackend::Cron => installed_cron(),
+        ScheduleBackend::Systemd => {
+            let (_, timer_path) = systemd_unit_paths();
+            timer_path.exists().then(|| timer_path.display().to_string())
+        }
+        ScheduleBackend::Launchd => {
+            let path = launchd_plist_path();
+            path.exists().then(|| path.display().to_string())
+        }
+    }
+}
+
+/// 上一次 schedule --run 的结果，保存在 state_dir/last_run.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LastRun {
+    /// 开始执行的时间（RFC 3339）
+    pub at: String,
+    /// 耗时（秒）
+    #[serde(default)]
+    pub duration_secs: f64,
+    /// 本次设置的壁纸，壁纸没有变化（如仍在同一时段）或失败时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
+    /// 失败时的错误信息，成功时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn last_run_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("last_run.json")
+}
+
+impl LastRun {
+    /// 读取上一次的结果，从未执行过时返回 None
+    pub fn load(state_dir: &Path) -> Option<Self> {
+        let content = fs::read_to_string(last_run_path(state_dir)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self, state_dir: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(last_run_path(state_dir), json)
+    }
+
+    /// 日志中的一行: "<时间> ok|unchanged|error <耗时> 详情"
+    fn log_line(&self) -> String {
+        let status = match (&self.error, &self.path) {
+            (Some(_), _) => "error",
+            (None, Some(_)) => "ok",
+            (None, None) => "unchanged",
+        };
+        let mut line = format!("{} {} {:.1}s", self.at, status, self.duration_secs);
+        if let Some(error) = &self.error {
+            line.push_str(&format!(": {}", error.replace('\n', " ")));
+        }
+        for (key, value) in [("source", &self.source), ("id", &self.id), ("query", &self.query), ("theme", &self.theme)] {
+            if let Some(value) = value {
+                line.push_str(&format!(" {}={:?}", key, value));
+            }
+        }
+        if let Some(path) = &self.path {
+            line.push_str(&format!(" path={:?}", path.display().to_string()));
+        }
+        line
+    }
+}
+
+/// 定时任务日志 state_dir/schedule.log，超过 LOG_MAX_BYTES 时轮转为 schedule.log.1 ~ .3
+pub fn log_path(state_dir: &Path) -> PathBuf {
+    state_dir.join("schedule.log")
+}
+
+const LOG_MAX_BYTES: u64 = 256 * 1024;
+const LOG_KEEP: usize = 3;
+
+/// 追加一行日志，必要时先轮转
+fn append_log(state_dir: &Path, line: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    let path = log_path(state_dir);
+    if fs::metadata(&path).is_ok_and(|m| m.len() > LOG_MAX_BYTES) {
+        let rotated = |n: usize| state_dir.join(format!("schedule.log.{}", n));
+        for n in (1..LOG_KEEP).rev() {
+            let _ = fs::rename(rotated(n), rotated(n + 1));
+        }
+        fs::rename(&path, rotated(1))?;
+    }
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", line)
+}
+
+/// 日志的最后 count 行
+pub fn log_tail(state_dir: &Path, count: usize) -> Vec<String> {
+    let content = fs::read_to_string(log_path(state_dir)).unwrap_or_default();
+    let lines: Vec<&str> = content.lines().collect();
+    lines[lines.len().saturating_sub(count)..].iter().map(|l| l.to_string()).collect()
+}
+
+/// 记录一次 schedule --run 的结果：写入 last_run.json 和日志，失败时按配置发送桌面通知
+///
+/// previous 为执行前的当前壁纸（设置时间和路径），用来判断本次是否换了壁纸；记录失败不影响执行结果
+pub fn finish_run(
+    config: &AppConfig,
+    started_at: chrono::DateTime<chrono::Local>,
+    previous: Option<(String, PathBuf)>,
+    error: Option<String>,
+) {
+    let mut last = LastRun {
+        at: started_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        duration_secs: (chrono::Local::now() - started_at).num_milliseconds() as f64 / 1000.0,
+        ..Default::default()
+    };
+    match error {
+        Some(error) => {
+            if config.schedule.notify_on_failure {
+                notify(&t!("schedule_notify_failed"), &error);
+            }
+            last.error = Some(error);
+        }
+        None => {
+            if let Some(current) = current::load(config)
+                && previous.as_ref() != Some(&(current.set_at.clone(), current.path.clone()))
+            {
+                // 搜索词记录在壁纸库索引中（转换后的文件继承原图的元数据）
+                last.query = Library::load(&config.state_dir).get(&current.path).and_then(|m| m.query.clone());
+                last.source = current.source;
+                last.id = current.id;
+                last.theme = current.theme;
+                last.path = Some(current.path);
+            }
+        }
+    }
+    let _ = last.save(&config.state_dir);
+    let _ = append_log(&config.state_dir, &last.log_line());
+}
+
+/// 发送桌面通知：macOS 使用 osascript，其他平台使用 notify-send（未安装时跳过）
+fn notify(title: &str, body: &str) {
+    if cfg!(target_os = "macos") {
+        let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let script = format!("display notification \"{}\" with title \"{}\"", quote(body), quote(title));
+        let _ = Command::new("osascript").args(["-e", &script]).output();
+    } else if crate::setter::in_path("notify-send") {
+        let _ = Command::new("notify-send").args(["--app-name=wallow", "--urgency=critical", title, body]).output();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// plist 中 StartCalendarInterval 数组的内容
+    fn intervals(plist: &str) -> &str {
+        let start = plist.find("<key>StartCalendarInterval</key>").unwrap();
+        &plist[start..]
+    }
+
+    #[test]
+    fn systemd_unit_files() {
+        let (service, timer) = systemd_units("*/15 9-17 * * 1-5", Path::new("/usr/local/bin/wallow")).unwrap();
+        assert!(service.contains("[Service]\nType=oneshot\nExecStart=/usr/local/bin/wallow schedule --run\n"));
+        assert!(timer.contains("Description=Change the wallpaper with wallow (*/15 9-17 * * 1-5)"));
+        assert!(timer.contains("[Timer]\nOnCalendar=Mon..Fri *-*-* 09..17:00,15,30,45:00\nPersistent=true\n"));
+        assert!(timer.contains("[Install]\nWantedBy=timers.target\n"));
+        // 日和周都有限制时拆成两条 OnCalendar
+        let (_, timer) = systemd_units("30 8 1 * 1", Path::new("/usr/local/bin/wallow")).unwrap();
+        assert_eq!(timer.matches("OnCalendar=").count(), 2);
+    }
+
+    #[test]
+    fn systemd_exec_start_escaping() {
+        let exec = |bin: &str| {
+            let (service, _) = systemd_units("@daily", Path::new(bin)).unwrap();
+            service.lines().find_map(|l| l.strip_prefix("ExecStart=")).unwrap().to_string()
+        };
+        assert_eq!(exec("/opt/100%/wallow"), "/opt/100%%/wallow schedule --run");
+        assert_eq!(exec("/opt/$HOME/wallow"), "/opt/$$HOME/wallow schedule --run");
+        assert_eq!(exec("/home/a b/%h/wallow"), "\"/home/a b/%%h/wallow\" schedule --run");
+        assert_eq!(exec("/opt/a\"b/wallow"), "\"/opt/a\\\"b/wallow\" schedule --run");
+    }
+
+    #[test]
+    fn launchd_plist_every_minute() {
+        let plist = launchd_plist("* * * * *", Path::new("/usr/local/bin/wallow")).unwrap();
+        assert_eq!(intervals(&plist).matches("<dict/>").count(), 1);
+        assert!(!intervals(&plist).contains("<key>Minute</key>"));
+    }
+
+    #[test]
+    fn launchd_plist_day_weekday_union() {
+        let plist = launchd_plist("30 8 1 * 1", Path::new("/usr/local/bin/wallow")).unwrap();
+        let intervals = intervals(&plist);
+        assert_eq!(intervals.matches("<dict>").count(), 2);
+        assert!(intervals.contains("<key>Day</key>\n            <integer>1</integer>"));
+        assert!(intervals.contains("<key>Weekday</key>\n            <integer>1</integer>"));
+        // 日和周分属两个字典，任一满足即触发
+        for dict in intervals.split("</dict>").filter(|d| d.contains("<dict>")) {
+            assert!(dict.contains("<key>Minute</key>\n            <integer>30</integer>"));
+            assert!(dict.contains("<key>Hour</key>\n            <integer>8</integer>"));
+            assert_ne!(dict.contains("<key>Day</key>"), dict.contains("<key>Weekday</key>"));
+        }
+    }
+
+    #[test]
+    fn launchd_plist_escapes_arguments() {
+        let plist = launchd_plist("@daily", Path::new("/Users/a&b/<bin>/wallow")).unwrap();
+        assert!(plist.contains("<string>/Users/a&amp;b/&lt;bin&gt;/wallow</string>"));
+        assert!(!plist.contains("a&b"));
+    }
+}
@@ -0,0 +1,489 @@
+// screen.rs — 屏幕信息检测模块
+// 通过系统自带的命令行工具查询主显示器的当前分辨率，以及所有已连接显示器的名称、分辨率和位置
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// 一个已连接的显示器
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Monitor {
+    /// 输出名称，如 "DP-1"、"HDMI-A-1"
+    pub name: String,
+    /// 分辨率（像素）
+    pub width: u32,
+    pub height: u32,
+    /// 在整个桌面中的位置，拼接模式下用于裁剪；Wayland 合成器给出的是缩放后的逻辑坐标
+    #[serde(default)]
+    pub x: i32,
+    #[serde(default)]
+    pub y: i32,
+    /// 缩放比例，逻辑尺寸为分辨率除以它；X11 的位置就是像素，为 1
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+impl Monitor {
+    /// "WxH" 形式的分辨率，用作搜索参数
+    pub fn resolution(&self) -> String {
+        format!("{}x{}", self.width, self.height)
+    }
+
+    /// 以每逻辑像素 `scale` 个画布像素计算，显示器在画布中占据的区域 (x, y, width, height)
+    ///
+    /// 边缘按逻辑坐标取整，相邻的显示器在画布上首尾相接
+    pub fn canvas_rect(&self, scale: f64) -> (i32, i32, u32, u32) {
+        let own = if self.scale > 0.0 { self.scale } else { 1.0 };
+        let edge = |logical: f64| (logical * scale).round() as i32;
+        let (left, top) = (edge(self.x as f64), edge(self.y as f64));
+        let right = edge(self.x as f64 + self.width as f64 / own);
+        let bottom = edge(self.y as f64 + self.height as f64 / own);
+        (left, top, (right - left) as u32, (bottom - top) as u32)
+    }
+}
+
+/// 拼接画布的缩放比例：取所有显示器中最大的，高分屏上的切片不需要放大
+pub fn canvas_scale(monitors: &[Monitor]) -> f64 {
+    monitors.iter().map(|m| m.scale).filter(|s| *s > 0.0).fold(1.0, f64::max)
+}
+
+/// 所有显示器拼成的整个桌面在画布上的外接矩形 (x, y, width, height)，见 [`Monitor::canvas_rect`]
+pub fn bounding_box(monitors: &[Monitor]) -> Option<(i32, i32, u32, u32)> {
+    let scale = canvas_scale(monitors);
+    let rects: Vec<_> = monitors.iter().map(|m| m.canvas_rect(scale)).collect();
+    let min_x = rects.iter().map(|r| r.0).min()?;
+    let min_y = rects.iter().map(|r| r.1).min()?;
+    let max_x = rects.iter().map(|r| r.0 + r.2 as i32).max()?;
+    let max_y = rects.iter().map(|r| r.1 + r.3 as i32).max()?;
+    Some((min_x, min_y, (max_x - min_x) as u32, (max_y - min_y) as u32))
+}
+
+/// 检测所有已连接的显示器，按位置从左到右、从上到下排序
+///
+/// 检测顺序：
+/// - Hyprland: `hyprctl monitors -j`
+/// - Sway: `swaymsg -t get_outputs -r`
+/// - 其他 Wayland: `wlr-randr`
+/// - X11: `xrandr --current`
+///
+/// 无法检测时返回空列表，可在 [setter] monitors 中手动列出
+pub fn detect_monitors() -> Vec<Monitor> {
+    let env_set = |key: &str| env_var(key).is_some_and(|v| !v.is_empty());
+    let mut monitors = None;
+    if env_set("HYPRLAND_INSTANCE_SIGNATURE") {
+        monitors = command_stdout("hyprctl", &["monitors", "-j"]).and_then(|out| parse_hyprctl(&out));
+    }
+    if monitors.is_none() && env_set("SWAYSOCK") {
+        monitors = command_stdout("swaymsg", &["-t", "get_outputs", "-r"]).and_then(|out| parse_sway_outputs(&out));
+    }
+    if monitors.is_none() && env_set("WAYLAND_DISPLAY") {
+        monitors = command_stdout("wlr-randr", &[]).map(|out| parse_wlr_randr_outputs(&out));
+    }
+    if monitors.as_ref().is_none_or(Vec::is_empty) {
+        monitors = command_stdout("xrandr", &["--current"]).map(|out| parse_xrandr_outputs(&out));
+    }
+    let mut monitors = monitors.unwrap_or_default();
+    monitors.sort_by_key(|m| (m.x, m.y));
+    monitors
+}
+
+/// 检测主显示器的当前分辨率，返回 (width, height)
+///
+/// 检测顺序：
+/// - Wayland: `wlr-randr`（标记为 current 的模式）
+/// - X11: `xrandr --current`（primary 输出的当前几何尺寸，没有 primary 时取第一个带 * 的模式）
+/// - macOS: `system_profiler SPDisplaysDataType`（Resolution 行）
+///
+/// 所有方式都失败时返回 None，调用方应自行降级
+pub fn detect_resolution() -> Option<(u32, u32)> {
+    if cfg!(target_os = "macos") {
+        return command_stdout("system_profiler", &["SPDisplaysDataType"])
+            .and_then(|out| parse_system_profiler(&out));
+    }
+    if env_var("WAYLAND_DISPLAY").is_some()
+        && let Some(res) = command_stdout("wlr-randr", &[]).and_then(|out| parse_wlr_randr(&out))
+    {
+        return Some(res);
+    }
+    command_stdout("xrandr", &["--current"]).and_then(|out| parse_xrandr(&out))
+}
+
+/// 屏幕分辨率：配置的 screen 优先，其次检测主显示器，最后取检测到的第一个显示器
+pub fn screen_resolution(configured: Option<&str>) -> Option<(u32, u32)> {
+    if let Some(res) = configured.and_then(parse_dimensions) {
+        return Some(res);
+    }
+    detect_resolution().or_else(|| detect_monitors().first().map(|m| (m.width, m.height)))
+}
+
+/// 测试中代替真实环境的假命令目录和环境变量，只对设置它的线程生效，
+/// 测试可以并行执行而不修改进程环境（见 setter::tests::Stubs）
+#[cfg(test)]
+pub(crate) struct StubEnv {
+    pub dir: PathBuf,
+    pub vars: std::collections::HashMap<String, OsString>,
+}
+
+#[cfg(test)]
+thread_local! {
+    pub(crate) static STUB_ENV: std::cell::RefCell<Option<StubEnv>> = const { std::cell::RefCell::new(None) };
+}
+
+/// 读取检测显示器和设置后端用到的环境变量；测试中只读取 STUB_ENV 中的值
+pub(crate) fn env_var(key: &str) -> Option<OsString> {
+    #[cfg(test)]
+    if let Some(value) = STUB_ENV.with_borrow(|env| env.as_ref().map(|env| env.vars.get(key).cloned())) {
+        return value;
+    }
+    std::env::var_os(key)
+}
+
+/// 要执行的外部程序，由 Command 在 $PATH 中查找；测试中指向 STUB_ENV 目录中的假命令
+pub(crate) fn program(name: &str) -> PathBuf {
+    #[cfg(test)]
+    if let Some(dir) = STUB_ENV.with_borrow(|env| env.as_ref().map(|env| env.dir.clone())) {
+        return dir.join(name);
+    }
+    PathBuf::from(name)
+}
+
+/// 执行命令并返回标准输出，命令不存在或失败时返回 None
+pub(crate) fn command_stdout(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(self::program(program)).args(args).output().ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        None
+    }
+}
+
+/// 解析 "WxH" 形式的片段（允许两侧有多余字符，如 "1920x1080+0+0"）
+fn parse_dimensions(token: &str) -> Option<(u32, u32)> {
+    let (w, h) = token.split_once('x')?;
+    let w: u32 = w.trim().parse().ok()?;
+    let h: u32 = h
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .ok()?;
+    Some((w, h))
+}
+
+/// 优先取 primary 输出行中的几何尺寸（已考虑旋转），形如 "DP-1 connected primary 2560x1440+0+0 ..."；
+/// 没有 primary 时取第一个当前模式，所在行形如 "   1920x1080     60.00*+  59.94"
+fn parse_xrandr(output: &str) -> Option<(u32, u32)> {
+    let primary = output
+        .lines()
+        .filter(|line| !line.starts_with(' ') && line.contains(" connected primary"))
+        .find_map(|line| line.split_whitespace().find_map(parse_geometry))
+        .map(|(w, h, _, _)| (w, h));
+    primary.or_else(|| {
+        output
+            .lines()
+            .filter(|line| line.starts_with(' ') && line.contains('*'))
+            .find_map(|line| line.split_whitespace().next().and_then(parse_dimensions))
+    })
+}
+
+/// 解析 X11 几何尺寸 "WxH+X+Y"，偏移可以为负，如 "1920x1080-1920+0"
+fn parse_geometry(token: &str) -> Option<(u32, u32, i32, i32)> {
+    let (w, rest) = token.split_once('x')?;
+    let split = rest.find(['+', '-'])?;
+    let (h, offset) = rest.split_at(split);
+    let y_start = offset[1..].find(['+', '-'])? + 1;
+    let (x, y) = offset.split_at(y_start);
+    Some((w.parse().ok()?, h.parse().ok()?, x.parse().ok()?, y.parse().ok()?))
+}
+
+/// 旋转 90° / 270° 的输出宽高互换
+fn rotated(transform: &str) -> bool {
+    matches!(transform, "90" | "270" | "flipped-90" | "flipped-270")
+}
+
+/// wlr-randr 输出中当前模式所在行形如 "    1920x1080 px, 60.000000 Hz (preferred, current)"
+fn parse_wlr_randr(output: &str) -> Option<(u32, u32)> {
+    output
+        .lines()
+        .filter(|line| line.contains("current"))
+        .find_map(|line| line.split_whitespace().next().and_then(parse_dimensions))
+}
+
+/// system_profiler 输出形如 "Resolution: 2560 x 1600 Retina"
+fn parse_system_profiler(output: &str) -> Option<(u32, u32)> {
+    output.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix("Resolution:")?;
+        let mut nums = rest
+            .split(|c: char| !c.is_ascii_digit())
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| s.parse::<u32>().ok());
+        Some((nums.next()?, nums.next()?))
+    })
+}
+
+/// hyprctl monitors -j 输出 JSON 数组：[{"name", "width", "height", "x", "y", "scale", "transform", ...}]
+///
+/// width / height 是未旋转的模式尺寸，transform 为奇数（90° / 270°）时互换；x / y 是逻辑坐标
+fn parse_hyprctl(output: &str) -> Option<Vec<Monitor>> {
+    let list: Vec<serde_json::Value> = serde_json::from_str(output).ok()?;
+    Some(
+        list.iter()
+            .filter_map(|m| {
+                let (width, height) = (m["width"].as_u64()? as u32, m["height"].as_u64()? as u32);
+                let (width, height) =
+                    if m["transform"].as_u64().unwrap_or(0) % 2 == 1 { (height, width) } else { (width, height) };
+                Some(Monitor {
+                    name: m["name"].as_str()?.to_string(),
+                    width,
+                    height,
+                    x: m["x"].as_i64().unwrap_or(0) as i32,
+                    y: m["y"].as_i64().unwrap_or(0) as i32,
+                    scale: m["scale"].as_f64().unwrap_or(1.0),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// swaymsg -t get_outputs -r 输出 JSON 数组，分辨率取 current_mode（按 transform 旋转），
+/// 位置取 rect（逻辑坐标），缩放取 scale
+fn parse_sway_outputs(output: &str) -> Option<Vec<Monitor>> {
+    let list: Vec<serde_json::Value> = serde_json::from_str(output).ok()?;
+    Some(
+        list.iter()
+            .filter(|m| m["active"].as_bool().unwrap_or(true))
+            .filter_map(|m| {
+                let (width, height) =
+                    (m["current_mode"]["width"].as_u64()? as u32, m["current_mode"]["height"].as_u64()? as u32);
+                let (width, height) =
+                    if rotated(m["transform"].as_str().unwrap_or("")) { (height, width) } else { (width, height) };
+                Some(Monitor {
+                    name: m["name"].as_str()?.to_string(),
+                    width,
+                    height,
+                    x: m["rect"]["x"].as_i64().unwrap_or(0) as i32,
+                    y: m["rect"]["y"].as_i64().unwrap_or(0) as i32,
+                    scale: m["scale"].as_f64().unwrap_or(1.0),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// wlr-randr 输出按显示器分块：顶格的行是输出名，缩进行中有 "Position: x,y"（逻辑坐标）、"Transform: 90"、
+/// "Scale: 2.000000" 和带 current 的模式
+fn parse_wlr_randr_outputs(output: &str) -> Vec<Monitor> {
+    let finish = |m: Monitor, enabled: bool, transform: bool| {
+        let m = if transform { Monitor { width: m.height, height: m.width, ..m } } else { m };
+        Some(m).filter(|m| enabled && m.width > 0)
+    };
+    let mut monitors = Vec::new();
+    let mut current: Option<Monitor> = None;
+    let mut enabled = true;
+    let mut transform = false;
+    for line in output.lines() {
+        if !line.starts_with(' ') && !line.trim().is_empty() {
+            if let Some(m) = current.take().and_then(|m| finish(m, enabled, transform)) {
+                monitors.push(m);
+            }
+            let name = line.split_whitespace().next().unwrap_or_default().to_string();
+            current = Some(Monitor { name, width: 0, height: 0, x: 0, y: 0, scale: 1.0 });
+            enabled = true;
+            transform = false;
+            continue;
+        }
+        let Some(m) = current.as_mut() else { continue };
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("Enabled:") {
+            enabled = rest.trim() == "yes";
+        } else if let Some(rest) = trimmed.strip_prefix("Transform:") {
+            transform = rotated(rest.trim());
+        } else if let Some(rest) = trimmed.strip_prefix("Position:")
+            && let Some((x, y)) = rest.trim().split_once(',')
+        {
+            m.x = x.trim().parse().unwrap_or(0);
+            m.y = y.trim().parse().unwrap_or(0);
+        } else if let Some(rest) = trimmed.strip_prefix("Scale:") {
+            m.scale = rest.trim().parse().unwrap_or(1.0);
+        } else if trimmed.contains("current")
+            && let Some((w, h)) = trimmed.split_whitespace().next().and_then(parse_dimensions)
+        {
+            m.width = w;
+            m.height = h;
+        }
+    }
+    if let Some(m) = current.and_then(|m| finish(m, enabled, transform)) {
+        monitors.push(m);
+    }
+    monitors
+}
+
+/// xrandr 中已连接的输出形如 "DP-1 connected primary 2560x1440+0+0 (normal left ...) 597mm x 336mm"，
+/// 几何尺寸已按旋转互换宽高；没有几何尺寸的输出（已连接但未启用）跳过
+fn parse_xrandr_outputs(output: &str) -> Vec<Monitor> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with(' ') && line.contains(" connected"))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?.to_string();
+            let (width, height, x, y) = parts.find_map(parse_geometry)?;
+            Some(Monitor { name, width, height, x, y, scale: 1.0 })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn monitor(name: &str, width: u32, height: u32, x: i32, y: i32) -> Monitor {
+        Monitor { name: name.to_string(), width, height, x, y, scale: 1.0 }
+    }
+
+    fn scaled(name: &str, width: u32, height: u32, x: i32, y: i32, scale: f64) -> Monitor {
+        Monitor { scale, ..monitor(name, width, height, x, y) }
+    }
+
+    /// 笔记本屏幕在前，外接显示器为 primary 且竖屏放在左侧，另有一个已连接但未启用的输出
+    const XRANDR: &str = "\
+Screen 0: minimum 320 x 200, current 4000 x 2560, maximum 16384 x 16384
+eDP-1 connected 2560x1600+1440+0 (normal left inverted right x axis y axis) 302mm x 189mm
+   2560x1600     60.00*+  48.00
+   1920x1200     60.00
+DP-1 connected primary 1440x2560-0+0 left (normal left inverted right x axis y axis) 597mm x 336mm
+   2560x1440     59.95*+
+   1920x1080     60.00
+HDMI-1 connected (normal left inverted right x axis y axis)
+   1920x1080     60.00 +
+DP-2 disconnected (normal left inverted right x axis y axis)
+";
+
+    #[test]
+    fn xrandr_prefers_primary() {
+        assert_eq!(parse_xrandr(XRANDR), Some((1440, 2560)));
+        // 没有 primary 时取第一个当前模式
+        assert_eq!(parse_xrandr(&XRANDR.replace(" primary", "")), Some((2560, 1600)));
+        assert_eq!(parse_xrandr("Screen 0: minimum 320 x 200\n"), None);
+    }
+
+    #[test]
+    fn xrandr_outputs() {
+        assert_eq!(
+            parse_xrandr_outputs(XRANDR),
+            [monitor("eDP-1", 2560, 1600, 1440, 0), monitor("DP-1", 1440, 2560, 0, 0)]
+        );
+        let negative = "HDMI-1 connected 1920x1080-1920+0 (normal) 0mm x 0mm\n";
+        assert_eq!(parse_xrandr_outputs(negative), [monitor("HDMI-1", 1920, 1080, -1920, 0)]);
+    }
+
+    const WLR_RANDR: &str = "\
+eDP-1 \"Sharp Corporation 0x1453 (eDP-1)\"
+  Make: Sharp Corporation
+  Physical size: 290x180 mm
+  Enabled: yes
+  Modes:
+    2560x1600 px, 60.002998 Hz (preferred, current)
+    1920x1200 px, 59.950001 Hz
+  Position: 0,0
+  Transform: normal
+  Scale: 2.000000
+DP-1 \"Dell Inc. DELL U2720Q\"
+  Enabled: yes
+  Modes:
+    3840x2160 px, 60.000000 Hz (preferred, current)
+  Position: 1280,0
+  Transform: 90
+  Scale: 1.500000
+HDMI-A-1 \"Unknown\"
+  Enabled: no
+  Modes:
+    1920x1080 px, 60.000000 Hz (preferred)
+";
+
+    #[test]
+    fn wlr_randr() {
+        assert_eq!(parse_wlr_randr(WLR_RANDR), Some((2560, 1600)));
+        // 缩放后仍使用物理像素，旋转 90° 时宽高互换，未启用的输出跳过
+        assert_eq!(
+            parse_wlr_randr_outputs(WLR_RANDR),
+            [scaled("eDP-1", 2560, 1600, 0, 0, 2.0), scaled("DP-1", 2160, 3840, 1280, 0, 1.5)]
+        );
+    }
+
+    #[test]
+    fn hyprctl() {
+        let json = r#"[
+            {"id": 0, "name": "eDP-1", "width": 2560, "height": 1600, "x": 0, "y": 0, "scale": 1.6, "transform": 0},
+            {"id": 1, "name": "DP-1", "width": 2560, "height": 1440, "x": 1600, "y": -200, "scale": 1.0, "transform": 3},
+            {"id": 2, "width": 1920, "height": 1080}
+        ]"#;
+        assert_eq!(
+            parse_hyprctl(json).unwrap(),
+            [scaled("eDP-1", 2560, 1600, 0, 0, 1.6), monitor("DP-1", 1440, 2560, 1600, -200)]
+        );
+        assert_eq!(parse_hyprctl("not json"), None);
+    }
+
+    #[test]
+    fn sway_outputs() {
+        let json = r#"[
+            {"name": "eDP-1", "active": true, "scale": 2.0, "transform": "normal",
+             "rect": {"x": 0, "y": 0, "width": 1280, "height": 800},
+             "current_mode": {"width": 2560, "height": 1600, "refresh": 60002}},
+            {"name": "DP-1", "active": true, "scale": 1.0, "transform": "flipped-270",
+             "rect": {"x": 1280, "y": 0, "width": 1440, "height": 2560},
+             "current_mode": {"width": 2560, "height": 1440, "refresh": 59951}},
+            {"name": "HDMI-A-1", "active": false, "rect": {"x": 0, "y": 0, "width": 0, "height": 0}}
+        ]"#;
+        assert_eq!(
+            parse_sway_outputs(json).unwrap(),
+            [scaled("eDP-1", 2560, 1600, 0, 0, 2.0), monitor("DP-1", 1440, 2560, 1280, 0)]
+        );
+    }
+
+    #[test]
+    fn canvas_rects() {
+        // 缩放 2 的笔记本在左，逻辑宽度 1280；右侧缩放 1 的显示器紧挨着它
+        let monitors = [scaled("eDP-1", 2560, 1600, 0, 0, 2.0), monitor("DP-1", 1920, 1080, 1280, 0)];
+        assert_eq!(canvas_scale(&monitors), 2.0);
+        assert_eq!(monitors[0].canvas_rect(2.0), (0, 0, 2560, 1600));
+        assert_eq!(monitors[1].canvas_rect(2.0), (2560, 0, 3840, 2160));
+        assert_eq!(bounding_box(&monitors), Some((0, 0, 6400, 2160)));
+        // 没有缩放时与像素坐标相同
+        assert_eq!(bounding_box(&[monitor("HDMI-1", 1920, 1080, -1920, 0)]), Some((-1920, 0, 1920, 1080)));
+    }
+
+    #[test]
+    fn system_profiler() {
+        let output = "\
+Graphics/Displays:
+
+    Apple M1 Pro:
+
+      Displays:
+        Color LCD:
+          Display Type: Built-in Liquid Retina XDR Display
+          Resolution: 3024 x 1964 Retina
+          Main Display: Yes
+        DELL U2720Q:
+          Resolution: 3840 x 2160 (2160p/4K UHD 1 - Ultra High Definition)
+";
+        assert_eq!(parse_system_profiler(output), Some((3024, 1964)));
+        assert_eq!(parse_system_profiler("Displays:\n"), None);
+    }
+
+    #[test]
+    fn dimensions() {
+        assert_eq!(parse_dimensions("1920x1080+0+0"), Some((1920, 1080)));
+        assert_eq!(parse_dimensions(" 2560 x 1440 "), Some((2560, 1440)));
+        assert_eq!(parse_dimensions("auto"), None);
+    }
+}
@@ -0,0 +1,126 @@
+// select.rs — 搜索结果选择策略模块
+// 决定从壁纸源返回的结果列表中下载哪一张（或哪几张）
+// 只依赖统一的 WallpaperInfo 结构，对所有 WallpaperSource 生效
+
+use crate::source::{WallpaperInfo, parse_resolution};
+use rand::seq::SliceRandom;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 已见过的壁纸最多记录的条数，超过后丢弃最旧的记录
+const SEEN_LIMIT: usize = 5000;
+
+/// 搜索结果的选择策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum SelectionStrategy {
+    /// 取第一条结果（由壁纸源的排序方式决定）
+    #[default]
+    First,
+    /// 随机选择
+    Random,
+    /// 像素数最多的优先
+    HighestResolution,
+    /// 宽高比最接近当前屏幕的优先
+    ClosestAspect,
+    /// 收藏数 / 点赞数最多的优先
+    MostFavorited,
+    /// 优先选择之前没有下载过的
+    Unseen,
+}
+
+/// 已下载过的壁纸记录，保存在 state_dir/seen.json
+/// 每条记录为 "{source}:{id}"
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SeenHistory {
+    #[serde(default)]
+    entries: VecDeque<String>,
+}
+
+impl SeenHistory {
+    fn path(state_dir: &Path) -> PathBuf {
+        state_dir.join("seen.json")
+    }
+
+    fn key(info: &WallpaperInfo) -> String {
+        format!("{}:{}", info.source, info.id)
+    }
+
+    /// 读取记录文件，不存在或损坏时视为空记录
+    pub fn load(state_dir: &Path) -> Self {
+        fs::read_to_string(Self::path(state_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, info: &WallpaperInfo) -> bool {
+        let key = Self::key(info);
+        self.entries.iter().any(|e| *e == key)
+    }
+
+    /// 记录一张已下载的壁纸并写回文件
+    pub fn record(state_dir: &Path, info: &WallpaperInfo) -> std::io::Result<()> {
+        let mut history = Self::load(state_dir);
+        if !history.contains(info) {
+            history.entries.push_back(Self::key(info));
+            while history.entries.len() > SEEN_LIMIT {
+                history.entries.pop_front();
+            }
+        }
+        let json = serde_json::to_string_pretty(&history).map_err(std::io::Error::other)?;
+        fs::write(Self::path(state_dir), json)
+    }
+}
+
+/// 按选择策略对搜索结果重新排序，调用方按顺序取前 N 张即可
+///
+/// # 参数
+/// - `wallpapers`: 壁纸源返回的原始结果
+/// - `strategy`: 选择策略
+/// - `screen`: 目标屏幕分辨率，仅 ClosestAspect 使用
+/// - `state_dir`: 状态目录，仅 Unseen 使用
+pub fn order(
+    mut wallpapers: Vec<WallpaperInfo>,
+    strategy: SelectionStrategy,
+    screen: (u32, u32),
+    state_dir: &Path,
+) -> Vec<WallpaperInfo> {
+    match strategy {
+        SelectionStrategy::First => {}
+        SelectionStrategy::Random => wallpapers.shuffle(&mut rand::thread_rng()),
+        SelectionStrategy::HighestResolution => {
+            wallpapers.sort_by_key(|w| {
+                let (width, height) = parse_resolution(&w.resolution);
+                std::cmp::Reverse(width as u64 * height as u64)
+            });
+        }
+        SelectionStrategy::ClosestAspect => {
+            let (sw, sh) = screen;
+            if sw > 0 && sh > 0 {
+                let target = sw as f64 / sh as f64;
+                // 无法解析分辨率的结果排到最后
+                let distance = |w: &WallpaperInfo| match parse_resolution(&w.resolution) {
+                    (width, height) if width > 0 && height > 0 => {
+                        (width as f64 / height as f64 - target).abs()
+                    }
+                    _ => f64::MAX,
+                };
+                wallpapers.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+            }
+        }
+        SelectionStrategy::MostFavorited => {
+            wallpapers.sort_by_key(|w| std::cmp::Reverse(w.favorites.unwrap_or(0)));
+        }
+        SelectionStrategy::Unseen => {
+            // 稳定分区：未见过的保持原顺序排在前面，全部见过时等同于 First
+            let history = SeenHistory::load(state_dir);
+            wallpapers.sort_by_key(|w| history.contains(w));
+        }
+    }
+    wallpapers
+}
@@ -0,0 +1,189 @@
+// setter/command.rs — 自定义命令壁纸设置后端
+// 按顺序执行 [setter] command 中的命令，参数中的占位符替换为本次设置的信息；
+// 第一条命令设置壁纸，之后的命令（如通知状态栏刷新）每次换壁纸只执行一次
+
+use super::{run_command, SetContext, WallpaperSetter};
+use crate::config::WallpaperMode;
+use std::path::{Path, PathBuf};
+
+/// 执行用户配置的命令，第一个元素为程序名，其余为参数
+pub struct CommandSetter {
+    commands: Vec<Vec<String>>,
+    /// 命令自带过渡动画，对应 [setter] command_animates
+    animates: bool,
+}
+
+impl CommandSetter {
+    pub fn new(commands: Vec<&[String]>, animates: bool) -> Self {
+        Self {
+            commands: commands.into_iter().map(<[String]>::to_vec).collect(),
+            animates,
+        }
+    }
+}
+
+/// 替换单个参数中的占位符: {path} {monitor} {theme} {mode}
+fn render(arg: &str, path: &Path, ctx: &SetContext) -> String {
+    arg.replace("{path}", &path.to_string_lossy())
+        .replace("{monitor}", ctx.monitor.as_deref().unwrap_or(""))
+        .replace("{theme}", ctx.theme.as_deref().unwrap_or(""))
+        .replace("{mode}", ctx.mode.as_str())
+}
+
+/// 参数中是否含有占位符
+fn has_placeholder(arg: &str) -> bool {
+    ["{path}", "{monitor}", "{theme}", "{mode}"].iter().any(|p| arg.contains(p))
+}
+
+/// 替换所有参数中的占位符，占位符取值为空（如未指定显示器）时去掉对应的选项：
+///
+/// - 整个参数为空时去掉它，前一个参数是不含占位符的选项（以 - 开头）时一并去掉，
+///   如 `["-o", "{monitor}"]`
+/// - `--flag={monitor}` 形式的参数在 = 之后为空时整个去掉
+fn render_args(args: &[String], path: &Path, ctx: &SetContext) -> Vec<String> {
+    let mut rendered: Vec<String> = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        let value = render(arg, path, ctx);
+        let empty_option = arg.starts_with('-')
+            && arg.split_once('=').is_some_and(|(_, template)| has_placeholder(template))
+            && value.ends_with('=');
+        if !value.is_empty() && !empty_option {
+            rendered.push(value);
+            continue;
+        }
+        if value.is_empty() && i > 0 && args[i - 1].starts_with('-') && !has_placeholder(&args[i - 1]) {
+            rendered.pop();
+        }
+    }
+    rendered
+}
+
+/// 替换占位符后执行一条命令
+fn run(command: &[String], path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+    let program = render(&command[0], path, ctx);
+    let args = render_args(&command[1..], path, ctx);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_command(&program, &args).map(|_| ())
+}
+
+impl WallpaperSetter for CommandSetter {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    /// 依次执行每条命令，任何一条失败都会中止并把它的 stderr 作为错误返回；
+    /// 过渡动画的中间帧只执行第一条
+    fn set(&self, path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        let Some((first, rest)) = self.commands.split_first() else { return Ok(()) };
+        run(first, path, ctx)?;
+        if !ctx.frame {
+            for command in rest {
+                run(command, path, ctx)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 第一条命令为每个显示器各执行一次，其余命令在全部设置完后只执行一次，
+    /// 此时 {monitor} 为空、{path} 为第一个显示器的图片
+    fn set_outputs(&self, outputs: &[(PathBuf, String)], ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        let Some((first, rest)) = self.commands.split_first() else { return Ok(()) };
+        for (path, monitor) in outputs {
+            run(first, path, &SetContext { monitor: Some(monitor.clone()), ..ctx.clone() })?;
+        }
+        if let Some((path, _)) = outputs.first()
+            && !ctx.frame
+        {
+            let ctx = SetContext { monitor: None, ..ctx.clone() };
+            for command in rest {
+                run(command, path, &ctx)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 模式通过 {mode} 交给用户的命令处理
+    fn supports_mode(&self, _mode: WallpaperMode) -> bool {
+        true
+    }
+
+    fn animates(&self) -> bool {
+        self.animates
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::setter::tests::Stubs;
+
+    fn setter(commands: &[&[&str]]) -> CommandSetter {
+        let commands: Vec<Vec<String>> =
+            commands.iter().map(|c| c.iter().map(|s| s.to_string()).collect()).collect();
+        CommandSetter::new(commands.iter().map(Vec::as_slice).collect(), false)
+    }
+
+    fn ctx(monitor: Option<&str>) -> SetContext {
+        SetContext {
+            theme: Some("nord".to_string()),
+            monitor: monitor.map(str::to_string),
+            mode: WallpaperMode::Fit,
+            frame: false,
+        }
+    }
+
+    #[test]
+    fn substitutes_placeholders() {
+        let stubs = Stubs::new(&["tool"]);
+        let setter = setter(&[&["tool", "{path}", "-o", "{monitor}", "--theme={theme}", "{mode}"]]);
+        setter.set(Path::new("/tmp/a b.png"), &ctx(Some("DP-1"))).unwrap();
+        assert_eq!(stubs.calls(), ["tool /tmp/a b.png -o DP-1 --theme=nord fit"]);
+    }
+
+    #[test]
+    fn drops_options_with_empty_values() {
+        let stubs = Stubs::new(&["swww"]);
+        let setter = setter(&[
+            &["swww", "img", "-o", "{monitor}", "{path}"],
+            &["swww", "img", "--outputs={monitor}", "{path}"],
+            &["swww", "img", "{monitor}", "{path}"],
+        ]);
+        setter.set(Path::new("/tmp/a.png"), &ctx(None)).unwrap();
+        assert_eq!(stubs.calls(), ["swww img /tmp/a.png"; 3]);
+    }
+
+    #[test]
+    fn stops_at_first_failure_with_stderr() {
+        let stubs = Stubs::new(&["first", "third"]);
+        stubs.script("second", "echo 'no such output' >&2\nexit 1");
+        let setter = setter(&[&["first", "{path}"], &["second", "{theme}"], &["third"]]);
+        let err = setter.set(Path::new("/tmp/a.png"), &ctx(None)).unwrap_err();
+        assert!(err.to_string().contains("no such output"), "{}", err);
+        assert_eq!(stubs.calls(), ["first /tmp/a.png", "second nord"]);
+    }
+
+    #[test]
+    fn follow_up_commands_run_once() {
+        let stubs = Stubs::new(&["swww", "pkill"]);
+        let setter = setter(&[&["swww", "img", "-o", "{monitor}", "{path}"], &["pkill", "-SIGUSR2", "waybar"]]);
+        let outputs = [
+            (PathBuf::from("/tmp/a.png"), "DP-1".to_string()),
+            (PathBuf::from("/tmp/b.png"), "HDMI-A-1".to_string()),
+        ];
+        setter.set_outputs(&outputs, &ctx(None)).unwrap();
+        assert_eq!(
+            stubs.calls(),
+            ["swww img -o DP-1 /tmp/a.png", "swww img -o HDMI-A-1 /tmp/b.png", "pkill -SIGUSR2 waybar"]
+        );
+    }
+
+    #[test]
+    fn frames_skip_follow_up_commands() {
+        let stubs = Stubs::new(&["swww", "pkill"]);
+        let setter = setter(&[&["swww", "img", "{path}"], &["pkill", "-SIGUSR2", "waybar"]]);
+        let frame = SetContext { frame: true, ..ctx(None) };
+        setter.set(Path::new("/tmp/frame.jpg"), &frame).unwrap();
+        setter.set_outputs(&[(PathBuf::from("/tmp/frame.jpg"), "DP-1".to_string())], &frame).unwrap();
+        assert_eq!(stubs.calls(), ["swww img /tmp/frame.jpg", "swww img /tmp/frame.jpg"]);
+    }
+}
@@ -0,0 +1,541 @@
+// setter/linux.rs — Wayland 合成器与 X11 窗口管理器的壁纸设置后端
+// 通过 std::process::Command 调用 swww、hyprpaper、swaybg、feh、xwallpaper、nitrogen
+
+use super::{in_path, run_command, SetContext, WallpaperSetter};
+use crate::config::WallpaperMode;
+use crate::screen::{self, Monitor};
+use rust_i18n::t;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+fn path_str(path: &Path) -> Result<&str, Box<dyn std::error::Error>> {
+    Ok(path.to_str().ok_or(t!("error_utf8"))?)
+}
+
+/// 后台启动常驻进程，不等待其退出
+fn spawn_detached(program: &str, args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    Command::new(screen::program(program))
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| t!("error_setter_failed", backend => program, reason => e.to_string()))?;
+    Ok(())
+}
+
+/// swww：`swww img [-o <monitor>] <path>`，守护进程未运行时先启动 swww-daemon
+pub struct Swww;
+
+impl WallpaperSetter for Swww {
+    fn name(&self) -> &'static str {
+        "swww"
+    }
+
+    fn set(&self, path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        if run_command("swww", &["query"]).is_err() && in_path("swww-daemon") {
+            spawn_detached("swww-daemon", &[])?;
+            std::thread::sleep(Duration::from_millis(500));
+        }
+        let resize = match ctx.mode {
+            WallpaperMode::Fit => "fit",
+            WallpaperMode::Center => "no",
+            _ => "crop",
+        };
+        let mut args = vec!["img", "--resize", resize];
+        if let Some(monitor) = &ctx.monitor {
+            args.extend(["-o", monitor]);
+        }
+        args.push(path_str(path)?);
+        run_command("swww", &args).map(|_| ())
+    }
+
+    fn supports_mode(&self, mode: WallpaperMode) -> bool {
+        matches!(mode, WallpaperMode::Crop | WallpaperMode::Fit | WallpaperMode::Center)
+    }
+
+    /// swww img 自带过渡动画
+    fn animates(&self) -> bool {
+        true
+    }
+
+    /// 优先使用合成器的检测结果（带位置）；检测不到时解析 `swww query`，
+    /// 其输出形如 "DP-1: 2560x1440, scale: 1, currently displaying: ..."，没有位置信息，按顺序横向排列
+    fn monitors(&self) -> Vec<Monitor> {
+        let detected = screen::detect_monitors();
+        if !detected.is_empty() {
+            return detected;
+        }
+        let Ok(output) = run_command("swww", &["query"]) else { return Vec::new() };
+        let mut x = 0;
+        output
+            .lines()
+            .filter_map(|line| {
+                let line = line.trim().trim_start_matches(':').trim();
+                let (name, rest) = line.split_once(':')?;
+                let size = rest.split(',').next()?.trim();
+                let (w, h) = size.split_once('x')?;
+                let monitor = Monitor {
+                    name: name.trim().to_string(),
+                    width: w.parse().ok()?,
+                    height: h.parse().ok()?,
+                    x,
+                    y: 0,
+                    scale: 1.0,
+                };
+                x += monitor.width as i32;
+                Some(monitor)
+            })
+            .collect()
+    }
+}
+
+/// hyprpaper：通过 hyprctl 预加载并设置到所有显示器，再卸载不再使用的图片
+pub struct Hyprpaper;
+
+impl Hyprpaper {
+    /// hyprctl 即使 hyprpaper 报错也返回 0，需要检查回复是否为 "ok"
+    fn request(args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut full = vec!["hyprpaper"];
+        full.extend_from_slice(args);
+        let reply = run_command("hyprctl", &full)?;
+        if reply.trim().eq_ignore_ascii_case("ok") {
+            Ok(())
+        } else {
+            Err(t!("error_setter_failed", backend => "hyprpaper", reason => reply.trim()).into())
+        }
+    }
+}
+
+impl WallpaperSetter for Hyprpaper {
+    fn name(&self) -> &'static str {
+        "hyprpaper"
+    }
+
+    fn set(&self, path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path_str(path)?;
+        Self::request(&["preload", path])?;
+        // 显示器名留空表示所有显示器；contain: / tile: 前缀指定缩放模式
+        let monitor = ctx.monitor.as_deref().unwrap_or("");
+        let prefix = match ctx.mode {
+            WallpaperMode::Fit => "contain:",
+            WallpaperMode::Tile => "tile:",
+            _ => "",
+        };
+        Self::request(&["wallpaper", &format!("{},{}{}", monitor, prefix, path)])?;
+        let _ = Self::request(&["unload", "unused"]);
+        Ok(())
+    }
+
+    fn supports_mode(&self, mode: WallpaperMode) -> bool {
+        matches!(mode, WallpaperMode::Crop | WallpaperMode::Fit | WallpaperMode::Tile)
+    }
+}
+
+/// swaybg：在 Sway 中通过 swaymsg 设置；其他合成器直接启动新的 swaybg 再结束旧进程
+pub struct Swaybg;
+
+impl Swaybg {
+    fn use_swaymsg() -> bool {
+        screen::env_var("SWAYSOCK").is_some() && in_path("swaymsg")
+    }
+
+    /// swaybg 和 swaymsg 使用的模式名
+    fn mode(mode: WallpaperMode) -> &'static str {
+        match mode {
+            WallpaperMode::Fit => "fit",
+            WallpaperMode::Stretch => "stretch",
+            WallpaperMode::Center => "center",
+            WallpaperMode::Tile => "tile",
+            _ => "fill",
+        }
+    }
+
+    /// swaymsg 会把参数拼成一条 sway 命令再解析，路径需要加引号
+    fn swaymsg(path: &str, output: &str, mode: WallpaperMode) -> Result<(), Box<dyn std::error::Error>> {
+        let quoted = format!("\"{}\"", path.replace('"', "\\\""));
+        run_command("swaymsg", &["output", output, "bg", &quoted, Self::mode(mode)]).map(|_| ())
+    }
+
+    /// 启动一个 swaybg 进程显示 args 指定的壁纸，再结束 pattern 匹配到的旧进程
+    ///
+    /// 先启动新进程、显示后再结束旧进程，避免切换时闪出空白背景
+    fn respawn(args: &[&str], pattern: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let old_pids: Vec<String> = run_command("pgrep", &["-f", pattern])
+            .map(|out| out.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+        spawn_detached("swaybg", args)?;
+        if !old_pids.is_empty() {
+            std::thread::sleep(Duration::from_millis(300));
+            let mut kill_args = vec!["-TERM"];
+            kill_args.extend(old_pids.iter().map(String::as_str));
+            let _ = run_command("kill", &kill_args);
+        }
+        Ok(())
+    }
+}
+
+impl WallpaperSetter for Swaybg {
+    fn name(&self) -> &'static str {
+        "swaybg"
+    }
+
+    fn set(&self, path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        let path = path_str(path)?;
+        if Self::use_swaymsg() {
+            return Self::swaymsg(path, ctx.monitor.as_deref().unwrap_or("*"), ctx.mode);
+        }
+        let mode = Self::mode(ctx.mode);
+        match &ctx.monitor {
+            // 只替换负责该显示器的 swaybg 进程
+            Some(monitor) => Self::respawn(
+                &["-o", monitor, "-i", path, "-m", mode],
+                &format!("^swaybg -o {} ", monitor),
+            ),
+            None => Self::respawn(&["-i", path, "-m", mode], "^swaybg( |$)"),
+        }
+    }
+
+    /// 不在 Sway 中时用一个 swaybg 进程显示所有显示器的壁纸
+    fn set_outputs(&self, outputs: &[(PathBuf, String)], ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        if Self::use_swaymsg() {
+            for (path, monitor) in outputs {
+                Self::swaymsg(path_str(path)?, monitor, ctx.mode)?;
+            }
+            return Ok(());
+        }
+        let mut args = Vec::new();
+        for (path, monitor) in outputs {
+            args.extend(["-o", monitor.as_str(), "-i", path_str(path)?, "-m", Self::mode(ctx.mode)]);
+        }
+        Self::respawn(&args, "^swaybg( |$)")
+    }
+
+    fn supports_mode(&self, mode: WallpaperMode) -> bool {
+        mode != WallpaperMode::Span
+    }
+}
+
+/// 显示器的 Xinerama 序号，feh / nitrogen 按它区分显示器
+///
+/// 取自 `xrandr --listmonitors`，与按位置排序的检测结果不一定相同
+fn head_index(monitor: &str) -> Result<usize, Box<dyn std::error::Error>> {
+    run_command("xrandr", &["--listmonitors"])
+        .ok()
+        .and_then(|output| parse_listmonitors(&output, monitor))
+        .ok_or_else(|| t!("error_unknown_monitor", monitor => monitor).into())
+}
+
+/// `xrandr --listmonitors` 每行形如 " 0: +*DP-1 2560/597x1440/336+0+0  DP-1"：
+/// 冒号前为序号，之后依次是带 + / * 标记的监视器名、几何尺寸和组成它的输出名
+fn parse_listmonitors(output: &str, monitor: &str) -> Option<usize> {
+    output.lines().find_map(|line| {
+        let (index, rest) = line.trim().split_once(':')?;
+        let index = index.parse().ok()?;
+        let mut parts = rest.split_whitespace();
+        let name = parts.next()?.trim_start_matches(['+', '*']);
+        (name == monitor || parts.skip(1).any(|output| output == monitor)).then_some(index)
+    })
+}
+
+/// feh：`feh --no-fehbg --bg-<mode> <path>...`，多张图片按顺序对应各个显示器
+pub struct Feh;
+
+impl Feh {
+    /// span 使用 --no-xinerama 把整个 X 屏幕当作一块
+    fn mode_args(mode: WallpaperMode) -> Vec<&'static str> {
+        match mode {
+            WallpaperMode::Crop => vec!["--bg-fill"],
+            WallpaperMode::Fit => vec!["--bg-max"],
+            WallpaperMode::Stretch => vec!["--bg-scale"],
+            WallpaperMode::Center => vec!["--bg-center"],
+            WallpaperMode::Tile => vec!["--bg-tile"],
+            WallpaperMode::Span => vec!["--no-xinerama", "--bg-fill"],
+        }
+    }
+}
+
+impl WallpaperSetter for Feh {
+    fn name(&self) -> &'static str {
+        "feh"
+    }
+
+    fn set(&self, path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        // feh 每次都会重绘所有显示器，无法只设置其中一个
+        if ctx.monitor.is_some() {
+            return Err(t!("error_setter_no_monitor", backend => self.name()).into());
+        }
+        let mut args = vec!["--no-fehbg"];
+        args.extend(Self::mode_args(ctx.mode));
+        args.push(path_str(path)?);
+        run_command("feh", &args).map(|_| ())
+    }
+
+    fn set_outputs(&self, outputs: &[(PathBuf, String)], ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        let mut ordered = outputs
+            .iter()
+            .map(|(path, monitor)| Ok((head_index(monitor)?, path_str(path)?)))
+            .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+        ordered.sort_by_key(|(index, _)| *index);
+        let mut args = vec!["--no-fehbg"];
+        args.extend(Self::mode_args(ctx.mode));
+        args.extend(ordered.iter().map(|(_, path)| *path));
+        run_command("feh", &args).map(|_| ())
+    }
+
+    fn supports_mode(&self, _mode: WallpaperMode) -> bool {
+        true
+    }
+}
+
+/// xwallpaper：`xwallpaper [--output <monitor>] --<mode> <path>`
+pub struct Xwallpaper;
+
+impl Xwallpaper {
+    fn mode(mode: WallpaperMode) -> &'static str {
+        match mode {
+            WallpaperMode::Fit => "--maximize",
+            WallpaperMode::Stretch => "--stretch",
+            WallpaperMode::Center => "--center",
+            WallpaperMode::Tile => "--tile",
+            _ => "--zoom",
+        }
+    }
+}
+
+impl WallpaperSetter for Xwallpaper {
+    fn name(&self) -> &'static str {
+        "xwallpaper"
+    }
+
+    fn set(&self, path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        let mut args = Vec::new();
+        if let Some(monitor) = &ctx.monitor {
+            args.extend(["--output", monitor.as_str()]);
+        }
+        args.extend([Self::mode(ctx.mode), path_str(path)?]);
+        run_command("xwallpaper", &args).map(|_| ())
+    }
+
+    /// xwallpaper 每次调用都会重建根窗口背景，多个显示器需要在一次调用中给出
+    fn set_outputs(&self, outputs: &[(PathBuf, String)], ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        let mut args = Vec::new();
+        for (path, monitor) in outputs {
+            args.extend(["--output", monitor.as_str(), Self::mode(ctx.mode), path_str(path)?]);
+        }
+        run_command("xwallpaper", &args).map(|_| ())
+    }
+
+    fn supports_mode(&self, mode: WallpaperMode) -> bool {
+        mode != WallpaperMode::Span
+    }
+}
+
+/// nitrogen：`nitrogen [--head=N] --set-<mode> --save <path>`，--save 让下次登录时恢复
+pub struct Nitrogen;
+
+impl WallpaperSetter for Nitrogen {
+    fn name(&self) -> &'static str {
+        "nitrogen"
+    }
+
+    fn set(&self, path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        let head = match &ctx.monitor {
+            Some(monitor) => Some(format!("--head={}", head_index(monitor)?)),
+            None => None,
+        };
+        let mode = match ctx.mode {
+            WallpaperMode::Fit => "--set-zoom",
+            WallpaperMode::Stretch => "--set-scaled",
+            WallpaperMode::Center => "--set-centered",
+            WallpaperMode::Tile => "--set-tiled",
+            _ => "--set-zoom-fill",
+        };
+        let mut args: Vec<&str> = head.iter().map(String::as_str).collect();
+        args.extend([mode, "--save", path_str(path)?]);
+        run_command("nitrogen", &args).map(|_| ())
+    }
+
+    fn supports_mode(&self, mode: WallpaperMode) -> bool {
+        mode != WallpaperMode::Span
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::setter::tests::Stubs;
+
+    fn ctx(monitor: Option<&str>, mode: WallpaperMode) -> SetContext {
+        SetContext { monitor: monitor.map(str::to_string), mode, ..SetContext::default() }
+    }
+
+    fn outputs(list: &[(&str, &str)]) -> Vec<(PathBuf, String)> {
+        list.iter().map(|(path, monitor)| (PathBuf::from(path), monitor.to_string())).collect()
+    }
+
+    /// xrandr 的输出：HDMI-1 在左（Xinerama 序号 0），DP-1 在右（序号 1）
+    /// Xinerama 序号与位置顺序相反：HDMI-1 在左侧，序号却是 1
+    const LISTMONITORS: &str = "\
+Monitors: 2
+ 0: +*DP-1 1920/527x1080/296+1920+0  DP-1
+ 1: +HDMI-1 1920/527x1080/296+0+0  HDMI-1
+";
+
+    #[test]
+    fn swww_args() {
+        let stubs = Stubs::new(&["swww"]);
+        Swww.set(Path::new("/w/a.png"), &ctx(Some("DP-1"), WallpaperMode::Fit)).unwrap();
+        Swww.set(Path::new("/w/b.png"), &ctx(None, WallpaperMode::Center)).unwrap();
+        assert_eq!(
+            stubs.calls(),
+            [
+                "swww query",
+                "swww img --resize fit -o DP-1 /w/a.png",
+                "swww query",
+                "swww img --resize no /w/b.png",
+            ]
+        );
+    }
+
+    #[test]
+    fn swww_starts_daemon() {
+        let stubs = Stubs::new(&["swww-daemon"]);
+        stubs.script("swww", "[ \"$1\" = query ] && exit 1\nexit 0");
+        Swww.set(Path::new("/w/a.png"), &ctx(None, WallpaperMode::Crop)).unwrap();
+        let calls = stubs.calls();
+        assert_eq!(calls[0], "swww query");
+        assert!(calls.contains(&"swww-daemon ".to_string()));
+        assert_eq!(calls.last().unwrap(), "swww img --resize crop /w/a.png");
+    }
+
+    #[test]
+    fn hyprpaper_args() {
+        let stubs = Stubs::new(&[]);
+        stubs.script("hyprctl", "echo ok");
+        Hyprpaper.set(Path::new("/w/a.png"), &ctx(Some("DP-1"), WallpaperMode::Fit)).unwrap();
+        Hyprpaper.set(Path::new("/w/b.png"), &ctx(None, WallpaperMode::Tile)).unwrap();
+        assert_eq!(
+            stubs.calls(),
+            [
+                "hyprctl hyprpaper preload /w/a.png",
+                "hyprctl hyprpaper wallpaper DP-1,contain:/w/a.png",
+                "hyprctl hyprpaper unload unused",
+                "hyprctl hyprpaper preload /w/b.png",
+                "hyprctl hyprpaper wallpaper ,tile:/w/b.png",
+                "hyprctl hyprpaper unload unused",
+            ]
+        );
+    }
+
+    #[test]
+    fn hyprpaper_reports_error_reply() {
+        let stubs = Stubs::new(&[]);
+        stubs.script("hyprctl", "echo 'wallpaper failed (not preloaded)'");
+        assert!(Hyprpaper.set(Path::new("/w/a.png"), &ctx(None, WallpaperMode::Crop)).is_err());
+        assert_eq!(stubs.calls(), ["hyprctl hyprpaper preload /w/a.png"]);
+    }
+
+    #[test]
+    fn swaymsg_args() {
+        let stubs = Stubs::new(&["swaymsg"]);
+        stubs.set_env("SWAYSOCK", "/run/sway.sock");
+        Swaybg.set(Path::new("/w/a b.png"), &ctx(Some("HDMI-A-1"), WallpaperMode::Tile)).unwrap();
+        Swaybg.set(Path::new("/w/c.png"), &ctx(None, WallpaperMode::Crop)).unwrap();
+        Swaybg
+            .set_outputs(&outputs(&[("/w/d.png", "DP-1"), ("/w/e.png", "DP-2")]), &ctx(None, WallpaperMode::Fit))
+            .unwrap();
+        assert_eq!(
+            stubs.calls(),
+            [
+                "swaymsg output HDMI-A-1 bg \"/w/a b.png\" tile",
+                "swaymsg output * bg \"/w/c.png\" fill",
+                "swaymsg output DP-1 bg \"/w/d.png\" fit",
+                "swaymsg output DP-2 bg \"/w/e.png\" fit",
+            ]
+        );
+    }
+
+    #[test]
+    fn swaybg_args() {
+        let stubs = Stubs::new(&["swaybg", "pgrep"]);
+        Swaybg.set(Path::new("/w/a.png"), &ctx(Some("DP-1"), WallpaperMode::Center)).unwrap();
+        Swaybg
+            .set_outputs(&outputs(&[("/w/b.png", "DP-1"), ("/w/c.png", "DP-2")]), &ctx(None, WallpaperMode::Stretch))
+            .unwrap();
+        // swaybg 在后台启动，等它写完日志
+        std::thread::sleep(Duration::from_millis(200));
+        let calls = stubs.calls();
+        assert!(calls.contains(&"pgrep -f ^swaybg -o DP-1 ".to_string()));
+        assert!(calls.contains(&"swaybg -o DP-1 -i /w/a.png -m center".to_string()));
+        assert!(calls.contains(&"swaybg -o DP-1 -i /w/b.png -m stretch -o DP-2 -i /w/c.png -m stretch".to_string()));
+    }
+
+    #[test]
+    fn feh_args() {
+        let stubs = Stubs::new(&["feh"]);
+        stubs.script("xrandr", &echo(LISTMONITORS));
+        Feh.set(Path::new("/w/a.png"), &ctx(None, WallpaperMode::Span)).unwrap();
+        Feh.set(Path::new("/w/b.png"), &ctx(None, WallpaperMode::Fit)).unwrap();
+        // 按 Xinerama 序号排列，与传入顺序和位置无关
+        Feh.set_outputs(&outputs(&[("/w/hdmi.png", "HDMI-1"), ("/w/dp.png", "DP-1")]), &ctx(None, WallpaperMode::Crop))
+            .unwrap();
+        assert!(Feh.set(Path::new("/w/c.png"), &ctx(Some("DP-1"), WallpaperMode::Crop)).is_err());
+        let calls: Vec<String> = stubs.calls().into_iter().filter(|c| c.starts_with("feh")).collect();
+        assert_eq!(
+            calls,
+            [
+                "feh --no-fehbg --no-xinerama --bg-fill /w/a.png",
+                "feh --no-fehbg --bg-max /w/b.png",
+                "feh --no-fehbg --bg-fill /w/dp.png /w/hdmi.png",
+            ]
+        );
+    }
+
+    /// 让假命令原样输出 text
+    fn echo(text: &str) -> String {
+        text.lines().map(|line| format!("echo '{}'", line)).collect::<Vec<_>>().join("\n")
+    }
+
+    #[test]
+    fn listmonitors() {
+        assert_eq!(parse_listmonitors(LISTMONITORS, "DP-1"), Some(0));
+        assert_eq!(parse_listmonitors(LISTMONITORS, "HDMI-1"), Some(1));
+        assert_eq!(parse_listmonitors(LISTMONITORS, "Monitors"), None);
+        // 多个输出合成的监视器按任一输出名都能找到
+        let merged = " 0: +*big 3840/1054x1080/296+0+0  DP-1 HDMI-1\n";
+        assert_eq!(parse_listmonitors(merged, "big"), Some(0));
+        assert_eq!(parse_listmonitors(merged, "HDMI-1"), Some(0));
+    }
+
+    #[test]
+    fn xwallpaper_args() {
+        let stubs = Stubs::new(&["xwallpaper"]);
+        Xwallpaper.set(Path::new("/w/a.png"), &ctx(Some("DP-1"), WallpaperMode::Tile)).unwrap();
+        Xwallpaper
+            .set_outputs(&outputs(&[("/w/b.png", "DP-1"), ("/w/c.png", "HDMI-1")]), &ctx(None, WallpaperMode::Crop))
+            .unwrap();
+        assert_eq!(
+            stubs.calls(),
+            [
+                "xwallpaper --output DP-1 --tile /w/a.png",
+                "xwallpaper --output DP-1 --zoom /w/b.png --output HDMI-1 --zoom /w/c.png",
+            ]
+        );
+    }
+
+    #[test]
+    fn nitrogen_args() {
+        let stubs = Stubs::new(&["nitrogen"]);
+        stubs.script("xrandr", &echo(LISTMONITORS));
+        Nitrogen.set(Path::new("/w/a.png"), &ctx(Some("HDMI-1"), WallpaperMode::Crop)).unwrap();
+        Nitrogen.set(Path::new("/w/b.png"), &ctx(None, WallpaperMode::Stretch)).unwrap();
+        assert!(Nitrogen.set(Path::new("/w/c.png"), &ctx(Some("VGA-1"), WallpaperMode::Crop)).is_err());
+        let calls: Vec<String> = stubs.calls().into_iter().filter(|c| c.starts_with("nitrogen")).collect();
+        assert_eq!(
+            calls,
+            ["nitrogen --head=1 --set-zoom-fill --save /w/a.png", "nitrogen --set-scaled --save /w/b.png"]
+        );
+    }
+}
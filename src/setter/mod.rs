@@ -0,0 +1,476 @@
+// setter/mod.rs — 系统壁纸设置模块
+// 定义壁纸设置后端的通用 Trait，按 [setter] backend 配置或运行环境选择后端
+pub mod command;
+pub mod linux;
+pub mod render;
+pub mod transition;
+
+use crate::config::{AppConfig, SetterBackend, SetterConfig, WallpaperMode};
+use crate::library::Library;
+use crate::screen::{self, Monitor};
+use rust_i18n::t;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 一次设置壁纸的上下文，供需要额外信息的后端（如自定义命令）使用
+#[derive(Debug, Clone)]
+pub struct SetContext {
+    /// 壁纸的主题，来自壁纸库索引
+    pub theme: Option<String>,
+    /// 目标显示器，None 表示所有显示器
+    pub monitor: Option<String>,
+    /// 缩放模式
+    pub mode: WallpaperMode,
+    /// 设置的是过渡动画的中间帧，之后还会设置最终的壁纸
+    pub frame: bool,
+}
+
+impl Default for SetContext {
+    fn default() -> Self {
+        Self {
+            theme: None,
+            monitor: None,
+            mode: WallpaperMode::Crop,
+            frame: false,
+        }
+    }
+}
+
+/// 壁纸设置后端的抽象 Trait
+/// 每个后端对应一种设置方式（wallpaper 库、swww、feh 等外部工具或自定义命令）
+pub trait WallpaperSetter {
+    /// 后端名称，用于日志输出
+    fn name(&self) -> &'static str;
+
+    /// 将指定路径的图片设置为壁纸，ctx.monitor 为 Some 时只设置该显示器
+    fn set(&self, path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// 为多个显示器分别设置壁纸，outputs 为 (图片, 显示器名)
+    ///
+    /// 默认逐个调用 set；一次调用就要设置全部显示器的工具（如 feh）需要重写
+    fn set_outputs(&self, outputs: &[(PathBuf, String)], ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        for (path, monitor) in outputs {
+            let ctx = SetContext { monitor: Some(monitor.clone()), ..ctx.clone() };
+            self.set(path, &ctx)?;
+        }
+        Ok(())
+    }
+
+    /// 后端能否原生实现该缩放模式，不能时先按屏幕尺寸渲染图片再以 crop 模式设置
+    fn supports_mode(&self, mode: WallpaperMode) -> bool {
+        mode == WallpaperMode::Crop
+    }
+
+    /// 后端切换壁纸时是否自带过渡动画，为 false 时按 [setter.transition] 生成交叉淡入淡出
+    fn animates(&self) -> bool {
+        false
+    }
+
+    /// 后端能看到的显示器，默认使用 screen 模块的检测结果
+    fn monitors(&self) -> Vec<Monitor> {
+        screen::detect_monitors()
+    }
+}
+
+/// 使用 wallpaper 库设置壁纸
+/// 这个库会自动识别操作系统并调用相应的 API（macOS、Windows、GNOME、KDE 等）
+pub struct SystemSetter;
+
+impl WallpaperSetter for SystemSetter {
+    fn name(&self) -> &'static str {
+        "system"
+    }
+
+    fn set(&self, path: &Path, ctx: &SetContext) -> Result<(), Box<dyn std::error::Error>> {
+        // wallpaper 库只能设置所有显示器
+        if ctx.monitor.is_some() {
+            return Err(t!("error_setter_no_monitor", backend => self.name()).into());
+        }
+        let path_str = path.to_str().ok_or(t!("error_utf8"))?;
+        wallpaper::set_from_path(path_str).map_err(|e| t!("error_set_failed", reason => e.to_string()))?;
+        let mode = match ctx.mode {
+            WallpaperMode::Crop => wallpaper::Mode::Crop,
+            WallpaperMode::Fit => wallpaper::Mode::Fit,
+            WallpaperMode::Stretch => wallpaper::Mode::Stretch,
+            WallpaperMode::Center => wallpaper::Mode::Center,
+            WallpaperMode::Tile => wallpaper::Mode::Tile,
+            WallpaperMode::Span => wallpaper::Mode::Span,
+        };
+        // 不支持设置模式的桌面环境只能使用默认的 crop，此时忽略错误
+        match wallpaper::set_mode(mode) {
+            Err(e) if ctx.mode != WallpaperMode::Crop => Err(t!("error_set_failed", reason => e.to_string()).into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// macOS 上 wallpaper 库无法设置模式
+    fn supports_mode(&self, _mode: WallpaperMode) -> bool {
+        !cfg!(target_os = "macos")
+    }
+}
+
+/// 根据配置创建设置器
+///
+/// auto 时配置了 command 则使用自定义命令，否则自动检测
+pub fn build(config: &SetterConfig) -> Result<Box<dyn WallpaperSetter>, Box<dyn std::error::Error>> {
+    let backend = match config.backend {
+        SetterBackend::Auto if config.command.is_some() => SetterBackend::Command,
+        SetterBackend::Auto => detect(),
+        backend => backend,
+    };
+    Ok(match backend {
+        SetterBackend::Auto | SetterBackend::System => Box::new(SystemSetter),
+        SetterBackend::Swww => Box::new(linux::Swww),
+        SetterBackend::Hyprpaper => Box::new(linux::Hyprpaper),
+        SetterBackend::Swaybg => Box::new(linux::Swaybg),
+        SetterBackend::Feh => Box::new(linux::Feh),
+        SetterBackend::Xwallpaper => Box::new(linux::Xwallpaper),
+        SetterBackend::Nitrogen => Box::new(linux::Nitrogen),
+        SetterBackend::Command => {
+            let commands = config.command.as_ref().map(|c| c.commands()).unwrap_or_default();
+            if commands.is_empty() {
+                return Err(t!("error_setter_no_command").into());
+            }
+            Box::new(command::CommandSetter::new(commands, config.command_animates))
+        }
+    })
+}
+
+/// 根据桌面环境和已安装的工具选择后端
+///
+/// - Hyprland：swww，其次 hyprpaper
+/// - Sway：swaymsg / swaybg
+/// - GNOME、KDE 等完整桌面环境：wallpaper 库
+/// - 其他 Wayland 合成器：swww，其次 swaybg
+/// - 其他 X11 窗口管理器：feh、xwallpaper、nitrogen 中第一个已安装的
+/// - 都不满足时退回 wallpaper 库
+pub fn detect() -> SetterBackend {
+    if cfg!(any(target_os = "macos", target_os = "windows")) {
+        return SetterBackend::System;
+    }
+    let env_set = |key: &str| screen::env_var(key).is_some_and(|v| !v.is_empty());
+    let desktop = screen::env_var("XDG_CURRENT_DESKTOP").unwrap_or_default().to_string_lossy().to_lowercase();
+
+    if desktop.contains("hyprland") || env_set("HYPRLAND_INSTANCE_SIGNATURE") {
+        if in_path("swww") {
+            return SetterBackend::Swww;
+        }
+        if in_path("hyprctl") && in_path("hyprpaper") {
+            return SetterBackend::Hyprpaper;
+        }
+    }
+    if (desktop.contains("sway") || env_set("SWAYSOCK")) && (in_path("swaymsg") || in_path("swaybg")) {
+        return SetterBackend::Swaybg;
+    }
+    const DESKTOPS: &[&str] = &[
+        "gnome", "kde", "xfce", "cinnamon", "mate", "lxde", "lxqt", "deepin", "budgie", "unity", "pantheon",
+    ];
+    if DESKTOPS.iter().any(|d| desktop.contains(d)) {
+        return SetterBackend::System;
+    }
+    if env_set("WAYLAND_DISPLAY") {
+        if in_path("swww") {
+            return SetterBackend::Swww;
+        }
+        if in_path("swaybg") {
+            return SetterBackend::Swaybg;
+        }
+    }
+    if env_set("DISPLAY") {
+        for (program, backend) in [
+            ("feh", SetterBackend::Feh),
+            ("xwallpaper", SetterBackend::Xwallpaper),
+            ("nitrogen", SetterBackend::Nitrogen),
+        ] {
+            if in_path(program) {
+                return backend;
+            }
+        }
+    }
+    SetterBackend::System
+}
+
+/// 在 $PATH 中查找可执行文件
+pub fn in_path(program: &str) -> bool {
+    let Some(paths) = screen::env_var("PATH") else { return false };
+    std::env::split_paths(&paths).any(|dir| {
+        let candidate = dir.join(program);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            candidate
+                .metadata()
+                .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        }
+        #[cfg(not(unix))]
+        {
+            candidate.is_file() || candidate.with_extension("exe").is_file()
+        }
+    })
+}
+
+/// 执行外部命令并等待完成，返回标准输出；失败时将 stderr 作为错误信息
+pub(crate) fn run_command(program: &str, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    let output = Command::new(screen::program(program))
+        .args(args)
+        .output()
+        .map_err(|e| t!("error_setter_failed", backend => program, reason => e.to_string()))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Err(t!("error_setter_failed", backend => program, reason => stderr).into())
+    }
+}
+
+/// 设置时的上下文：主题从壁纸库索引中查询
+fn context(config: &AppConfig, path: &Path, monitor: Option<&str>, mode: WallpaperMode) -> SetContext {
+    SetContext {
+        theme: Library::load(&config.state_dir).get(path).and_then(|m| m.theme.clone()),
+        monitor: monitor.map(str::to_string),
+        mode,
+        frame: false,
+    }
+}
+
+/// 目标显示器的尺寸，monitor 为 None 时取整个屏幕的分辨率
+fn target_size(config: &AppConfig, monitor: Option<&str>) -> Result<Option<(u32, u32)>, Box<dyn std::error::Error>> {
+    Ok(match monitor {
+        Some(name) => monitors(config)?.into_iter().find(|m| m.name == name).map(|m| (m.width, m.height)),
+        None => screen::screen_resolution(config.search_defaults.screen.as_deref()),
+    })
+}
+
+/// 后端不支持 ctx.mode 时按目标屏幕尺寸渲染图片，保存到 state_dir/render/<显示器>-<时间戳> 并把 ctx.mode 改为 crop
+///
+/// 文件名带时间戳，避免按路径缓存图片的后端（如 macOS、GNOME 的 system）不刷新；
+/// 写入新图后删除该显示器之前的渲染结果。检测不到屏幕尺寸时原样返回，由后端按 crop 设置
+fn prerender(
+    config: &AppConfig,
+    setter: &dyn WallpaperSetter,
+    path: &Path,
+    ctx: &mut SetContext,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if setter.supports_mode(ctx.mode) {
+        return Ok(path.to_path_buf());
+    }
+    let mode = std::mem::replace(&mut ctx.mode, WallpaperMode::Crop);
+    let Some((width, height)) = target_size(config, ctx.monitor.as_deref())? else { return Ok(path.to_path_buf()) };
+
+    let img = image::open(path).map_err(|e| t!("error_pipeline_failed", step => "render", reason => e.to_string()))?;
+    let dir = config.state_dir.join("render");
+    std::fs::create_dir_all(&dir)?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    let name = ctx.monitor.as_deref().unwrap_or("all");
+    let dest = dir.join(format!("{}-{}.{}", name, chrono::Local::now().timestamp_millis(), ext));
+    crate::recolor::save_image(render::render_mode(img, mode, width, height), &dest)?;
+    // 只匹配 <显示器>-<时间戳>，不误删名字以该显示器名开头的其他显示器的渲染结果
+    let prefix = format!("{}-", name);
+    for entry in std::fs::read_dir(&dir)?.flatten() {
+        let file_name = entry.file_name();
+        let is_old = Path::new(&file_name)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .and_then(|s| s.strip_prefix(&prefix))
+            .is_some_and(|stamp| !stamp.is_empty() && stamp.bytes().all(|b| b.is_ascii_digit()));
+        if is_old && entry.path() != dest {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+    Ok(dest)
+}
+
+/// 将指定路径的图片设置为系统壁纸
+///
+/// # 参数
+/// - `path`: 图片的绝对路径
+/// - `monitor`: 目标显示器，None 表示所有显示器
+/// - `mode`: 缩放模式
+pub fn set_from_path(
+    config: &AppConfig,
+    path: impl AsRef<Path>,
+    monitor: Option<&str>,
+    mode: WallpaperMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let path_ref = path.as_ref();
+    let setter = build(&config.setter)?;
+    let mut ctx = context(config, path_ref, monitor, mode);
+
+    // 打印调试信息，让用户知道到底在设置哪张图、用的哪个后端
+    match monitor {
+        Some(m) => println!("  -> {} [{}] ({}, {})", path_ref.display(), m, setter.name(), mode.as_str()),
+        None => println!("  -> {} ({}, {})", path_ref.display(), setter.name(), mode.as_str()),
+    }
+
+    // 后端不能原生横跨显示器时，切成每个显示器一块分别设置
+    if mode == WallpaperMode::Span && !setter.supports_mode(mode) {
+        if monitor.is_some() {
+            ctx.mode = WallpaperMode::Crop;
+        } else {
+            let monitors = monitors(config)?;
+            if monitors.len() > 1 {
+                let outputs = render::span_slices(config, path_ref, &monitors)?;
+                ctx.mode = WallpaperMode::Crop;
+                return set_with_transition(config, setter.as_ref(), &outputs, &ctx);
+            }
+        }
+    }
+
+    let rendered = prerender(config, setter.as_ref(), path_ref, &mut ctx)?;
+    if !setter.animates() {
+        transition::crossfade(config, setter.as_ref(), &rendered, &ctx);
+    }
+    setter.set(&rendered, &ctx)
+}
+
+/// 为多个显示器分别设置壁纸
+pub fn set_outputs(
+    config: &AppConfig,
+    outputs: &[(PathBuf, String)],
+    mode: WallpaperMode,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let setter = build(&config.setter)?;
+    for (path, monitor) in outputs {
+        println!("  -> {} [{}] ({}, {})", path.display(), monitor, setter.name(), mode.as_str());
+    }
+    let Some((first, _)) = outputs.first() else { return Ok(()) };
+    let mut ctx = context(config, first, None, mode);
+    if !setter.supports_mode(mode) {
+        let mut rendered = Vec::new();
+        for (path, monitor) in outputs {
+            let mut output_ctx = SetContext { monitor: Some(monitor.clone()), ..ctx.clone() };
+            rendered.push((prerender(config, setter.as_ref(), path, &mut output_ctx)?, monitor.clone()));
+        }
+        ctx.mode = WallpaperMode::Crop;
+        return set_with_transition(config, setter.as_ref(), &rendered, &ctx);
+    }
+    set_with_transition(config, setter.as_ref(), outputs, &ctx)
+}
+
+/// 后端没有过渡动画时先让各显示器一起淡入，再设置最终的壁纸
+fn set_with_transition(
+    config: &AppConfig,
+    setter: &dyn WallpaperSetter,
+    outputs: &[(PathBuf, String)],
+    ctx: &SetContext,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !setter.animates() {
+        transition::crossfade_outputs(config, setter, outputs, ctx);
+    }
+    setter.set_outputs(outputs, ctx)
+}
+
+/// 当前的显示器列表：[setter] monitors 配置优先，否则由设置后端检测
+pub fn monitors(config: &AppConfig) -> Result<Vec<Monitor>, Box<dyn std::error::Error>> {
+    if !config.setter.monitors.is_empty() {
+        return Ok(config.setter.monitors.clone());
+    }
+    Ok(build(&config.setter)?.monitors())
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::screen::{StubEnv, STUB_ENV};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// 临时目录中的假命令：每次调用把 "程序名 参数..." 追加到 log
+    ///
+    /// 存在期间当前线程只会执行该目录中的程序，PATH 只包含该目录，其他环境变量都视为未设置；
+    /// 进程环境不受影响，测试之间不需要加锁
+    pub(crate) struct Stubs {
+        dir: PathBuf,
+    }
+
+    impl Stubs {
+        pub(crate) fn new(programs: &[&str]) -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let dir = std::env::temp_dir().join(format!(
+                "wallow-setter-test-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            let vars = [("PATH".to_string(), dir.clone().into_os_string())].into();
+            STUB_ENV.set(Some(StubEnv { dir: dir.clone(), vars }));
+            let stubs = Self { dir };
+            for program in programs {
+                stubs.script(program, "");
+            }
+            stubs
+        }
+
+        /// 写入名为 program 的假命令，记录参数后执行 body（如 echo 输出）
+        pub(crate) fn script(&self, program: &str, body: &str) {
+            use std::os::unix::fs::PermissionsExt;
+            let path = self.dir.join(program);
+            let log = self.dir.join("log");
+            let script = format!("#!/bin/sh\nprintf '%s\\n' \"${{0##*/}} $*\" >> '{}'\n{}\n", log.display(), body);
+            std::fs::write(&path, script).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+
+        pub(crate) fn set_env(&self, key: &str, value: &str) {
+            STUB_ENV.with_borrow_mut(|env| {
+                if let Some(env) = env {
+                    env.vars.insert(key.to_string(), value.into());
+                }
+            });
+        }
+
+        /// 按顺序记录的调用
+        pub(crate) fn calls(&self) -> Vec<String> {
+            std::fs::read_to_string(self.dir.join("log"))
+                .unwrap_or_default()
+                .lines()
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    impl Drop for Stubs {
+        fn drop(&mut self) {
+            STUB_ENV.set(None);
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn in_path_requires_executable() {
+        let stubs = Stubs::new(&["swww"]);
+        std::fs::write(stubs.dir.join("feh"), "").unwrap();
+        assert!(in_path("swww"));
+        assert!(!in_path("feh"));
+        assert!(!in_path("xwallpaper"));
+    }
+
+    /// (环境变量, PATH 中的程序, 期望的后端)
+    type DetectCase = (&'static [(&'static str, &'static str)], &'static [&'static str], SetterBackend);
+
+    #[test]
+    fn detect_from_environment() {
+        let cases: &[DetectCase] = &[
+            (&[("XDG_CURRENT_DESKTOP", "Hyprland")], &["swww", "hyprctl", "hyprpaper"], SetterBackend::Swww),
+            (&[("XDG_CURRENT_DESKTOP", "Hyprland")], &["hyprctl", "hyprpaper"], SetterBackend::Hyprpaper),
+            (&[("HYPRLAND_INSTANCE_SIGNATURE", "abc"), ("WAYLAND_DISPLAY", "wayland-1")], &["swaybg"], SetterBackend::Swaybg),
+            (&[("XDG_CURRENT_DESKTOP", "sway"), ("WAYLAND_DISPLAY", "wayland-1")], &["swaymsg", "swww"], SetterBackend::Swaybg),
+            (&[("XDG_CURRENT_DESKTOP", "ubuntu:GNOME"), ("WAYLAND_DISPLAY", "wayland-0")], &["swww"], SetterBackend::System),
+            (&[("XDG_CURRENT_DESKTOP", "KDE"), ("DISPLAY", ":0")], &["feh"], SetterBackend::System),
+            (&[("WAYLAND_DISPLAY", "wayland-1")], &["swww", "swaybg"], SetterBackend::Swww),
+            (&[("WAYLAND_DISPLAY", "wayland-1")], &["swaybg", "feh"], SetterBackend::Swaybg),
+            (&[("DISPLAY", ":0")], &["nitrogen", "xwallpaper", "feh"], SetterBackend::Feh),
+            (&[("DISPLAY", ":0")], &["nitrogen", "xwallpaper"], SetterBackend::Xwallpaper),
+            (&[("DISPLAY", ":0")], &["nitrogen"], SetterBackend::Nitrogen),
+            (&[("DISPLAY", ":0")], &[], SetterBackend::System),
+            (&[], &["swww", "feh"], SetterBackend::System),
+        ];
+        for (env, programs, expected) in cases {
+            let stubs = Stubs::new(programs);
+            for (key, value) in *env {
+                stubs.set_env(key, value);
+            }
+            assert_eq!(detect(), *expected, "{:?} {:?}", env, programs);
+        }
+    }
+}
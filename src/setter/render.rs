@@ -0,0 +1,121 @@
+// setter/render.rs — 按显示器渲染壁纸
+// 拼接模式下把一张图片切成每个显示器各自的部分，以及为不支持某种缩放模式的后端预先渲染画布
+
+use crate::config::{AppConfig, WallpaperMode};
+use crate::{current, recolor, screen};
+use image::DynamicImage;
+use rust_i18n::t;
+use std::path::{Path, PathBuf};
+
+/// 把一张图片铺满所有显示器组成的整体区域，再按每个显示器的位置裁出各自的部分
+///
+/// 布局按逻辑坐标计算（见 [`screen::Monitor::canvas_rect`]），每张切片再缩放到显示器的分辨率。
+/// 切片保存在 state_dir/span/<显示器>-<时间戳>，返回 (切片路径, 显示器名)
+pub fn span_slices(
+    config: &AppConfig,
+    src: &Path,
+    monitors: &[screen::Monitor],
+) -> Result<Vec<(PathBuf, String)>, Box<dyn std::error::Error>> {
+    let img = image::open(src).map_err(|e| t!("error_pipeline_failed", step => "span", reason => e.to_string()))?;
+    let slices = slice(&img, monitors).ok_or(t!("error_no_monitors"))?;
+
+    let dir = config.state_dir.join("span");
+    std::fs::create_dir_all(&dir)?;
+    // 清掉旧切片，但保留各显示器正在显示的那一张，供过渡动画作为起始画面；
+    // 文件名带时间戳，新切片不会覆盖它们
+    let shown: Vec<PathBuf> = monitors
+        .iter()
+        .filter_map(|m| std::fs::canonicalize(current::monitor_link_path(config, &m.name)).ok())
+        .collect();
+    for entry in std::fs::read_dir(&dir)?.flatten() {
+        if !std::fs::canonicalize(entry.path()).is_ok_and(|p| shown.contains(&p)) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+    let stamp = chrono::Local::now().timestamp_millis();
+    let ext = src.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    monitors
+        .iter()
+        .zip(slices)
+        .map(|(m, slice)| {
+            let dest = dir.join(format!("{}-{}.{}", m.name, stamp, ext));
+            recolor::save_image(slice, &dest)?;
+            Ok((dest, m.name.clone()))
+        })
+        .collect()
+}
+
+/// 把图片铺满整个画布后按显示器切开，切片尺寸为各显示器的分辨率；没有显示器时返回 None
+fn slice(img: &DynamicImage, monitors: &[screen::Monitor]) -> Option<Vec<DynamicImage>> {
+    use image::imageops::FilterType;
+
+    let (min_x, min_y, width, height) = screen::bounding_box(monitors)?;
+    let canvas = img.resize_to_fill(width, height, FilterType::Lanczos3);
+    let scale = screen::canvas_scale(monitors);
+    let slices = monitors
+        .iter()
+        .map(|m| {
+            let (x, y, w, h) = m.canvas_rect(scale);
+            let slice = canvas.crop_imm((x - min_x) as u32, (y - min_y) as u32, w, h);
+            if (w, h) == (m.width, m.height) {
+                slice
+            } else {
+                slice.resize_exact(m.width, m.height, FilterType::Lanczos3)
+            }
+        })
+        .collect();
+    Some(slices)
+}
+
+/// 按缩放模式把图片渲染成 width x height 的画布，空白处为黑色
+///
+/// 供不支持该模式的设置后端使用，渲染结果再以 crop 模式设置时效果与原生模式一致
+pub fn render_mode(img: DynamicImage, mode: WallpaperMode, width: u32, height: u32) -> DynamicImage {
+    use image::imageops::{self, FilterType};
+
+    let mut canvas = image::RgbaImage::from_pixel(width, height, image::Rgba([0, 0, 0, 255]));
+    // 把 img 居中贴到画布上，超出画布的部分被裁掉
+    let mut center = |img: &DynamicImage| {
+        let x = (width as i64 - img.width() as i64) / 2;
+        let y = (height as i64 - img.height() as i64) / 2;
+        imageops::overlay(&mut canvas, &img.to_rgba8(), x, y);
+    };
+    match mode {
+        WallpaperMode::Crop | WallpaperMode::Span => return img.resize_to_fill(width, height, FilterType::Lanczos3),
+        WallpaperMode::Stretch => return img.resize_exact(width, height, FilterType::Lanczos3),
+        WallpaperMode::Fit => center(&img.resize(width, height, FilterType::Lanczos3)),
+        WallpaperMode::Center => center(&img),
+        WallpaperMode::Tile => imageops::tile(&mut canvas, &img.to_rgba8()),
+    }
+    DynamicImage::ImageRgba8(canvas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::screen::Monitor;
+
+    #[test]
+    fn span_scaled_outputs() {
+        // 左右两半颜色不同的图片，切开后每个显示器只能看到一种颜色
+        let (red, blue) = (image::Rgb([255, 0, 0]), image::Rgb([0, 0, 255]));
+        let img = DynamicImage::ImageRgb8(image::RgbImage::from_fn(640, 160, |x, _| if x < 320 { red } else { blue }));
+        // 缩放 2 的 256x160 屏幕逻辑宽度 128，右侧缩放 1 的屏幕紧挨着它
+        let monitors = [
+            Monitor { name: "eDP-1".to_string(), width: 256, height: 160, x: 0, y: 0, scale: 2.0 },
+            Monitor { name: "DP-1".to_string(), width: 128, height: 80, x: 128, y: 0, scale: 1.0 },
+        ];
+        let slices = slice(&img, &monitors).unwrap();
+        assert_eq!((slices[0].width(), slices[0].height()), (256, 160));
+        assert_eq!((slices[1].width(), slices[1].height()), (128, 80));
+
+        // 画布上的区域首尾相接，没有重叠，拼起来正好是整个外接矩形
+        let scale = screen::canvas_scale(&monitors);
+        let (left, right) = (monitors[0].canvas_rect(scale), monitors[1].canvas_rect(scale));
+        assert_eq!(left.0 + left.2 as i32, right.0);
+        assert_eq!(screen::bounding_box(&monitors), Some((0, 0, left.2 + right.2, 160)));
+        let (a, b) = (slices[0].to_rgb8(), slices[1].to_rgb8());
+        assert_eq!(a.get_pixel(250, 80), &red);
+        assert_eq!(b.get_pixel(5, 40), &blue);
+    }
+}
@@ -0,0 +1,181 @@
+// setter/transition.rs — 交叉淡入淡出
+// 后端自身没有过渡动画时，把旧壁纸和新壁纸按屏幕尺寸渲染后逐帧混合，
+// 保存到 state_dir/transition/ 并在 [setter.transition] duration 秒内依次设置，最后再由调用方设置新壁纸；
+// 多显示器时每个显示器各自混合，逐帧一起设置
+
+use super::{SetContext, WallpaperSetter};
+use crate::config::{AppConfig, WallpaperMode};
+use crate::{current, recolor};
+use image::{DynamicImage, RgbImage};
+use rust_i18n::t;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 当前显示的壁纸：指定显示器时优先使用该显示器的链接
+fn previous(config: &AppConfig, monitor: Option<&str>) -> Option<PathBuf> {
+    monitor
+        .and_then(|m| fs::canonicalize(current::monitor_link_path(config, m)).ok())
+        .or_else(|| fs::canonicalize(current::link_path(config)).ok())
+}
+
+/// 按比例 alpha（0 为 from，1 为 to）混合两张同尺寸的图片
+fn blend(from: &RgbImage, to: &RgbImage, alpha: f32) -> RgbImage {
+    let mut frame = to.clone();
+    for (out, (a, b)) in frame.pixels_mut().zip(from.pixels().zip(to.pixels())) {
+        for channel in 0..3 {
+            out[channel] = (a[channel] as f32 + (b[channel] as f32 - a[channel] as f32) * alpha).round() as u8;
+        }
+    }
+    frame
+}
+
+/// 是否开启了过渡
+fn enabled(config: &AppConfig) -> bool {
+    let settings = &config.setter.transition;
+    settings.duration > 0.0 && settings.steps > 0
+}
+
+/// 显示器上当前的壁纸，没有旧壁纸或与 next 相同时返回 None
+fn changed(config: &AppConfig, next: &Path, monitor: Option<&str>) -> Option<PathBuf> {
+    let previous = previous(config, monitor)?;
+    if fs::canonicalize(next).is_ok_and(|next| next == previous) {
+        return None;
+    }
+    Some(previous)
+}
+
+/// 从当前壁纸淡入到 next，结束时停在最后一张中间帧，由调用方设置 next 本身
+///
+/// 过渡只是视觉效果：未开启或没有旧壁纸时直接跳过，图片无法读取或设置中间帧失败时打印警告后跳过
+pub fn crossfade(config: &AppConfig, setter: &dyn WallpaperSetter, next: &Path, ctx: &SetContext) {
+    if !enabled(config) {
+        return;
+    }
+    let Some(previous) = changed(config, next, ctx.monitor.as_deref()) else { return };
+    // 中间帧已是屏幕尺寸，按 crop 设置
+    let frame_ctx = SetContext { mode: WallpaperMode::Crop, frame: true, ..ctx.clone() };
+    if let Err(e) = frames(config, &previous, next, ctx)
+        .and_then(|frames| play(config, &frames, |frame| setter.set(frame, &frame_ctx)))
+    {
+        warn(e);
+    }
+}
+
+/// 多个显示器同时淡入各自的新壁纸，outputs 为 (图片, 显示器名)，每一步用 set_outputs 一次设置全部显示器
+///
+/// 没有旧壁纸或壁纸未变化的显示器每一帧都使用新图；所有显示器都没有变化时跳过
+pub fn crossfade_outputs(config: &AppConfig, setter: &dyn WallpaperSetter, outputs: &[(PathBuf, String)], ctx: &SetContext) {
+    if !enabled(config) {
+        return;
+    }
+    let steps = config.setter.transition.steps as usize;
+    let mut per_output = Vec::new();
+    let mut any = false;
+    for (next, monitor) in outputs {
+        let output_ctx = SetContext { monitor: Some(monitor.clone()), ..ctx.clone() };
+        let output_frames = match changed(config, next, Some(monitor)) {
+            Some(previous) => match frames(config, &previous, next, &output_ctx) {
+                Ok(frames) => frames,
+                Err(e) => return warn(e),
+            },
+            None => vec![next.clone(); steps],
+        };
+        any |= output_frames.first() != Some(next);
+        per_output.push(output_frames);
+    }
+    if !any {
+        return;
+    }
+    // 按步骤转置成每一帧的 (图片, 显示器名)
+    let frames: Vec<Vec<(PathBuf, String)>> = (0..steps)
+        .map(|step| per_output.iter().zip(outputs).map(|(frames, (_, monitor))| (frames[step].clone(), monitor.clone())).collect())
+        .collect();
+    let frame_ctx = SetContext { mode: WallpaperMode::Crop, frame: true, ..ctx.clone() };
+    if let Err(e) = play(config, &frames, |frame| setter.set_outputs(frame, &frame_ctx)) {
+        warn(e);
+    }
+}
+
+fn warn(e: Box<dyn std::error::Error>) {
+    eprintln!("{}", t!("warn_transition_failed", reason => e.to_string()));
+}
+
+/// 是否是 name 显示器的中间帧：`{name}-<时间戳>-<序号>.jpg`
+///
+/// 只按前缀匹配会把 DP-1-1 的帧当成 DP-1 的帧
+fn is_frame(file_name: &str, name: &str) -> bool {
+    let numeric = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    Path::new(file_name)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .and_then(|s| s.strip_prefix(&format!("{}-", name)))
+        .and_then(|rest| rest.split_once('-'))
+        .is_some_and(|(stamp, step)| numeric(stamp) && numeric(step))
+}
+
+/// 生成从 previous 到 next 的中间帧，按 ctx 的显示器尺寸和缩放模式渲染，返回帧路径
+fn frames(config: &AppConfig, previous: &Path, next: &Path, ctx: &SetContext) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let next_img = image::open(next)?;
+    // 两张图都按目标屏幕尺寸渲染成相同大小，检测不到屏幕时以新图的尺寸为准
+    let (width, height) = super::target_size(config, ctx.monitor.as_deref())?
+        .unwrap_or((next_img.width(), next_img.height()));
+    let render = |img: DynamicImage| super::render::render_mode(img, ctx.mode, width, height).to_rgb8();
+    let from = render(image::open(previous)?);
+    let to = render(next_img);
+
+    // 先生成所有中间帧，设置时只剩调用后端的开销
+    let dir = config.state_dir.join("transition");
+    fs::create_dir_all(&dir)?;
+    let name = ctx.monitor.as_deref().unwrap_or("all");
+    // 清掉上一次的帧；文件名带时间戳，避免按路径缓存图片的桌面环境（如 GNOME）不刷新
+    for entry in fs::read_dir(&dir)?.flatten() {
+        if is_frame(&entry.file_name().to_string_lossy(), name) {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+    let stamp = chrono::Local::now().timestamp_millis();
+    let steps = config.setter.transition.steps;
+    let mut frames = Vec::new();
+    for step in 1..=steps {
+        let path = dir.join(format!("{}-{}-{}.jpg", name, stamp, step));
+        let alpha = step as f32 / (steps + 1) as f32;
+        recolor::save_image(DynamicImage::ImageRgb8(blend(&from, &to, alpha)), &path)?;
+        frames.push(path);
+    }
+    Ok(frames)
+}
+
+/// 在 [setter.transition] duration 秒内依次设置每一帧；最后一步由调用方设置新图，总共 steps + 1 个间隔
+fn play<T>(
+    config: &AppConfig,
+    frames: &[T],
+    mut set: impl FnMut(&T) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let settings = &config.setter.transition;
+    let interval = Duration::from_secs_f64(settings.duration / (settings.steps + 1) as f64);
+    let start = Instant::now();
+    for (i, frame) in frames.iter().enumerate() {
+        set(frame)?;
+        // 按开始时间对齐，后端较慢时不再额外等待
+        if let Some(wait) = (interval * (i as u32 + 1)).checked_sub(start.elapsed()) {
+            std::thread::sleep(wait);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_of_prefixed_outputs() {
+        assert!(is_frame("DP-1-1700000000000-1.jpg", "DP-1"));
+        assert!(!is_frame("DP-1-1-1700000000000-1.jpg", "DP-1"));
+        assert!(is_frame("DP-1-1-1700000000000-1.jpg", "DP-1-1"));
+        assert!(!is_frame("DP-1-1700000000000-1.jpg", "DP-1-1"));
+        assert!(!is_frame("DP-1-1700000000000.jpg", "DP-1"));
+        assert!(!is_frame("all-1700000000000-1.jpg", "DP-1"));
+    }
+}
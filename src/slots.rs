@@ -0,0 +1,232 @@
+// slots.rs — 定时任务的时间段模块
+// 根据 [schedule.slots] 计算当天各时段的开始时间（固定时刻或按日出日落偏移），
+// 定时任务每次触发时选择当前所在的时段
+
+use crate::config::{ScheduleConfig, TimeSlot};
+use crate::solar;
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveTime, TimeZone};
+use rand::seq::SliceRandom;
+use rust_i18n::t;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 内置时段名称未配置 start 时的默认开始时间：(名称, 固定时刻, 配置了经纬度时的日出日落时刻)
+const DEFAULT_STARTS: &[(&str, &str, &str)] = &[
+    ("morning", "06:00", "sunrise"),
+    ("day", "10:00", "sunrise+3h"),
+    ("evening", "17:00", "sunset-1h"),
+    ("night", "21:00", "sunset+1h"),
+];
+
+/// 极昼 / 极夜时没有日出日落，退回到的固定时刻
+const FALLBACK_SUNRISE: &str = "06:00";
+const FALLBACK_SUNSET: &str = "18:00";
+
+/// 某天某个时段的开始时间
+#[derive(Debug, Clone)]
+pub struct SlotTime<'a> {
+    pub name: &'a str,
+    pub slot: &'a TimeSlot,
+    pub start: DateTime<Local>,
+}
+
+/// 计算某天所有时段的开始时间，按时间先后排序
+pub fn boundaries(schedule: &ScheduleConfig, date: NaiveDate) -> Result<Vec<SlotTime<'_>>, Box<dyn std::error::Error>> {
+    let location = schedule.latitude.zip(schedule.longitude);
+    // 日出日落时间（本地时区），未配置经纬度时为 None
+    let sun = location.map(|(lat, lon)| {
+        solar::sun_times(date, lat, lon)
+            .map(|(rise, set)| (rise.with_timezone(&Local), set.with_timezone(&Local)))
+            .or_else(|| Some((at(date, parse_time(FALLBACK_SUNRISE)?)?, at(date, parse_time(FALLBACK_SUNSET)?)?)))
+    });
+
+    let mut times = schedule
+        .slots
+        .iter()
+        .map(|(name, slot)| {
+            let spec = match &slot.start {
+                Some(start) => start.as_str(),
+                None => {
+                    let (_, fixed, solar) = DEFAULT_STARTS
+                        .iter()
+                        .find(|(default, _, _)| default == name)
+                        .ok_or_else(|| t!("error_slot_no_start", name => name))?;
+                    if location.is_some() { solar } else { fixed }
+                }
+            };
+            let start = resolve(spec, date, sun.flatten())
+                .ok_or_else(|| t!("error_slot_start", name => name, value => spec))?;
+            Ok(SlotTime { name, slot, start })
+        })
+        .collect::<Result<Vec<_>, Box<dyn std::error::Error>>>()?;
+    times.sort_by_key(|slot| slot.start);
+    Ok(times)
+}
+
+/// 当前所在的时段：开始时间不晚于 now 的最后一个时段；
+/// 早于当天第一个时段时属于前一天的最后一个时段。没有配置时段时返回 None
+pub fn active(schedule: &ScheduleConfig, now: DateTime<Local>) -> Result<Option<SlotTime<'_>>, Box<dyn std::error::Error>> {
+    if schedule.slots.is_empty() {
+        return Ok(None);
+    }
+    let today = now.date_naive();
+    if let Some(slot) = boundaries(schedule, today)?.into_iter().rev().find(|slot| slot.start <= now) {
+        return Ok(Some(slot));
+    }
+    let yesterday = today.pred_opt().ok_or_else(|| t!("error_slot_date", date => today))?;
+    Ok(boundaries(schedule, yesterday)?.pop())
+}
+
+/// 解析开始时间："HH:MM"、"sunrise"、"sunset"，后面可以跟 "+1h30m"、"-45m" 形式的偏移
+fn resolve(spec: &str, date: NaiveDate, sun: Option<(DateTime<Local>, DateTime<Local>)>) -> Option<DateTime<Local>> {
+    let spec = spec.trim();
+    let (base, offset) = match spec.find(['+', '-']) {
+        Some(pos) => {
+            let sign = if spec[pos..].starts_with('-') { -1 } else { 1 };
+            (&spec[..pos], parse_offset(&spec[pos + 1..])? * sign)
+        }
+        None => (spec, Duration::zero()),
+    };
+    let base = match base.trim() {
+        "sunrise" => sun?.0,
+        "sunset" => sun?.1,
+        time => at(date, parse_time(time)?)?,
+    };
+    Some(base + offset)
+}
+
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+}
+
+/// 本地时区中某天的某个时刻，夏令时跳过的时刻不存在时返回 None
+fn at(date: NaiveDate, time: NaiveTime) -> Option<DateTime<Local>> {
+    Local.from_local_datetime(&date.and_time(time)).earliest()
+}
+
+/// 解析 "1h30m"、"45m"、"2h" 形式的时长
+fn parse_offset(s: &str) -> Option<Duration> {
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in s.trim().chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' if !number.is_empty() => {
+                let value: i64 = number.parse().ok()?;
+                total += if c == 'h' { Duration::hours(value) } else { Duration::minutes(value) };
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    number.is_empty().then_some(total)
+}
+
+/// 上一次定时任务所在的时段，保存在 state_dir/slot，内容为 "<名称> <开始时间>"
+pub fn last_run(state_dir: &Path) -> Option<String> {
+    fs::read_to_string(state_dir.join("slot")).ok().map(|s| s.trim().to_string())
+}
+
+/// 记录本次定时任务所在的时段
+pub fn record_run(state_dir: &Path, slot: &SlotTime) -> std::io::Result<()> {
+    fs::write(state_dir.join("slot"), slot_key(slot))
+}
+
+/// 同一时段在不同日期的出现视为不同的时段
+pub fn slot_key(slot: &SlotTime) -> String {
+    format!("{} {}", slot.name, slot.start.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
+/// 目录中的图片，按文件名排序
+pub fn folder_images(folder: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut images: Vec<PathBuf> = fs::read_dir(folder)
+        .map_err(|e| format!("{}: {}", folder.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|ext| matches!(ext.to_lowercase().as_str(), "jpg" | "jpeg" | "png" | "webp"))
+        })
+        .collect();
+    images.sort();
+    Ok(images)
+}
+
+/// 从时段的本地目录中随机选择一张图片，目录中有多张时避开当前壁纸
+pub fn pick_image(folder: &Path, current: Option<&Path>) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let images = folder_images(folder)?;
+    let candidates: Vec<&PathBuf> = match images.len() {
+        0 => return Err(t!("error_slot_folder_empty", path => folder.display()).into()),
+        1 => images.iter().collect(),
+        _ => images.iter().filter(|path| Some(path.as_path()) != current).collect(),
+    };
+    Ok(candidates.choose(&mut rand::thread_rng()).copied().unwrap_or(&images[0]).clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(toml: &str) -> ScheduleConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn local(date: NaiveDate, time: &str) -> DateTime<Local> {
+        at(date, parse_time(time).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse_offset("1h30m"), Some(Duration::minutes(90)));
+        assert_eq!(parse_offset("45m"), Some(Duration::minutes(45)));
+        assert_eq!(parse_offset("2h"), Some(Duration::hours(2)));
+        assert_eq!(parse_offset("2"), None);
+        assert_eq!(parse_offset("h"), None);
+        assert_eq!(parse_offset("1d"), None);
+    }
+
+    #[test]
+    fn resolve_specs() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let sun = Some((local(date, "07:10"), local(date, "16:50")));
+        assert_eq!(resolve("07:30", date, None), Some(local(date, "07:30")));
+        assert_eq!(resolve("sunset-1h", date, sun), Some(local(date, "15:50")));
+        assert_eq!(resolve("sunrise+1h30m", date, sun), Some(local(date, "08:40")));
+        assert_eq!(resolve(" sunset - 45m ", date, sun), Some(local(date, "16:05")));
+        // 没有经纬度时不能使用 sunrise / sunset
+        assert_eq!(resolve("sunrise", date, None), None);
+        assert_eq!(resolve("25:00", date, None), None);
+    }
+
+    #[test]
+    fn active_wraps_around_midnight() {
+        let schedule = schedule(
+            r#"
+            [slots.morning]
+            [slots.night]
+            [slots.late]
+            start = "23:30"
+            "#,
+        );
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let name = |time: &str| active(&schedule, local(date, time)).unwrap().map(|slot| slot.name);
+        assert_eq!(name("05:59"), Some("late"));
+        assert_eq!(name("06:00"), Some("morning"));
+        assert_eq!(name("20:59"), Some("morning"));
+        assert_eq!(name("21:00"), Some("night"));
+        assert_eq!(name("23:45"), Some("late"));
+        // 凌晨属于前一天最后一个时段，开始时间也是前一天的
+        let slot = active(&schedule, local(date, "01:00")).unwrap().unwrap();
+        assert_eq!(slot.start, local(date.pred_opt().unwrap(), "23:30"));
+        assert!(active(&ScheduleConfig::default(), local(date, "01:00")).unwrap().is_none());
+    }
+
+    #[test]
+    fn custom_slot_needs_start() {
+        let schedule = schedule("[slots.lunch]\nquery = \"food\"\n");
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        assert!(boundaries(&schedule, date).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+// solar.rs — 日出日落计算模块
+// 按 NOAA 简化的日出方程离线计算指定日期和经纬度的日出、日落时间，不依赖网络服务
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+/// 儒略日 2000-01-01 12:00 UTC
+const J2000: f64 = 2451545.0;
+/// Unix 纪元对应的儒略日
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+
+/// 计算某天的日出和日落时间 (UTC)
+///
+/// 太阳高度角取 -0.833°（考虑大气折射和日轮半径）；
+/// 极昼或极夜时太阳不会穿过地平线，返回 None
+pub fn sun_times(date: NaiveDate, latitude: f64, longitude: f64) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let days_since_epoch = date.signed_duration_since(NaiveDate::from_ymd_opt(1970, 1, 1)?).num_days() as f64;
+    // 当天 UTC 正午的儒略日
+    let jd = days_since_epoch + UNIX_EPOCH_JD + 0.5;
+    let n = (jd - J2000 + 0.0008).round();
+
+    // 平太阳正午
+    let j_star = n - longitude / 360.0;
+    // 太阳平近点角
+    let m = (357.5291 + 0.98560028 * j_star).rem_euclid(360.0).to_radians();
+    // 中心差
+    let c = 1.9148 * m.sin() + 0.0200 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    // 黄经
+    let lambda = (m.to_degrees() + c + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    // 太阳过中天
+    let transit = J2000 + j_star + 0.0053 * m.sin() - 0.0069 * (2.0 * lambda).sin();
+    // 赤纬
+    let sin_decl = lambda.sin() * 23.4397_f64.to_radians().sin();
+    let cos_decl = sin_decl.asin().cos();
+    // 时角
+    let phi = latitude.to_radians();
+    let cos_omega = ((-0.833_f64).to_radians().sin() - phi.sin() * sin_decl) / (phi.cos() * cos_decl);
+    if !(-1.0..=1.0).contains(&cos_omega) {
+        return None;
+    }
+    let omega = cos_omega.acos().to_degrees();
+
+    let to_utc = |julian: f64| Utc.timestamp_opt(((julian - UNIX_EPOCH_JD) * 86400.0).round() as i64, 0).single();
+    Some((to_utc(transit - omega / 360.0)?, to_utc(transit + omega / 360.0)?))
+}
+
+/// 计算某一时刻的太阳高度角和方位角（度，方位角自正北顺时针）
+pub fn sun_position(at: DateTime<Utc>, latitude: f64, longitude: f64) -> (f64, f64) {
+    let d = at.timestamp() as f64 / 86400.0 + UNIX_EPOCH_JD - J2000;
+    // 太阳平近点角和黄经
+    let m = (357.5291 + 0.98560028 * d).rem_euclid(360.0).to_radians();
+    let c = 1.9148 * m.sin() + 0.0200 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let lambda = (m.to_degrees() + c + 180.0 + 102.9372).rem_euclid(360.0).to_radians();
+    // 赤经、赤纬
+    let epsilon = 23.4397_f64.to_radians();
+    let ra = (lambda.sin() * epsilon.cos()).atan2(lambda.cos());
+    let decl = (lambda.sin() * epsilon.sin()).asin();
+    // 地方恒星时与时角
+    let sidereal = (280.16 + 360.9856235 * d + longitude).to_radians();
+    let h = sidereal - ra;
+    let phi = latitude.to_radians();
+    let altitude = (phi.sin() * decl.sin() + phi.cos() * decl.cos() * h.cos()).asin();
+    // 方位角先按自正南起算，再换成自正北起算
+    let azimuth = h.sin().atan2(h.cos() * phi.sin() - decl.tan() * phi.cos());
+    (altitude.to_degrees(), (azimuth.to_degrees() + 180.0).rem_euclid(360.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 与 NOAA Solar Calculator 的结果相差不超过 2 分钟
+    fn assert_near(actual: DateTime<Utc>, expected: &str) {
+        let expected = DateTime::parse_from_rfc3339(expected).unwrap();
+        let diff = (actual - expected.with_timezone(&Utc)).num_seconds().abs();
+        assert!(diff <= 120, "{} vs {}", actual, expected);
+    }
+
+    #[test]
+    fn matches_noaa() {
+        // 伦敦夏至：日出 04:43 BST，日落 21:21 BST
+        let (rise, set) = sun_times(NaiveDate::from_ymd_opt(2024, 6, 21).unwrap(), 51.5074, -0.1278).unwrap();
+        assert_near(rise, "2024-06-21T03:43:00Z");
+        assert_near(set, "2024-06-21T20:21:40Z");
+        // 纽约冬至：日出 07:17 EST，日落 16:32 EST
+        let (rise, set) = sun_times(NaiveDate::from_ymd_opt(2024, 12, 21).unwrap(), 40.7128, -74.0060).unwrap();
+        assert_near(rise, "2024-12-21T12:16:50Z");
+        assert_near(set, "2024-12-21T21:32:00Z");
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        // 特罗姆瑟夏至极昼、冬至极夜
+        assert!(sun_times(NaiveDate::from_ymd_opt(2024, 6, 21).unwrap(), 69.65, 18.96).is_none());
+        assert!(sun_times(NaiveDate::from_ymd_opt(2024, 12, 21).unwrap(), 69.65, 18.96).is_none());
+    }
+
+    #[test]
+    fn position_at_noon() {
+        // 春分正午前后赤道上的太阳接近天顶
+        let (altitude, _) = sun_position(DateTime::parse_from_rfc3339("2024-03-20T12:07:00Z").unwrap().with_timezone(&Utc), 0.0, 0.0);
+        assert!(altitude > 88.0, "{}", altitude);
+        // 午夜在地平线以下
+        let (altitude, _) = sun_position(DateTime::parse_from_rfc3339("2024-03-20T00:00:00Z").unwrap().with_timezone(&Utc), 51.5, 0.0);
+        assert!(altitude < 0.0, "{}", altitude);
+    }
+}
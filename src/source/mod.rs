@@ -19,19 +19,22 @@ pub struct WallpaperInfo {
     /// 分辨率描述
     pub resolution: String,
     /// 来源站名称（如 "wallhaven"）
-    #[allow(dead_code)]
-    #[allow(dead_code)]
     pub source: String,
     /// 来源特定的附加数据（如 Unsplash 的 download_location）
     #[allow(dead_code)]
     pub extra: Option<String>,
+    /// 收藏数 / 点赞数（Wallhaven 的 favorites，Unsplash 的 likes），来源不提供时为 None
+    pub favorites: Option<u64>,
 }
 
 /// 搜索参数结构体
 /// 抽象了通用的搜索需求
 pub struct SearchOptions<'a> {
     pub query: Option<&'a str>,
+    /// "WxH" 形式的分辨率，为空表示不限制
     pub resolution: &'a str,
+    /// 为 true 时 resolution 表示最低分辨率，否则要求精确匹配
+    pub atleast: bool,
     pub categories: &'a str,
     pub purity: &'a str,
     pub sorting: &'a str,
@@ -50,6 +53,20 @@ pub trait WallpaperSource {
     async fn search(&self, options: SearchOptions<'_>) -> Result<Vec<WallpaperInfo>, Box<dyn std::error::Error>>;
 
     /// 下载壁纸
-    /// 接收一个 WallpaperInfo 和保存目录，返回保存后的完整路径
-    async fn download(&self, info: &WallpaperInfo, save_dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>>;
+    /// 接收一个 WallpaperInfo、保存目录和文件名（不含扩展名），返回保存后的完整路径
+    /// 扩展名由壁纸源根据图片格式决定
+    async fn download(&self, info: &WallpaperInfo, save_dir: &Path, file_stem: &str) -> Result<PathBuf, Box<dyn std::error::Error>>;
+}
+
+/// 解析 "WxH" 格式的分辨率字符串，返回 (width, height)
+/// 解析失败时返回 (0, 0)
+pub fn parse_resolution(resolution: &str) -> (u32, u32) {
+    let parts: Vec<&str> = resolution.splitn(2, 'x').collect();
+    if parts.len() == 2 {
+        let w = parts[0].parse::<u32>().unwrap_or(0);
+        let h = parts[1].parse::<u32>().unwrap_or(0);
+        (w, h)
+    } else {
+        (0, 0)
+    }
 }
@@ -4,7 +4,7 @@
 // 注意：根据 Unsplash API Guidelines，每次实际下载图片前
 // 必须先调用 links.download_location 触发下载统计。
 
-use super::{SearchOptions, WallpaperInfo, WallpaperSource};
+use super::{SearchOptions, WallpaperInfo, WallpaperSource, parse_resolution};
 use async_trait::async_trait;
 use serde::Deserialize; // 反序列化 trait，用于将 JSON 转为 Rust 结构体
 use std::path::{Path, PathBuf};
@@ -31,6 +31,10 @@ pub struct Photo {
     /// 图片高度（像素）
     pub height: u32,
 
+    /// 点赞数
+    #[serde(default)]
+    pub likes: u64,
+
     /// 各尺寸图片 URL 集合
     pub urls: PhotoUrls,
 
@@ -66,6 +70,26 @@ struct DownloadResponse {
     url: String,
 }
 
+/// search() 在 raw URL 后追加的尺寸和格式参数
+const SIZE_PARAMS: [&str; 6] = ["w", "h", "fit", "cs", "fm", "q"];
+
+/// 把 info_url 中的尺寸和格式参数转移到 download_location 返回的下载 URL 上
+///
+/// 下载 URL 只带签名参数，直接请求会得到原始尺寸的图片；两边都有的参数以 info_url 为准
+fn with_size_params(download_url: &str, info_url: &str) -> String {
+    let is_size_param = |param: &&str| SIZE_PARAMS.contains(&param.split('=').next().unwrap_or_default());
+    let (base, query) = download_url.split_once('?').unwrap_or((download_url, ""));
+    let mut params: Vec<&str> = query.split('&').filter(|p| !p.is_empty() && !is_size_param(p)).collect();
+    if let Some((_, info_query)) = info_url.split_once('?') {
+        params.extend(info_query.split('&').filter(is_size_param));
+    }
+    if params.is_empty() {
+        base.to_string()
+    } else {
+        format!("{}?{}", base, params.join("&"))
+    }
+}
+
 /// Unsplash API 异步客户端
 ///
 /// 封装了 reqwest::Client 和 API 配置，提供搜索和下载方法。
@@ -162,6 +186,7 @@ impl WallpaperSource for UnsplashClient {
                     source: "unsplash".to_string(),
                     // 将 download_location 存入 extra，供 download() 调用统计接口
                     extra: Some(photo.links.download_location),
+                    favorites: Some(photo.likes),
                 }
             })
             .collect();
@@ -173,6 +198,7 @@ impl WallpaperSource for UnsplashClient {
         &self,
         info: &WallpaperInfo,
         save_dir: &Path,
+        file_stem: &str,
     ) -> Result<PathBuf, Box<dyn std::error::Error>> {
         // 第一步：调用 download_location 触发 Unsplash 下载统计（API Guidelines 强制要求）
         // 同时获取带签名的真实下载 URL
@@ -186,16 +212,16 @@ impl WallpaperSource for UnsplashClient {
                 .json()
                 .await?;
 
-            // 第二步：用统计接口返回的 URL 下载实际图片
+            // 第二步：用统计接口返回的 URL 下载实际图片，带上 search() 追加的尺寸和格式参数
             let bytes = self
                 .client
-                .get(&dl_response.url)
+                .get(with_size_params(&dl_response.url, &info.url))
                 .send()
                 .await?
                 .bytes()
                 .await?;
 
-            let filename = format!("wallow-unsplash-{}.jpg", info.id);
+            let filename = format!("{}.jpg", file_stem);
             let save_path = save_dir.join(filename);
 
             let mut file = File::create(&save_path).await?;
@@ -212,7 +238,7 @@ impl WallpaperSource for UnsplashClient {
                 .bytes()
                 .await?;
 
-            let filename = format!("wallow-unsplash-{}.jpg", info.id);
+            let filename = format!("{}.jpg", file_stem);
             let save_path = save_dir.join(filename);
 
             let mut file = File::create(&save_path).await?;
@@ -223,15 +249,68 @@ impl WallpaperSource for UnsplashClient {
     }
 }
 
-/// 解析 "WxH" 格式的分辨率字符串，返回 (width, height)
-/// 解析失败时返回 (0, 0)
-fn parse_resolution(resolution: &str) -> (u32, u32) {
-    let parts: Vec<&str> = resolution.splitn(2, 'x').collect();
-    if parts.len() == 2 {
-        let w = parts[0].parse::<u32>().unwrap_or(0);
-        let h = parts[1].parse::<u32>().unwrap_or(0);
-        (w, h)
-    } else {
-        (0, 0)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn size_params_move_to_download_url() {
+        let info_url = "https://images.unsplash.com/photo-1?ixid=raw&w=1920&h=1080&fit=crop&cs=srgb&fm=jpg";
+        assert_eq!(
+            with_size_params("https://images.unsplash.com/photo-1?ixid=signed&fm=png", info_url),
+            "https://images.unsplash.com/photo-1?ixid=signed&w=1920&h=1080&fit=crop&cs=srgb&fm=jpg"
+        );
+        assert_eq!(
+            with_size_params("https://images.unsplash.com/photo-1", "https://images.unsplash.com/photo-1?ixid=raw&fm=jpg&q=85"),
+            "https://images.unsplash.com/photo-1?fm=jpg&q=85"
+        );
+    }
+
+    /// download() 最终请求的图片 URL 带有尺寸参数
+    #[tokio::test]
+    async fn download_requests_sized_image() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        // 第一个请求是 download_location，返回指向本服务器的下载 URL；第二个请求是图片本身
+        let server = tokio::spawn(async move {
+            let mut request_lines = Vec::new();
+            for body in [format!("{{\"url\":\"http://{}/photo-1?ixid=signed\"}}", addr), "image".to_string()] {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let n = stream.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                request_lines.push(request.lines().next().unwrap_or_default().to_string());
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+            request_lines
+        });
+
+        let client = UnsplashClient::new("key".to_string());
+        let info = WallpaperInfo {
+            id: "1".to_string(),
+            url: "https://images.unsplash.com/photo-1?ixid=raw&w=2560&h=1440&fit=crop&cs=srgb&fm=jpg".to_string(),
+            resolution: "6000x4000".to_string(),
+            source: "unsplash".to_string(),
+            extra: Some(format!("http://{}/photos/1/download", addr)),
+            favorites: None,
+        };
+        let dir = std::env::temp_dir().join(format!("wallow-unsplash-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = client.download(&info, &dir, "photo").await.unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(path, dir.join("photo.jpg"));
+
+        let request_lines = server.await.unwrap();
+        assert_eq!(
+            request_lines[1],
+            "GET /photo-1?ixid=signed&w=2560&h=1440&fit=crop&cs=srgb&fm=jpg HTTP/1.1"
+        );
     }
 }
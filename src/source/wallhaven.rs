@@ -28,6 +28,10 @@ pub struct Wallpaper {
 
     /// 壁纸分辨率（如 "3840x2160"）
     pub resolution: String,
+
+    /// 收藏数
+    #[serde(default)]
+    pub favorites: u64,
 }
 
 /// Wallhaven API 异步客户端
@@ -53,12 +57,17 @@ impl WallpaperSource for WallhavenClient {
         let url = format!("{}/search", self.base_url);
 
         let mut params: Vec<(&str, &str)> = vec![
-            ("resolutions", options.resolution),
             ("categories", options.categories),
             ("purity", options.purity),
             ("sorting", options.sorting),
         ];
 
+        // atleast 返回不低于该分辨率的壁纸，resolutions 只返回完全相同的
+        if !options.resolution.is_empty() {
+            let key = if options.atleast { "atleast" } else { "resolutions" };
+            params.push((key, options.resolution));
+        }
+
         if let Some(q) = options.query {
             params.push(("q", q));
         }
@@ -80,6 +89,7 @@ impl WallpaperSource for WallhavenClient {
                 resolution: w.resolution,
                 source: "wallhaven".to_string(),
                 extra: None,
+                favorites: Some(w.favorites),
             })
             .collect();
 
@@ -90,13 +100,14 @@ impl WallpaperSource for WallhavenClient {
         &self,
         info: &WallpaperInfo,
         save_dir: &Path,
+        file_stem: &str,
     ) -> Result<PathBuf, Box<dyn std::error::Error>> {
         // 从 URL 中提取原始文件名以获取扩展名
         let original_filename = info.url.rsplit('/').next().unwrap_or("wallpaper.jpg");
         let extension = original_filename.rsplit('.').next().unwrap_or("jpg");
 
-        // 构建格式化的文件名: wallow-{来源}-{ID}.{扩展名}
-        let filename = format!("wallow-{}-{}.{}", info.source, info.id, extension);
+        // 文件名由调用方按 filename_template 生成，这里只追加扩展名
+        let filename = format!("{}.{}", file_stem, extension);
 
         let save_path = save_dir.join(filename);
 
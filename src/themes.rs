@@ -0,0 +1,135 @@
+// themes.rs — 用户自定义主题模块
+// 从 config.toml 的 [themes.<name>] 和 ~/.config/wallow/themes/ 下的 JSON / TOML 文件加载调色板，
+// 供内置引擎直接使用，或生成 gowall 可识别的主题文件
+
+use crate::config::{AppConfig, ConvertBackend, ThemeDef};
+use crate::gowall;
+use crate::recolor::{self, Palette};
+use rust_i18n::t;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 将主题定义解析为调色板，任何一个颜色不合法都会报错
+fn parse_def(name: &str, def: &ThemeDef) -> Result<Palette, Box<dyn std::error::Error>> {
+    let colors = def
+        .colors
+        .iter()
+        .map(|c| {
+            recolor::parse_hex(c)
+                .ok_or_else(|| t!("error_invalid_color", theme => name, color => c).to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Palette {
+        name: name.to_string(),
+        colors,
+    })
+}
+
+/// 读取单个独立调色板文件（.json 或 .toml），名称缺省时使用文件名
+fn load_file(path: &Path) -> Result<Palette, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+    let def: ThemeDef = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&content)?,
+        _ => serde_json::from_str(&content)?,
+    };
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("custom");
+    let name = def.name.clone().unwrap_or_else(|| stem.to_string());
+    parse_def(&name, &def)
+}
+
+/// 自定义主题文件目录 ~/.config/wallow/themes
+pub fn themes_dir(config: &AppConfig) -> PathBuf {
+    config.config_dir().join("themes")
+}
+
+/// 加载所有自定义主题
+///
+/// config.toml 中的定义优先；同名的独立文件会被忽略。
+/// 无法解析的文件会打印警告并跳过，不影响其他主题。
+pub fn load_custom(config: &AppConfig) -> Vec<Palette> {
+    let mut palettes = Vec::new();
+    for (name, def) in &config.themes {
+        match parse_def(name, def) {
+            Ok(p) => palettes.push(p),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    let dir = themes_dir(config);
+    if let Ok(entries) = fs::read_dir(&dir) {
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("json" | "toml")))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match load_file(&path) {
+                Ok(p) if !palettes.iter().any(|x| x.name == p.name) => palettes.push(p),
+                Ok(_) => {}
+                Err(e) => eprintln!("{}", t!("warn_theme_file", path => path.display(), reason => e.to_string())),
+            }
+        }
+    }
+    palettes
+}
+
+/// 按名称查找自定义主题
+pub fn find_custom(config: &AppConfig, name: &str) -> Option<Palette> {
+    load_custom(config).into_iter().find(|p| p.name == name)
+}
+
+/// 按名称查找内置引擎可用的主题：自定义主题优先，其次内置调色板
+pub fn find_native(config: &AppConfig, name: &str) -> Option<Palette> {
+    find_custom(config, name).or_else(|| recolor::find_builtin(name))
+}
+
+/// 将调色板写成 gowall 的自定义主题 JSON（{"name": ..., "colors": [...]}），返回文件路径
+///
+/// gowall 的 -t 参数既接受主题名也接受主题文件路径，
+/// 文件写入 state_dir/themes，每次转换前重新生成以反映最新配置
+pub fn write_gowall_theme(config: &AppConfig, palette: &Palette) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let dir = config.state_dir.join("themes");
+    fs::create_dir_all(&dir)?;
+    let colors: Vec<String> = palette
+        .colors
+        .iter()
+        .map(|[r, g, b]| format!("#{:02X}{:02X}{:02X}", r, g, b))
+        .collect();
+    let json = serde_json::json!({ "name": palette.name, "colors": colors });
+    let path = dir.join(format!("{}.json", palette.name.replace(['/', '\\'], "_")));
+    fs::write(&path, serde_json::to_string_pretty(&json)?)?;
+    Ok(path)
+}
+
+/// 按 [convert] backend 调用对应的引擎，将 image 转换为 theme 主题并写入 output
+pub fn convert(
+    config: &AppConfig,
+    image: impl AsRef<Path>,
+    theme: &str,
+    output: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match config.convert.backend {
+        ConvertBackend::Gowall => {
+            // 自定义主题先生成 gowall 主题文件，再把文件路径作为 -t 参数传入
+            match find_custom(config, theme) {
+                Some(palette) => {
+                    let theme_file = write_gowall_theme(config, &palette)?;
+                    gowall::convert(image.as_ref(), &theme_file.to_string_lossy(), Some(output))?;
+                }
+                None => {
+                    gowall::convert(image.as_ref(), theme, Some(output))?;
+                }
+            }
+        }
+        ConvertBackend::Native => {
+            let palette = find_native(config, theme)
+                .ok_or_else(|| t!("error_unknown_theme", theme => theme))?;
+            let options = recolor::RecolorOptions {
+                dither: config.convert.dither,
+                preserve_luminance: config.convert.preserve_luminance,
+            };
+            recolor::convert(image, &palette, output, options)?;
+        }
+    }
+    Ok(())
+}